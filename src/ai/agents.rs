@@ -0,0 +1,322 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use thiserror::Error;
+use crate::{Action, Outcome, Player, State};
+use crate::ai::game_tree::EndgameSolver;
+use crate::ai::game_tree::score::ScoreValue;
+use crate::ai::policy::Policy;
+use crate::ai::random_rollout::add_pass_action;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AgentError {
+    #[error("an agent has to run at least one simulation")]
+    NoSimulations,
+
+    #[error("an agent has to search at least one determinization")]
+    NoDeterminizations,
+}
+
+/// Checks a searching agent has something to search.
+pub(crate) fn check_search_budget(num_determinizations: u32, num_simulations: u32) -> Result<(), AgentError> {
+    if num_determinizations == 0 {
+        return Err(AgentError::NoDeterminizations);
+    }
+
+    if num_simulations == 0 {
+        return Err(AgentError::NoSimulations);
+    }
+
+    Ok(())
+}
+
+/// Any kind of agent, searching or not, so the game runners can seat agents of different kinds
+/// against each other, e.g. an `ismcts::MtAgent` against an `mcts::Agent` and a `RandomAgent`.
+/// Every agent in the crate implements it, and through it `MctsAgent`, `IsMctsAgent` and
+/// `IsMctsMtAgent`. The trait is generic rather than its methods so agents can be boxed as
+/// `dyn Agent<R, S, A, P>`.
+pub trait Agent<R, S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    fn player(&self) -> P;
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A>;
+
+    /// Decides with `budget` left on the agent's clock under a `TimeControl`. Agents which can cut
+    /// their search short should stop by then and play the best action found so far. Defaults to
+    /// `decide`, ignoring the budget.
+    fn decide_within(&self, rng: &mut R, state: &S, _budget: Duration) -> Option<A> {
+        self.decide(rng, state)
+    }
+
+    /// Decides along with what the agent's search found, see `Decision`. Defaults to the decided
+    /// action getting all of the search and no value estimate, for agents which don't search.
+    fn decide_in_detail(&self, rng: &mut R, state: &S) -> Option<Decision<A>> {
+        let action = self.decide(rng, state)?;
+        Some(Decision {
+            visits: vec![(action.clone(), 1.0)],
+            action,
+            value: None,
+        })
+    }
+}
+
+/// An agent's decision along with what its search found.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Decision<A> {
+    pub action: A,
+    /// the share of the search each action got, e.g. the root's visit distribution, as training
+    /// data for a policy
+    pub visits: Vec<(A, ScoreValue)>,
+    /// the agent's estimate of what the decided action is worth to its player, e.g. the action's
+    /// average score at the root, which the game runners resign by
+    pub value: Option<ScoreValue>,
+}
+
+/// Plays a uniformly random legal action, the baseline to measure searching agents against.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RandomAgent<P: Player> {
+    player: P,
+}
+
+impl<P: Player> RandomAgent<P> {
+    pub fn new(player: P) -> Self {
+        Self { player }
+    }
+}
+
+/// a uniformly random legal action, or the state's pass action when it has none
+fn random_action<R: Rng, S: State<A, P>, A: Action, P: Player>(rng: &mut R, state: &S) -> Option<A> {
+    let mut actions = state.actions();
+    add_pass_action(state, &mut actions);
+    actions.choose(rng).cloned()
+}
+
+impl<R, S, A, P> Agent<R, S, A, P> for RandomAgent<P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    fn player(&self) -> P {
+        self.player.clone()
+    }
+
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A> {
+        random_action(rng, state)
+    }
+}
+
+impl<R, S, A, P> Policy<R, S, A, P> for RandomAgent<P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    fn decide(&mut self, rng: &mut R, state: &S) -> Option<A> {
+        random_action(rng, state)
+    }
+}
+
+/// Values a state for a player, the higher the better, e.g. a material count.
+pub type Evaluation<S, P> = Arc<dyn Fn(&S, &P) -> ScoreValue + Send + Sync>;
+
+/// Plays the action leading to the state it values highest, without looking any further ahead, a
+/// stronger baseline than `RandomAgent` which is still much weaker than searching. Finished games
+/// are valued by their outcome, a win at 1, a draw at 0.5 and a loss at 0, and equally valued
+/// actions are chosen between at random.
+pub struct GreedyAgent<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    player: P,
+    evaluation: Evaluation<S, P>,
+    _phantom_a: PhantomData<A>,
+}
+
+impl<S, A, P> GreedyAgent<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    /// Values states by `State::heuristic_value`.
+    pub fn new(player: P) -> Self {
+        Self {
+            player,
+            evaluation: Arc::new(|state: &S, player: &P| {
+                state
+                    .heuristic_value()
+                    .into_iter()
+                    .find(|(valued_player, _)| valued_player == player)
+                    .map_or(0.0, |(_, value)| value)
+            }),
+            _phantom_a: Default::default(),
+        }
+    }
+
+    /// Values states with `evaluation` rather than `State::heuristic_value`.
+    pub fn with_evaluation<F: Fn(&S, &P) -> ScoreValue + Send + Sync + 'static>(mut self, evaluation: F) -> Self {
+        self.evaluation = Arc::new(evaluation);
+        self
+    }
+
+    fn value(&self, state: &S) -> ScoreValue {
+        match state.outcome() {
+            Some(outcome) => outcome_value(&outcome, &self.player),
+            None => (self.evaluation)(state, &self.player),
+        }
+    }
+
+    fn greedy_action<R: Rng>(&self, rng: &mut R, state: &S) -> Option<A> {
+        let mut actions = state.actions();
+        add_pass_action(state, &mut actions);
+
+        let mut best_actions = Vec::new();
+        let mut best_value = ScoreValue::MIN;
+        for action in actions {
+            // an action the state can't apply isn't worth considering
+            let Ok(next_state) = state.apply_action(rng, &action) else {
+                continue;
+            };
+
+            let value = self.value(&next_state);
+            if value > best_value {
+                best_value = value;
+                best_actions.clear();
+            }
+
+            if value == best_value {
+                best_actions.push(action);
+            }
+        }
+
+        best_actions.choose(rng).cloned()
+    }
+}
+
+impl<S, A, P> Clone for GreedyAgent<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    fn clone(&self) -> Self {
+        Self {
+            player: self.player.clone(),
+            evaluation: self.evaluation.clone(),
+            _phantom_a: Default::default(),
+        }
+    }
+}
+
+/// what a finished game is worth to `player`, whose teammates' wins are their own
+pub(crate) fn outcome_value<P: Player>(outcome: &Outcome<P>, player: &P) -> ScoreValue {
+    match outcome {
+        Outcome::Winner(winner) | Outcome::WinnerBy(winner, _) if winner.teammates().contains(player) => 1.0,
+        Outcome::Draw(drawing_players) if drawing_players.iter().any(|drawing_player| drawing_player.teammates().contains(player)) => 0.5,
+        Outcome::Scores(rewards) => rewards.get(player).copied().unwrap_or(0.0),
+        Outcome::Resignation(resigner) if !resigner.teammates().contains(player) => 1.0,
+        _ => 0.0,
+    }
+}
+
+impl<R, S, A, P> Agent<R, S, A, P> for GreedyAgent<S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    fn player(&self) -> P {
+        self.player.clone()
+    }
+
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A> {
+        self.greedy_action(rng, state)
+    }
+}
+
+impl<R, S, A, P> Policy<R, S, A, P> for GreedyAgent<S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    fn decide(&mut self, rng: &mut R, state: &S) -> Option<A> {
+        self.greedy_action(rng, state)
+    }
+}
+
+/// Asks for a choice among a state's legal actions, returning the index of the chosen one, or None
+/// to decide on nothing, e.g. when the player quits.
+pub type Chooser<S, A> = Arc<dyn Fn(&S, &[A]) -> Option<usize> + Send + Sync>;
+
+/// Defers every decision to a person, through a callback which presents the legal actions and
+/// returns their choice, so the game runners can host matches between people and searching agents.
+pub struct HumanAgent<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    player: P,
+    choose: Chooser<S, A>,
+}
+
+impl<S, A, P> HumanAgent<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    pub fn new<F: Fn(&S, &[A]) -> Option<usize> + Send + Sync + 'static>(player: P, choose: F) -> Self {
+        Self {
+            player,
+            choose: Arc::new(choose),
+        }
+    }
+
+    /// a choice outside of the legal actions decides on nothing, like no choice at all
+    fn chosen_action(&self, state: &S) -> Option<A> {
+        let mut actions = state.actions();
+        add_pass_action(state, &mut actions);
+
+        let action_idx = (self.choose)(state, &actions)?;
+        (action_idx < actions.len()).then(|| actions.swap_remove(action_idx))
+    }
+}
+
+impl<S, A, P> Clone for HumanAgent<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    fn clone(&self) -> Self {
+        Self {
+            player: self.player.clone(),
+            choose: self.choose.clone(),
+        }
+    }
+}
+
+impl<R, S, A, P> Agent<R, S, A, P> for HumanAgent<S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    fn player(&self) -> P {
+        self.player.clone()
+    }
+
+    fn decide(&self, _rng: &mut R, state: &S) -> Option<A> {
+        self.chosen_action(state)
+    }
+}
+
+impl<R, S, A, P> Policy<R, S, A, P> for HumanAgent<S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    fn decide(&mut self, _rng: &mut R, state: &S) -> Option<A> {
+        self.chosen_action(state)
+    }
+}
+
+/// Plays the action an endgame solver proves best whenever it can, e.g. from a tablebase or a
+/// brute force search of small enough positions, skipping the search, and leaves every other
+/// decision to the agent it wraps.
+pub struct SolvingAgent<R, S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    agent: Box<dyn Agent<R, S, A, P>>,
+    solver: EndgameSolver<S, A, P>,
+}
+
+impl<R, S, A, P> SolvingAgent<R, S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    /// `solver` returns the proven best action of a state along with the outcome it leads to, or
+    /// None when it can't prove one, e.g. as the position isn't small enough yet.
+    pub fn new<T, F>(agent: T, solver: F) -> Self
+        where
+            T: Agent<R, S, A, P> + 'static,
+            F: Fn(&S) -> Option<(A, Outcome<P>)> + Send + Sync + 'static,
+    {
+        Self {
+            agent: Box::new(agent),
+            solver: Arc::new(solver),
+        }
+    }
+
+    fn solved_action(&self, state: &S) -> Option<A> {
+        (self.solver)(state).map(|(action, _)| action)
+    }
+}
+
+impl<R, S, A, P> Agent<R, S, A, P> for SolvingAgent<R, S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    fn player(&self) -> P {
+        self.agent.player()
+    }
+
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A> {
+        self.solved_action(state).or_else(|| self.agent.decide(rng, state))
+    }
+
+    fn decide_within(&self, rng: &mut R, state: &S, budget: Duration) -> Option<A> {
+        self.solved_action(state).or_else(|| self.agent.decide_within(rng, state, budget))
+    }
+
+    fn decide_in_detail(&self, rng: &mut R, state: &S) -> Option<Decision<A>> {
+        match (self.solver)(state) {
+            Some((action, outcome)) => Some(Decision {
+                visits: vec![(action.clone(), 1.0)],
+                action,
+                value: Some(outcome_value(&outcome, &self.agent.player())),
+            }),
+            None => self.agent.decide_in_detail(rng, state),
+        }
+    }
+}
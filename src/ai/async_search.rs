@@ -0,0 +1,47 @@
+use std::hash::Hash;
+use std::panic;
+use rand::Rng;
+use tokio::task::{spawn_blocking, JoinError};
+use crate::{Action, Player, State};
+use crate::ai::ismcts::{ismcts_mt_with_config, Determinable, IsMctsMtAgent, IsmctsConfig, IsmctsError, IsmctsResult};
+
+/// Runs `ismcts_mt_with_config` on tokio's blocking pool, so an async game backend can await a
+/// decision without stalling its other tasks. The state and rng are moved into the search.
+pub async fn ismcts_async<R, S, A, P>(state: S, rng: R, config: IsmctsConfig<A, P>) -> Result<IsmctsResult<A, P>, IsmctsError>
+    where
+        R: Rng + Clone + Send + 'static,
+        S: State<A, P> + Determinable<S, A, P> + Send + 'static,
+        A: Action + Send + Sync + Eq + Hash + 'static,
+        P: Player + Send + Sync,
+{
+    spawn_blocking(move || ismcts_mt_with_config(&state, &rng, &config))
+        .await
+        .unwrap_or_else(|error| Err(join_error(error)))
+}
+
+/// Has `agent` decide on tokio's blocking pool, returning the agent and rng along with its
+/// decision so they can be used again.
+pub async fn decide_async<G, R, S, A, P>(agent: G, mut rng: R, state: S) -> Result<(Option<A>, G, R), IsmctsError>
+    where
+        G: IsMctsMtAgent<R, S, A, P> + Send + 'static,
+        R: Rng + Clone + Send + 'static,
+        S: State<A, P> + Determinable<S, A, P> + Send + 'static,
+        A: Action + Send + Sync + Eq + Hash + 'static,
+        P: Player + Send + Sync,
+{
+    spawn_blocking(move || {
+        let action = agent.decide(&mut rng, &state);
+        (action, agent, rng)
+    })
+        .await
+        .map_err(join_error)
+}
+
+/// a search which panicked keeps panicking in the awaiting task, otherwise the runtime is shutting
+/// down and cancelled it
+fn join_error(error: JoinError) -> IsmctsError {
+    match error.try_into_panic() {
+        Ok(payload) => panic::resume_unwind(payload),
+        Err(_) => IsmctsError::Cancelled,
+    }
+}
@@ -0,0 +1,91 @@
+use rand::Rng;
+use crate::{Action, Player, State};
+use crate::ai::information_set::ObservableState;
+use crate::ai::ismcts::Determinable;
+
+/// Produces variations of a state which differ only in information hidden from an observer.
+///
+/// This must be implemented independently of `Determinable`, otherwise the audit can't catch
+/// mistakes made in `determine`.
+pub trait HiddenInformation<P: Player> {
+    /// Returns a copy of this state with every element hidden from `observer` rearranged, e.g.
+    /// shuffling the deck and the opponents' hands together and re-dealing them.
+    fn permute_hidden<R: Rng>(&self, rng: &mut R, observer: P) -> Self;
+}
+
+/// A permutation of the hidden information under which the audited agent changed its mind.
+pub struct Deviation<A> where A: Action {
+    pub permutation_idx: u32,
+    pub action: Option<A>,
+}
+
+pub struct LeakReport<A> where A: Action {
+    /// The action decided on when given the true state.
+    pub expected: Option<A>,
+    pub deviations: Vec<Deviation<A>>,
+}
+
+impl<A> LeakReport<A> where A: Action {
+    pub fn is_leak_free(&self) -> bool {
+        self.deviations.is_empty()
+    }
+}
+
+/// Verifies that a decision doesn't depend on information the deciding player shouldn't see.
+///
+/// The state is re-permuted `num_permutations` times from the perspective of its current player,
+/// and `decide` is re-run on each permutation with a clone of the same `search_rng`. Any decision
+/// which differs from the one made on the true state indicates a leak.
+///
+/// For the comparison to be meaningful `determine` has to be canonical, i.e. it must only depend on
+/// what the observer knows. Collecting unseen cards in the order they happen to sit in the true
+/// state before shuffling them is itself a (subtle) leak, and will be reported as such.
+pub fn audit_information_leaks<
+    R: Rng + Clone,
+    PR: Rng,
+    S: State<A, P> + HiddenInformation<P>,
+    A: Action,
+    P: Player,
+    F: Fn(&S, &mut R) -> Option<A>,
+>(state: &S, search_rng: &R, permutation_rng: &mut PR, num_permutations: u32, decide: F) -> LeakReport<A> {
+    let observer = state.current_player();
+    let expected = decide(state, &mut search_rng.clone());
+
+    let deviations = (0..num_permutations)
+        .filter_map(|permutation_idx| {
+            let permuted = state.permute_hidden(permutation_rng, observer.clone());
+            let action = decide(&permuted, &mut search_rng.clone());
+
+            if action == expected {
+                None
+            } else {
+                Some(Deviation {
+                    permutation_idx,
+                    action,
+                })
+            }
+        })
+        .collect();
+
+    LeakReport {
+        expected,
+        deviations,
+    }
+}
+
+/// Counts how many of `num_determinizations` determinizations of `state`, from the perspective of
+/// its current player, that player could tell apart from the true state. Any at all means
+/// `determine` changed something the player can see.
+pub fn audit_determinizations<
+    R: Rng,
+    S: ObservableState<A, P> + Determinable<S, A, P>,
+    A: Action,
+    P: Player,
+>(state: &S, rng: &mut R, num_determinizations: u32) -> u32 {
+    let observer = state.current_player();
+    let observation = state.observation(observer.clone());
+
+    (0..num_determinizations)
+        .filter(|_| state.determine(rng, observer.clone()).observation(observer.clone()) != observation)
+        .count() as u32
+}
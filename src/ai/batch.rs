@@ -0,0 +1,44 @@
+use std::sync::Mutex;
+use std::thread;
+
+/// The number of workers to use when the caller doesn't specify one.
+pub(crate) fn default_parallelism() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Runs `f` over every item on a pool of at most `parallelism` worker threads, returning the
+/// results in the same order as the items.
+pub(crate) fn run_batch<T, U, F>(items: Vec<T>, parallelism: usize, f: F) -> Vec<U>
+    where
+        T: Send,
+        U: Send,
+        F: Fn(T) -> U + Sync,
+{
+    let num_items = items.len();
+    let num_workers = parallelism.max(1).min(num_items);
+
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results: Mutex<Vec<Option<U>>> = Mutex::new((0..num_items).map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..num_workers {
+            scope.spawn(|| {
+                loop {
+                    let Some((idx, item)) = queue.lock().unwrap().next() else {
+                        break;
+                    };
+
+                    let result = f(item);
+                    results.lock().unwrap()[idx] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every batch item is processed"))
+        .collect()
+}
@@ -0,0 +1,218 @@
+use rand::Rng;
+use crate::{Action, Player, State};
+use crate::ai::game_tree::score::ScoreValue;
+use crate::ai::information_set::{Observable, ObservableState};
+use crate::ai::ismcts::{best_action, search_determinizations, Aggregation, Determinable, IsmctsConfig, IsmctsError, IsmctsResult};
+use crate::ai::random_rollout::{add_pass_action, transition};
+use crate::ai::rollout::weighted_choice;
+
+/// Estimates how likely a player was to play an action, given a possible true state of the game,
+/// so beliefs can be conditioned on opponents' choices, e.g. they didn't take an obvious capture
+/// so they probably don't hold the card which allows it.
+pub trait InferenceModel<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    /// The relative likelihood of `actor` playing `action` from `state`. Particles are weighted by
+    /// it, so zero rules the state out entirely.
+    fn likelihood(&self, state: &S, actor: P, action: &A) -> ScoreValue;
+}
+
+/// A possible true state of the game, along with how likely it is.
+#[derive(Debug, Clone)]
+pub struct Particle<S> {
+    pub state: S,
+    pub weight: ScoreValue,
+}
+
+/// A player's belief about the hidden information in a game, as a set of weighted particles.
+///
+/// Rather than determinizing the current state from scratch every turn, the particles are carried
+/// forward through each action as the player observed it, so they stay consistent with everything
+/// the player has seen happen. Particles in which nothing the player could have seen played was
+/// legal are discarded.
+#[derive(Debug, Clone)]
+pub struct BeliefState<S> {
+    particles: Vec<Particle<S>>,
+    num_particles: usize,
+}
+
+impl<S> BeliefState<S> {
+    /// the number of particles the belief is kept topped up to
+    pub fn num_particles(&self) -> usize {
+        self.num_particles
+    }
+
+    pub fn particles(&self) -> &[Particle<S>] {
+        &self.particles
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// The effective number of distinct particles given their weights, which drops as a few
+    /// particles come to dominate the belief.
+    pub fn effective_sample_size(&self) -> ScoreValue {
+        let total: ScoreValue = self.particles.iter().map(|particle| particle.weight).sum();
+        let total_squared: ScoreValue = self.particles.iter().map(|particle| particle.weight * particle.weight).sum();
+
+        if total_squared > 0.0 {
+            total * total / total_squared
+        } else {
+            0.0
+        }
+    }
+}
+
+impl<S: Clone> BeliefState<S> {
+    /// Samples `num_particles` determinizations of `state` from the perspective of `player`.
+    pub fn sample<R: Rng, A: Action, P: Player>(rng: &mut R, state: &S, player: P, num_particles: usize) -> Self where S: State<A, P> + Determinable<S, A, P> {
+        let mut belief = Self {
+            particles: Vec::with_capacity(num_particles),
+            num_particles,
+        };
+        belief.replenish(rng, state, player);
+
+        belief
+    }
+
+    /// Tops the belief back up to its full number of particles with fresh determinizations of
+    /// `state`, e.g. once every particle has been contradicted by what was observed.
+    pub fn replenish<R: Rng, A: Action, P: Player>(&mut self, rng: &mut R, state: &S, player: P) where S: State<A, P> + Determinable<S, A, P> {
+        while self.particles.len() < self.num_particles {
+            let (state, weight) = state.determine_weighted(rng, player.clone());
+            self.particles.push(Particle { state, weight });
+        }
+    }
+
+    /// Advances every particle by an action which looks like `observed` to `observer`, the action
+    /// as they saw it played, e.g. a face down discard. Each particle plays one of its own actions
+    /// which look alike, chosen at random, and resolves any chance events it leads to like the game
+    /// runners do. Those with no such action are discarded.
+    pub fn observe<R: Rng, A: Action, P: Player>(&mut self, rng: &mut R, observer: P, observed: &A) where S: State<A, P> + Observable<A, P> {
+        self.update(rng, observer, observed, None::<fn(&S, &A) -> ScoreValue>);
+    }
+
+    /// Like `observe`, but chooses among the actions which look alike by how likely `model` thinks
+    /// `actor` was to play each of them, and weights each particle by how likely it was to look
+    /// like `observed` at all.
+    pub fn observe_with<R: Rng, A: Action, P: Player, M: InferenceModel<S, A, P> + ?Sized>(&mut self, rng: &mut R, observer: P, actor: P, observed: &A, model: &M) where S: State<A, P> + Observable<A, P> {
+        self.update(rng, observer, observed, Some(|state: &S, action: &A| model.likelihood(state, actor.clone(), action)));
+    }
+
+    /// Discards the particles which `player` could tell apart from `state`, the game as it really
+    /// is, e.g. those where a card which has just been revealed is somewhere else. `replenish` tops
+    /// the belief back up afterwards.
+    pub fn discard_unobserved<A: Action, P: Player>(&mut self, state: &S, player: P) where S: ObservableState<A, P> {
+        let observation = state.observation(player.clone());
+        self.particles.retain(|particle| particle.state.observation(player.clone()) == observation);
+    }
+
+    /// Carries `player`'s belief forward through `action`, played from `previous` to reach `state`,
+    /// the game as it really is. The particles follow the action as `player` saw it,
+    /// those `player` could tell apart from `state` are discarded, e.g. as the particle drew
+    /// another card than the one really drawn, and the belief is resampled and topped back up.
+    pub fn track<R: Rng, A: Action, P: Player>(&mut self, rng: &mut R, previous: &S, state: &S, player: P, action: &A, model: Option<&dyn InferenceModel<S, A, P>>)
+        where S: ObservableState<A, P> + Observable<A, P> + Determinable<S, A, P>
+    {
+        let actor = previous.current_player();
+        let observed = previous.observe(action, player.clone());
+        match model {
+            // a player's own actions tell them nothing new
+            Some(model) if actor != player => self.observe_with(rng, player.clone(), actor, &observed, model),
+            _ => self.observe(rng, player.clone(), &observed),
+        }
+        self.discard_unobserved(state, player.clone());
+
+        if self.effective_sample_size() < self.num_particles as ScoreValue / 2.0 {
+            self.resample(rng);
+        }
+
+        // whatever was contradicted is replaced by fresh determinizations of what's known now
+        self.replenish(rng, state, player);
+    }
+
+    fn update<R: Rng, A: Action, P: Player, F: Fn(&S, &A) -> ScoreValue>(&mut self, rng: &mut R, observer: P, observed: &A, likelihood: Option<F>) where S: State<A, P> + Observable<A, P> {
+        self.particles = std::mem::take(&mut self.particles)
+            .into_iter()
+            .filter_map(|particle| {
+                let mut actions = particle.state.actions();
+                add_pass_action(&particle.state, &mut actions);
+                actions.retain(|action| particle.state.observe(action, observer.clone()) == *observed);
+
+                let likelihoods: Vec<ScoreValue> = actions
+                    .iter()
+                    .map(|action| likelihood.as_ref().map_or(1.0, |likelihood| likelihood(&particle.state, action)))
+                    .collect();
+                let total: ScoreValue = likelihoods.iter().sum();
+                if actions.is_empty() || total <= 0.0 {
+                    return None;
+                }
+
+                // without a model every consistent particle stays as likely as it was
+                let weight = match likelihood {
+                    Some(_) => particle.weight * total,
+                    None => particle.weight,
+                };
+                let action = &actions[weighted_choice(&likelihoods, rng)];
+                let state = transition(&particle.state, rng, action).ok()?;
+                Some(Particle {
+                    state,
+                    weight,
+                })
+            })
+            .collect();
+    }
+
+    /// Draws a full set of equally weighted particles in proportion to the current weights, so
+    /// search effort isn't wasted on particles which have become very unlikely.
+    pub fn resample<R: Rng>(&mut self, rng: &mut R) {
+        let total: ScoreValue = self.particles.iter().map(|particle| particle.weight).sum();
+        if self.particles.is_empty() || total <= 0.0 {
+            return;
+        }
+
+        // systematic resampling, a single random offset spaced evenly over the cumulative weights
+        let step = total / self.num_particles as ScoreValue;
+        let mut target = rng.gen::<ScoreValue>() * step;
+        let mut cumulative = 0.0;
+        let mut resampled = Vec::with_capacity(self.num_particles);
+
+        for particle in &self.particles {
+            cumulative += particle.weight;
+            while target < cumulative && resampled.len() < self.num_particles {
+                resampled.push(Particle {
+                    state: particle.state.clone(),
+                    weight: 1.0,
+                });
+                target += step;
+            }
+        }
+
+        self.particles = resampled;
+    }
+}
+
+/// Runs `ismcts` with the belief's particles as the determinizations, each weighted by the
+/// particle's weight.
+pub fn ismcts_with_belief<
+    R: Rng + Clone,
+    S: State<A, P>,
+    A: Action,
+    P: Player,
+>(belief: &BeliefState<S>, rng: &R, player: P, num_simulations: u32, aggregation: &Aggregation<A, P>) -> Result<A, IsmctsError> {
+    best_action(search_belief(belief, rng, player, num_simulations, aggregation))
+}
+
+/// `ismcts_with_belief`, returning everything the search found
+pub(crate) fn search_belief<
+    R: Rng + Clone,
+    S: State<A, P>,
+    A: Action,
+    P: Player,
+>(belief: &BeliefState<S>, rng: &R, player: P, num_simulations: u32, aggregation: &Aggregation<A, P>) -> Result<IsmctsResult<A, P>, IsmctsError> {
+    let particles = belief.particles();
+    let config = IsmctsConfig::new(particles.len() as u32, num_simulations).with_aggregation(aggregation.clone());
+    search_determinizations(rng, &config, player, |_, idx| {
+        let particle = &particles[idx as usize];
+        (particle.state.clone(), particle.weight)
+    })
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+/// The measurements for one combination of determinizations and simulations.
+#[derive(Debug, Clone)]
+pub struct BenchmarkRow {
+    pub num_determinizations: u32,
+    pub num_simulations: u32,
+    /// the mean of the decision quality over every benchmarked state
+    pub mean_quality: f64,
+    /// the mean time taken to decide
+    pub mean_wall_time: Duration,
+    /// the number of states no action was decided for
+    pub num_failures: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BenchmarkTable {
+    pub rows: Vec<BenchmarkRow>,
+}
+
+impl BenchmarkTable {
+    /// The rows which no other row beats on both quality and time, ordered from fastest to
+    /// slowest, i.e. the budget splits worth choosing between.
+    pub fn efficient_frontier(&self) -> Vec<&BenchmarkRow> {
+        let mut rows: Vec<&BenchmarkRow> = self.rows.iter().collect();
+        rows.sort_by(|a, b| a.mean_wall_time.cmp(&b.mean_wall_time).then(b.mean_quality.total_cmp(&a.mean_quality)));
+
+        let mut frontier: Vec<&BenchmarkRow> = Vec::new();
+        for row in rows {
+            if frontier.last().is_none_or(|best| row.mean_quality > best.mean_quality) {
+                frontier.push(row);
+            }
+        }
+
+        frontier
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("num_determinizations,num_simulations,mean_quality,mean_wall_time_ms,num_failures\n");
+        for row in &self.rows {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                row.num_determinizations,
+                row.num_simulations,
+                row.mean_quality,
+                row.mean_wall_time.as_secs_f64() * 1000.0,
+                row.num_failures,
+            ));
+        }
+
+        csv
+    }
+}
+
+impl Display for BenchmarkTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>16} {:>12} {:>10} {:>12} {:>9}", "determinizations", "simulations", "quality", "time (ms)", "failures")?;
+        for row in &self.rows {
+            writeln!(
+                f,
+                "{:>16} {:>12} {:>10.4} {:>12.3} {:>9}",
+                row.num_determinizations,
+                row.num_simulations,
+                row.mean_quality,
+                row.mean_wall_time.as_secs_f64() * 1000.0,
+                row.num_failures,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Measures decision quality and wall time for every combination of `determinizations` and
+/// `simulations`, deciding once for each of `states`.
+///
+/// `decide(state, num_determinizations, num_simulations)` makes a decision, typically by calling
+/// `ismcts` or `ismcts_mt`, and `quality(state, action)` scores the decided action, e.g. 1.0 if
+/// it matches a known best move. States no action is decided for count as a quality of zero.
+pub fn benchmark_ismcts<S, A, D, Q>(states: &[S], determinizations: &[u32], simulations: &[u32], mut decide: D, mut quality: Q) -> BenchmarkTable
+    where
+        D: FnMut(&S, u32, u32) -> Option<A>,
+        Q: FnMut(&S, &A) -> f64,
+{
+    let mut table = BenchmarkTable::default();
+    let num_states = states.len().max(1);
+
+    for num_determinizations in determinizations {
+        for num_simulations in simulations {
+            let mut total_quality = 0.0;
+            let mut total_wall_time = Duration::ZERO;
+            let mut num_failures = 0;
+
+            for state in states {
+                let start = Instant::now();
+                let action = decide(state, *num_determinizations, *num_simulations);
+                total_wall_time += start.elapsed();
+
+                match action {
+                    Some(action) => total_quality += quality(state, &action),
+                    None => num_failures += 1,
+                }
+            }
+
+            table.rows.push(BenchmarkRow {
+                num_determinizations: *num_determinizations,
+                num_simulations: *num_simulations,
+                mean_quality: total_quality / num_states as f64,
+                mean_wall_time: total_wall_time / num_states as u32,
+                num_failures,
+            });
+        }
+    }
+
+    table
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+
+/// A cache (of transpositions, evaluations, book moves and so on) which can be shared between
+/// agents in the same process.
+///
+/// Cloning the handle shares the same underlying map, so e.g. an analysis pane can read from the
+/// cache the playing agent fills, rather than holding its own copy.
+pub struct SharedCache<K, V> {
+    entries: Arc<RwLock<HashMap<K, V>>>,
+}
+
+/// A read-only handle onto a `SharedCache`.
+pub struct CacheReader<K, V> {
+    entries: Arc<RwLock<HashMap<K, V>>>,
+}
+
+impl<K: Hash + Eq, V: Clone> SharedCache<K, V> {
+    pub fn new() -> Self {
+        Self {
+            entries: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        read(&self.entries).get(key).cloned()
+    }
+
+    /// Inserts `value`, returning the value previously cached for `key` if there was one.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.entries
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(key, value)
+    }
+
+    /// Removes the value cached for `key` if `f` returns true for it, returning whether it did.
+    pub fn remove_if<F: FnOnce(&V) -> bool>(&self, key: &K, f: F) -> bool {
+        let mut entries = self.entries
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if entries.get(key).is_some_and(f) {
+            entries.remove(key);
+            return true;
+        }
+
+        false
+    }
+
+    /// Returns the cached value for `key`, computing and caching it with `f` if there isn't one.
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&self, key: K, f: F) -> V {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+
+        // computed outside of the lock, so a slow evaluation doesn't block readers
+        let value = f();
+        self.entries
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(key)
+            .or_insert(value)
+            .clone()
+    }
+
+    /// Updates the cached value for `key` in place, inserting `default()` first if there isn't one.
+    pub fn update<D: FnOnce() -> V, F: FnOnce(&mut V)>(&self, key: K, default: D, f: F) {
+        let mut entries = self.entries
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        f(entries.entry(key).or_insert_with(default));
+    }
+
+    pub fn len(&self) -> usize {
+        read(&self.entries).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.entries
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clear();
+    }
+
+    /// A handle which can read this cache but not modify it.
+    pub fn reader(&self) -> CacheReader<K, V> {
+        CacheReader {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> CacheReader<K, V> {
+    pub fn get(&self, key: &K) -> Option<V> {
+        read(&self.entries).get(key).cloned()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        read(&self.entries).contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        read(&self.entries).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> Default for SharedCache<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Clone for SharedCache<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+impl<K, V> Clone for CacheReader<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+fn read<K, V>(entries: &RwLock<HashMap<K, V>>) -> RwLockReadGuard<'_, HashMap<K, V>> {
+    // a writer panicking can't leave the map half updated, so a poisoned lock is still usable
+    entries.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
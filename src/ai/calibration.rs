@@ -0,0 +1,135 @@
+/// The result of a single calibration game, from the perspective of the budget being calibrated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchResult {
+    Win,
+    Draw,
+    Loss,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MatchRecord {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchRecord {
+    pub fn record(&mut self, result: MatchResult) {
+        match result {
+            MatchResult::Win => self.wins += 1,
+            MatchResult::Draw => self.draws += 1,
+            MatchResult::Loss => self.losses += 1,
+        }
+    }
+
+    pub fn num_games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// the fraction of points scored, counting a draw as half a win
+    pub fn score_rate(&self) -> f64 {
+        if self.num_games() == 0 {
+            return 0.5;
+        }
+
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.num_games() as f64
+    }
+
+    /// The Elo difference implied by the score rate. A perfect (or perfectly bad) record is treated
+    /// as if half a game had gone the other way, so the difference stays finite.
+    pub fn elo_difference(&self) -> f64 {
+        let num_games = self.num_games().max(1) as f64;
+        let margin = 0.5 / num_games;
+        let score_rate = self.score_rate().clamp(margin, 1.0 - margin);
+
+        -400.0 * (1.0 / score_rate - 1.0).log10()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CalibrationPoint {
+    pub num_simulations: u32,
+    /// Elo relative to the reference budget
+    pub elo: f64,
+    pub record: MatchRecord,
+}
+
+/// A fitted curve of `elo = intercept + slope * ln(num_simulations)`, relative to the reference
+/// budget it was calibrated against.
+#[derive(Debug, Clone)]
+pub struct StrengthCurve {
+    pub intercept: f64,
+    pub slope: f64,
+    pub points: Vec<CalibrationPoint>,
+}
+
+impl StrengthCurve {
+    pub fn predict_elo(&self, num_simulations: u32) -> f64 {
+        self.intercept + self.slope * (num_simulations.max(1) as f64).ln()
+    }
+
+    /// The number of simulations predicted to reach `target_elo`, or None if more simulations
+    /// didn't make the agent any stronger during calibration.
+    pub fn simulations_for_elo(&self, target_elo: f64) -> Option<u32> {
+        if self.slope <= 0.0 {
+            return None;
+        }
+
+        let num_simulations = ((target_elo - self.intercept) / self.slope).exp().ceil();
+        Some(num_simulations.clamp(1.0, u32::MAX as f64) as u32)
+    }
+}
+
+/// Fits a simulations-versus-Elo curve for an agent configuration.
+///
+/// Each of `budgets` plays `games_per_budget` games against the same configuration using
+/// `reference_budget` simulations. `play(budget, reference_budget, game_idx)` plays one such game
+/// (alternating seats as appropriate for the game) and reports the result for `budget`.
+pub fn calibrate<F: FnMut(u32, u32, u32) -> MatchResult>(budgets: &[u32], reference_budget: u32, games_per_budget: u32, mut play: F) -> StrengthCurve {
+    let mut points = vec![CalibrationPoint {
+        num_simulations: reference_budget,
+        elo: 0.0,
+        record: MatchRecord::default(),
+    }];
+
+    for budget in budgets {
+        let mut record = MatchRecord::default();
+        for game_idx in 0..games_per_budget {
+            record.record(play(*budget, reference_budget, game_idx));
+        }
+
+        points.push(CalibrationPoint {
+            num_simulations: *budget,
+            elo: record.elo_difference(),
+            record,
+        });
+    }
+
+    let (intercept, slope) = fit_log_curve(&points);
+
+    StrengthCurve {
+        intercept,
+        slope,
+        points,
+    }
+}
+
+/// least squares fit of elo against ln(num_simulations)
+fn fit_log_curve(points: &[CalibrationPoint]) -> (f64, f64) {
+    let xs: Vec<f64> = points.iter().map(|point| (point.num_simulations.max(1) as f64).ln()).collect();
+    let ys: Vec<f64> = points.iter().map(|point| point.elo).collect();
+
+    let n = points.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+
+    let covariance: f64 = xs.iter().zip(&ys).map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = xs.iter().map(|x| (x - mean_x).powi(2)).sum();
+
+    if variance == 0.0 {
+        return (mean_y, 0.0);
+    }
+
+    let slope = covariance / variance;
+    (mean_y - slope * mean_x, slope)
+}
@@ -0,0 +1,106 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+use thiserror::Error;
+
+/// How many times `redistribute` restarts a deal which ran into a dead end before giving up.
+const MAX_ATTEMPTS: u32 = 100;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DeterminizeError {
+    #[error("there are {num_items} unseen items to deal into {num_slots} slots")]
+    SizeMismatch { num_items: usize, num_slots: usize },
+
+    #[error("couldn't deal the unseen items within the constraints after {0} attempts")]
+    Unsatisfiable(u32),
+}
+
+/// Returns the items of `all` which aren't in `seen`, e.g. the cards of a deck which the
+/// perspective player hasn't seen.
+pub fn unseen<T: Clone + PartialEq>(all: &[T], seen: &[T]) -> Vec<T> {
+    let mut seen: Vec<&T> = seen.iter().collect();
+    all.iter()
+        .filter(|item| match seen.iter().position(|seen_item| seen_item == item) {
+            // each seen item only accounts for one copy of duplicated items
+            Some(idx) => {
+                seen.swap_remove(idx);
+                false
+            }
+            None => true,
+        })
+        .cloned()
+        .collect()
+}
+
+/// Deals the unseen items at random into buckets of the given sizes, e.g. opponents' hands and the
+/// rest of the deck, such that `may_hold(bucket_idx, item)` holds for every item dealt.
+///
+/// The constraints carry what's known about the hidden information, e.g. a player who didn't
+/// follow suit can't hold that suit. The most constrained items are dealt first, and a deal which
+/// runs into a dead end is restarted.
+pub fn redistribute<R, T, F>(rng: &mut R, mut unseen_items: Vec<T>, bucket_sizes: &[usize], may_hold: F) -> Result<Vec<Vec<T>>, DeterminizeError>
+where
+    R: Rng,
+    F: Fn(usize, &T) -> bool,
+{
+    let num_slots: usize = bucket_sizes.iter().sum();
+    if num_slots != unseen_items.len() {
+        return Err(DeterminizeError::SizeMismatch {
+            num_items: unseen_items.len(),
+            num_slots,
+        });
+    }
+
+    for _ in 0..MAX_ATTEMPTS {
+        // shuffle first so that items which are equally constrained are dealt in a random order
+        unseen_items.shuffle(rng);
+        unseen_items.sort_by_cached_key(|item| (0..bucket_sizes.len()).filter(|bucket_idx| may_hold(*bucket_idx, item)).count());
+
+        if let Some(assignment) = deal(rng, &unseen_items, bucket_sizes, &may_hold) {
+            let mut buckets: Vec<Vec<T>> = bucket_sizes.iter().map(|size| Vec::with_capacity(*size)).collect();
+            for (item, bucket_idx) in unseen_items.into_iter().zip(assignment) {
+                buckets[bucket_idx].push(item);
+            }
+
+            return Ok(buckets);
+        }
+    }
+
+    Err(DeterminizeError::Unsatisfiable(MAX_ATTEMPTS))
+}
+
+/// Picks a bucket for each item in order, weighted by the room left in the buckets which may hold
+/// it, or returns `None` when an item has nowhere left to go.
+fn deal<R: Rng, T, F: Fn(usize, &T) -> bool>(rng: &mut R, items: &[T], bucket_sizes: &[usize], may_hold: &F) -> Option<Vec<usize>> {
+    let mut room: Vec<usize> = bucket_sizes.to_vec();
+    let mut assignment = Vec::with_capacity(items.len());
+
+    for item in items {
+        let total_room: usize = room
+            .iter()
+            .enumerate()
+            .filter(|(bucket_idx, _)| may_hold(*bucket_idx, item))
+            .map(|(_, room)| *room)
+            .sum();
+
+        if total_room == 0 {
+            return None;
+        }
+
+        let mut slot = rng.gen_range(0..total_room);
+        for (bucket_idx, bucket_room) in room.iter_mut().enumerate() {
+            if *bucket_room == 0 || !may_hold(bucket_idx, item) {
+                continue;
+            }
+
+            if slot < *bucket_room {
+                *bucket_room -= 1;
+                assignment.push(bucket_idx);
+                break;
+            }
+
+            slot -= *bucket_room;
+        }
+    }
+
+    Some(assignment)
+}
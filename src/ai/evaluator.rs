@@ -0,0 +1,33 @@
+use crate::{Action, Player, State};
+use crate::ai::game_tree::score::ScoreValue;
+
+/// What an `Evaluator` makes of a state.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateEvaluation<P> {
+    /// a prior probability, or any non-negative weight, for each of the actions evaluated with
+    /// the state, in the same order
+    pub priors: Vec<ScoreValue>,
+    /// each player's value of the state, between 0 and 1 like a win
+    pub values: Vec<(P, ScoreValue)>,
+}
+
+/// Judges a state at a glance, e.g. a policy/value network, for a tree built with
+/// `GameTree::with_evaluator` which uses it in place of rollouts, as the search of an AlphaZero
+/// style setup. Closures taking a state and its actions implement it.
+pub trait Evaluator<S, A, P>: Send + Sync where S: State<A, P>, A: Action, P: Player {
+    /// Evaluates `state`, whose legal actions are `actions`.
+    fn evaluate(&self, state: &S, actions: &[A]) -> StateEvaluation<P>;
+}
+
+impl<S, A, P, F> Evaluator<S, A, P> for F
+    where
+        S: State<A, P>,
+        A: Action,
+        P: Player,
+        F: Fn(&S, &[A]) -> StateEvaluation<P> + Send + Sync,
+{
+    fn evaluate(&self, state: &S, actions: &[A]) -> StateEvaluation<P> {
+        self(state, actions)
+    }
+}
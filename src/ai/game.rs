@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::mem;
+use std::time::{Duration, Instant};
+use rand::Rng;
+use thiserror::Error;
+use crate::{Action, Describe, Outcome, Player, ScoreValue, State};
+use crate::ai::agents::Agent;
+use crate::ai::game_tree::error::SearchError;
+use crate::ai::policy::Policy;
+use crate::ai::replay::{Recording, Replay};
+use crate::ai::random_rollout::{add_pass_action, nature_choice, transition};
+
+#[derive(Error)]
+pub enum GameError<A: Action + Describe, P: Player + Describe> {
+    #[error("there is no agent mapped to player {}", .0.describe())]
+    NoAgentForPlayer(P),
+
+    #[error("the agent of player {} was unable to decide what to do", .0.describe())]
+    AgentDecisionError(P),
+
+    #[error("the agent of player {} couldn't search any determinization: {1}", .0.describe())]
+    AgentSearchError(P, SearchError),
+
+    #[error("the policy for player {} was unable to decide what to do", .0.describe())]
+    PolicyDecisionError(P),
+
+    #[error("unable to apply action {}", .0.describe())]
+    ActionApplicationError(A),
+
+    #[error("action {} isn't legal in the current state", .0.describe())]
+    IllegalAction(A),
+
+    #[error("player {} ran out of time", .0.describe())]
+    TimeForfeit(P),
+
+    #[error("player {} resigned", .0.describe())]
+    Resigned(P),
+}
+
+// written out rather than derived, which would need `A: Debug` and `P: Debug`
+impl<A: Action + Describe, P: Player + Describe> Debug for GameError<A, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoAgentForPlayer(player) => f.debug_tuple("NoAgentForPlayer").field(&player.describe()).finish(),
+            Self::AgentDecisionError(player) => f.debug_tuple("AgentDecisionError").field(&player.describe()).finish(),
+            Self::AgentSearchError(player, error) => f.debug_tuple("AgentSearchError").field(&player.describe()).field(error).finish(),
+            Self::PolicyDecisionError(player) => f.debug_tuple("PolicyDecisionError").field(&player.describe()).finish(),
+            Self::ActionApplicationError(action) => f.debug_tuple("ActionApplicationError").field(&action.describe()).finish(),
+            Self::IllegalAction(action) => f.debug_tuple("IllegalAction").field(&action.describe()).finish(),
+            Self::TimeForfeit(player) => f.debug_tuple("TimeForfeit").field(&player.describe()).finish(),
+            Self::Resigned(player) => f.debug_tuple("Resigned").field(&player.describe()).finish(),
+        }
+    }
+}
+
+/// What happens when an agent takes longer to decide than it had left on its clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Timeout {
+    /// the agent loses on time, which the runner reports as an error
+    #[default]
+    Forfeit,
+    /// the agent's action is played anyway, which an agent searching within its budget, like
+    /// `mcts::Agent`, will have cut short to the best it found so far
+    BestSoFar,
+}
+
+/// A chess clock style time control for an agent: the time it has left for the rest of the game,
+/// plus an increment added after each of its moves.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeControl {
+    pub remaining: Duration,
+    pub increment: Duration,
+    pub on_timeout: Timeout,
+}
+
+impl TimeControl {
+    /// `remaining` for the whole game, without an increment, forfeiting on timeout.
+    pub fn new(remaining: Duration) -> Self {
+        Self {
+            remaining,
+            increment: Duration::ZERO,
+            on_timeout: Timeout::default(),
+        }
+    }
+
+    pub fn with_increment(mut self, increment: Duration) -> Self {
+        self.increment = increment;
+        self
+    }
+
+    pub fn with_on_timeout(mut self, on_timeout: Timeout) -> Self {
+        self.on_timeout = on_timeout;
+        self
+    }
+
+    /// Charges a decision which took since `started` to the clock, returning false if the agent
+    /// has forfeited by running out of time.
+    pub(crate) fn charge(&mut self, started: Instant) -> bool {
+        let elapsed = started.elapsed();
+        if elapsed > self.remaining && self.on_timeout == Timeout::Forfeit {
+            self.remaining = Duration::ZERO;
+            return false;
+        }
+
+        self.remaining = self.remaining.saturating_sub(elapsed) + self.increment;
+        true
+    }
+}
+
+/// When agents give up a lost game rather than play it out, e.g. to save time in lopsided
+/// self-play games: once an agent's value estimate of its decisions, `Decision::value`, stays
+/// below `threshold` for `num_moves` of its moves in a row. Agents which don't estimate their
+/// decisions' values never resign.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Resignation {
+    pub threshold: ScoreValue,
+    pub num_moves: u32,
+}
+
+impl Resignation {
+    pub fn new(threshold: ScoreValue, num_moves: u32) -> Self {
+        Self { threshold, num_moves }
+    }
+}
+
+/// Tracks how many moves in a row each agent has valued below the resignation threshold, and who
+/// resigned, for the runners.
+pub(crate) struct Resignations<P> {
+    resignation: Option<Resignation>,
+    low_value_streaks: HashMap<P, u32>,
+    resigned: Option<P>,
+}
+
+impl<P: Player> Default for Resignations<P> {
+    fn default() -> Self {
+        Self {
+            resignation: None,
+            low_value_streaks: HashMap::new(),
+            resigned: None,
+        }
+    }
+}
+
+impl<P: Player> Resignations<P> {
+    pub(crate) fn set(&mut self, resignation: Resignation) {
+        self.resignation = Some(resignation);
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.resignation.is_some()
+    }
+
+    pub(crate) fn resigned(&self) -> Option<&P> {
+        self.resigned.as_ref()
+    }
+
+    /// counts a decision `player` valued at `value`, returning true if they resign after it
+    pub(crate) fn decided(&mut self, player: &P, value: Option<ScoreValue>) -> bool {
+        let Some(resignation) = &self.resignation else {
+            return false;
+        };
+
+        let streak = self.low_value_streaks.entry(player.clone()).or_insert(0);
+        match value {
+            Some(value) if value < resignation.threshold => *streak += 1,
+            _ => *streak = 0,
+        }
+
+        // an agent only resigns once it has valued at least one decision, even if `num_moves` is 0
+        if *streak > 0 && *streak >= resignation.num_moves {
+            self.resigned = Some(player.clone());
+        }
+
+        self.resigned.is_some()
+    }
+
+    /// forgets the streaks and any resignation, e.g. when the game is restored to another state
+    pub(crate) fn clear(&mut self) {
+        self.low_value_streaks.clear();
+        self.resigned = None;
+    }
+}
+
+type TurnStartHook<S, P> = Box<dyn FnMut(&S, &P)>;
+type ActionAppliedHook<S, A, P> = Box<dyn FnMut(&S, &P, &A)>;
+type OutcomeHook<S, P> = Box<dyn FnMut(&S, &Outcome<P>)>;
+
+/// Observers of a game's progress, e.g. a UI redrawing the board or a logger, which the runners call
+/// whether the game is played through `run`, `step` or `play`.
+pub(crate) struct Hooks<S, A, P: Player> {
+    on_turn_start: Option<TurnStartHook<S, P>>,
+    on_action_applied: Option<ActionAppliedHook<S, A, P>>,
+    on_outcome: Option<OutcomeHook<S, P>>,
+}
+
+impl<S, A, P: Player> Default for Hooks<S, A, P> {
+    fn default() -> Self {
+        Self {
+            on_turn_start: None,
+            on_action_applied: None,
+            on_outcome: None,
+        }
+    }
+}
+
+impl<S, A, P: Player> Hooks<S, A, P> {
+    pub(crate) fn set_on_turn_start<F: FnMut(&S, &P) + 'static>(&mut self, on_turn_start: F) {
+        self.on_turn_start = Some(Box::new(on_turn_start));
+    }
+
+    pub(crate) fn set_on_action_applied<F: FnMut(&S, &P, &A) + 'static>(&mut self, on_action_applied: F) {
+        self.on_action_applied = Some(Box::new(on_action_applied));
+    }
+
+    pub(crate) fn set_on_outcome<F: FnMut(&S, &Outcome<P>) + 'static>(&mut self, on_outcome: F) {
+        self.on_outcome = Some(Box::new(on_outcome));
+    }
+
+    pub(crate) fn turn_start(&mut self, state: &S, player: &P) {
+        if let Some(on_turn_start) = &mut self.on_turn_start {
+            on_turn_start(state, player);
+        }
+    }
+
+    /// reports the action along with the state it led to, and the outcome if it ended the game
+    pub(crate) fn outcome(&mut self, state: &S, outcome: &Outcome<P>) {
+        if let Some(on_outcome) = &mut self.on_outcome {
+            on_outcome(state, outcome);
+        }
+    }
+
+    pub(crate) fn action_applied(&mut self, state: &S, actor: &P, action: &A, outcome: Option<Outcome<P>>) {
+        if let Some(on_action_applied) = &mut self.on_action_applied {
+            on_action_applied(state, actor, action);
+        }
+
+        if let Some(outcome) = outcome {
+            self.outcome(state, &outcome);
+        }
+    }
+}
+
+/// An agent's action, if it decided on one, along with its estimate of the action's value.
+pub(crate) type Decided<A> = (Option<A>, Option<ScoreValue>);
+
+/// The decisions a runner makes for some players itself rather than through their `Agent`s, e.g.
+/// `MultithreadedInformationSetGame` searching for its `MtAgent`s, along with whatever it keeps up
+/// to date as the game is played. This is all the runners differ in; `Game` has none.
+pub(crate) trait Seats<R, S, A, P> where R: Rng, S: State<A, P>, A: Action + Describe, P: Player + Describe {
+    /// decides for `player` if they're seated here, by `deadline` if they're on a clock
+    fn decide(&mut self, _rng: &mut R, _state: &S, _history: &[(P, A)], _player: &P, _deadline: Option<Instant>) -> Result<Option<Decided<A>>, GameError<A, P>> {
+        Ok(None)
+    }
+
+    /// follows `action` being played, which led from `previous` to `state`
+    fn played(&mut self, _rng: &mut R, _previous: &S, _state: &S, _action: &A) {}
+
+    /// forgets whatever followed from the game before it was restored to `state`
+    fn restored(&mut self, _rng: &mut R, _state: &S) {}
+}
+
+impl<R, S, A, P> Seats<R, S, A, P> for () where R: Rng, S: State<A, P>, A: Action + Describe, P: Player + Describe {}
+
+/// The turn loop the game runners share: their agents of any kind and policies, nature's turns,
+/// clocks, resignations, history and hooks. The runners keep the state and rng as fields of their
+/// own, and pass them in along with their `Seats`.
+pub(crate) struct Runner<R, S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    pub(crate) agents: HashMap<P, Box<dyn Agent<R, S, A, P>>>,
+    pub(crate) policies: HashMap<P, Box<dyn Policy<R, S, A, P>>>,
+    pub(crate) history: Vec<(P, A)>,
+    pub(crate) hooks: Hooks<S, A, P>,
+    pub(crate) recording: Option<Recording>,
+    pub(crate) clocks: HashMap<P, TimeControl>,
+    pub(crate) resignations: Resignations<P>,
+}
+
+impl<R, S, A, P> Runner<R, S, A, P>
+    where
+        R: Rng,
+        S: State<A, P>,
+        A: Action + Describe,
+        P: Player + Describe,
+{
+    pub(crate) fn new(agents: HashMap<P, Box<dyn Agent<R, S, A, P>>>) -> Self {
+        Self {
+            agents,
+            policies: HashMap::new(),
+            history: Vec::new(),
+            hooks: Hooks::default(),
+            recording: None,
+            clocks: HashMap::new(),
+            resignations: Resignations::default(),
+        }
+    }
+
+    pub(crate) fn replay(&self) -> Replay<A, P> {
+        Replay {
+            seed: self.recording.as_ref().map(|recording| recording.seed),
+            state_id: self.recording.as_ref().map(|recording| recording.state_id.clone()),
+            actions: self.history.clone(),
+        }
+    }
+
+    pub(crate) fn run<T: Seats<R, S, A, P>>(&mut self, state: &mut S, rng: &mut R, seats: &mut T) -> Result<Outcome<P>, GameError<A, P>> {
+        loop {
+            if let Some(outcome) = self.outcome(state) {
+                return Ok(outcome);
+            }
+
+            match self.step(state, rng, seats) {
+                Ok(_) | Err(GameError::Resigned(_)) => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    pub(crate) fn step<T: Seats<R, S, A, P>>(&mut self, state: &mut S, rng: &mut R, seats: &mut T) -> Result<A, GameError<A, P>> {
+        if let Some(resigner) = self.resignations.resigned() {
+            return Err(GameError::Resigned(resigner.clone()));
+        }
+
+        let current_player = state.current_player();
+        self.hooks.turn_start(state, &current_player);
+
+        let started = Instant::now();
+        let deadline = self.clocks.get(&current_player).map(|clock| started + clock.remaining);
+        let decided = match seats.decide(rng, state, &self.history, &current_player, deadline)? {
+            Some(decided) => Some(decided),
+            None => self.agents.get(&current_player).map(|agent| match self.clocks.get(&current_player) {
+                Some(clock) => (agent.decide_within(rng, state, clock.remaining), None),
+                None if self.resignations.is_enabled() => match agent.decide_in_detail(rng, state) {
+                    Some(decision) => (Some(decision.action), decision.value),
+                    None => (None, None),
+                },
+                None => (agent.decide(rng, state), None),
+            }),
+        };
+
+        let action = if let Some((action, value)) = decided {
+            self.charge_clock(&current_player, started)?;
+
+            let Some(action) = action else {
+                return Err(GameError::AgentDecisionError(current_player))
+            };
+
+            if self.resignations.decided(&current_player, value) {
+                self.hooks.outcome(state, &Outcome::Resignation(current_player.clone()));
+                return Err(GameError::Resigned(current_player));
+            }
+
+            action
+        } else if let Some(policy) = self.policies.get_mut(&current_player) {
+            let Some(action) = policy.decide(rng, state) else {
+                return Err(GameError::PolicyDecisionError(current_player))
+            };
+
+            action
+        } else if let Some(action) = nature_action(state, &current_player, rng) {
+            action
+        } else {
+            return Err(GameError::NoAgentForPlayer(current_player))
+        };
+
+        self.play(state, rng, seats, action.clone())?;
+
+        Ok(action)
+    }
+
+    /// charges the decision `player`'s agent started on at `started` to its clock, if it has one
+    fn charge_clock(&mut self, player: &P, started: Instant) -> Result<(), GameError<A, P>> {
+        let forfeited = self.clocks.get_mut(player).is_some_and(|clock| !clock.charge(started));
+        match forfeited {
+            true => Err(GameError::TimeForfeit(player.clone())),
+            false => Ok(()),
+        }
+    }
+
+    pub(crate) fn step_with_action<T: Seats<R, S, A, P>>(&mut self, state: &mut S, rng: &mut R, seats: &mut T, action: A) -> Result<(), GameError<A, P>> {
+        let mut actions = state.actions();
+        add_pass_action(state, &mut actions);
+        if self.outcome(state).is_some() || !actions.contains(&action) {
+            return Err(GameError::IllegalAction(action));
+        }
+
+        self.hooks.turn_start(state, &state.current_player());
+        self.play(state, rng, seats, action)
+    }
+
+    pub(crate) fn play<T: Seats<R, S, A, P>>(&mut self, state: &mut S, rng: &mut R, seats: &mut T, action: A) -> Result<(), GameError<A, P>> {
+        let actor = state.current_player();
+        let next = match &mut self.recording {
+            Some(recording) => transition(state, &mut recording.rng, &action),
+            None => transition(state, rng, &action),
+        };
+        let Ok(next) = next else {
+            return Err(GameError::ActionApplicationError(action))
+        };
+        let previous = mem::replace(state, next);
+
+        seats.played(rng, &previous, state, &action);
+        self.hooks.action_applied(state, &actor, &action, state.outcome());
+        self.history.push((actor, action));
+
+        Ok(())
+    }
+
+    pub(crate) fn restore<T: Seats<R, S, A, P>>(&mut self, current: &mut S, rng: &mut R, seats: &mut T, state: S, history: Vec<(P, A)>) {
+        for policy in self.policies.values_mut() {
+            policy.restore(&state, &history);
+        }
+
+        seats.restored(rng, &state);
+        *current = state;
+        self.history = history;
+        self.resignations.clear();
+    }
+
+    pub(crate) fn outcome(&self, state: &S) -> Option<Outcome<P>> {
+        match self.resignations.resigned() {
+            Some(resigner) => Some(Outcome::Resignation(resigner.clone())),
+            None => state.outcome(),
+        }
+    }
+}
+
+/// on a chance player's turn which has no agent or policy, samples nature's action by the state's
+/// priors
+fn nature_action<R: Rng, S: State<A, P>, A: Action, P: Player>(state: &S, player: &P, rng: &mut R) -> Option<A> {
+    if !player.is_chance() {
+        return None;
+    }
+
+    let mut actions = state.actions();
+    add_pass_action(state, &mut actions);
+    if actions.is_empty() {
+        return None;
+    }
+
+    let action_idx = nature_choice(state, &actions, rng);
+    Some(actions.swap_remove(action_idx))
+}
+
+/// A perfect information game played out by agents of any kind, the counterpart of
+/// `MultithreadedInformationSetGame` for games without hidden information.
+pub struct Game<R, S, A, P>
+    where
+        R: Rng,
+        S: State<A, P>,
+        A: Action,
+        P: Player,
+{
+    pub state: S,
+    pub rng: R,
+    runner: Runner<R, S, A, P>,
+}
+
+impl<R, S, A, P> Game<R, S, A, P>
+    where
+        R: Rng,
+        S: State<A, P>,
+        A: Action + Describe,
+        P: Player + Describe,
+{
+    pub fn new(rng: R, state: S, agents: HashMap<P, Box<dyn Agent<R, S, A, P>>>) -> Self {
+        Self {
+            state,
+            rng,
+            runner: Runner::new(agents),
+        }
+    }
+
+    /// Has `policy` decide for `player` rather than a searching agent. An agent mapped to the same
+    /// player takes precedence.
+    pub fn with_policy<T: Policy<R, S, A, P> + 'static>(mut self, player: P, policy: T) -> Self {
+        self.runner.policies.insert(player, Box::new(policy));
+        self
+    }
+
+    /// Calls `on_turn_start` with the state and the player to move, before the player's agent or
+    /// policy decides what to do.
+    pub fn with_on_turn_start<F: FnMut(&S, &P) + 'static>(mut self, on_turn_start: F) -> Self {
+        self.runner.hooks.set_on_turn_start(on_turn_start);
+        self
+    }
+
+    /// Calls `on_action_applied` with the state an action led to, the player who played it and
+    /// the action, after each action is played.
+    pub fn with_on_action_applied<F: FnMut(&S, &P, &A) + 'static>(mut self, on_action_applied: F) -> Self {
+        self.runner.hooks.set_on_action_applied(on_action_applied);
+        self
+    }
+
+    /// Calls `on_outcome` with the final state and the outcome once an action ends the game, or an
+    /// agent resigns.
+    pub fn with_on_outcome<F: FnMut(&S, &Outcome<P>) + 'static>(mut self, on_outcome: F) -> Self {
+        self.runner.hooks.set_on_outcome(on_outcome);
+        self
+    }
+
+    /// Puts `player`'s agent on a clock, which each of its decisions is charged to. Agents are
+    /// told the time they have left through `Agent::decide_within`.
+    pub fn with_time_control(mut self, player: P, time_control: TimeControl) -> Self {
+        self.runner.clocks.insert(player, time_control);
+        self
+    }
+
+    /// the time control of `player`'s agent, with the time it has left
+    pub fn time_control(&self, player: &P) -> Option<&TimeControl> {
+        self.runner.clocks.get(player)
+    }
+
+    /// Lets the agents resign lost games, which ends the game with an `Outcome::Resignation`.
+    /// Agents on a clock decide within their budget instead, and don't resign.
+    pub fn with_resignation(mut self, resignation: Resignation) -> Self {
+        self.runner.resignations.set(resignation);
+        self
+    }
+
+    /// Records the game for replaying, drawing its random transitions from an rng seeded with
+    /// `seed` rather than the game's own, which the agents draw from too. `state_id` identifies
+    /// the initial state to whoever replays it. A game moved to another state with `restore`
+    /// doesn't replay its random transitions as they happened.
+    pub fn with_recording<I: Into<String>>(mut self, seed: u64, state_id: I) -> Self {
+        self.runner.recording = Some(Recording::new(seed, state_id.into()));
+        self
+    }
+
+    /// The game so far as a replay, which reproduces its random transitions if it was recorded
+    /// with `with_recording`.
+    pub fn replay(&self) -> Replay<A, P> {
+        self.runner.replay()
+    }
+
+    /// every action played so far, oldest first, along with the player who played it
+    pub fn history(&self) -> &[(P, A)] {
+        &self.runner.history
+    }
+
+    /// Steps through the game until it's over, returning its outcome.
+    pub fn run(&mut self) -> Result<Outcome<P>, GameError<A, P>> {
+        self.runner.run(&mut self.state, &mut self.rng, &mut ())
+    }
+
+    /// Has the current player's agent or policy decide on an action and plays it. An agent which
+    /// resigns instead ends the game without playing anything.
+    pub fn step(&mut self) -> Result<A, GameError<A, P>> {
+        self.runner.step(&mut self.state, &mut self.rng, &mut ())
+    }
+
+    /// Plays the current player's move decided outside of the game, e.g. by a network opponent or
+    /// in a GUI, in place of their agent or policy. Unlike `play` the action is checked to be
+    /// legal first.
+    pub fn step_with_action(&mut self, action: A) -> Result<(), GameError<A, P>> {
+        self.runner.step_with_action(&mut self.state, &mut self.rng, &mut (), action)
+    }
+
+    /// Plays an action decided outside of the game's agents and policies, e.g. by a human. Any
+    /// chance events it leads to are resolved right away.
+    pub fn play(&mut self, action: A) -> Result<(), GameError<A, P>> {
+        self.runner.play(&mut self.state, &mut self.rng, &mut (), action)
+    }
+
+    /// Moves the game to `state`, reached by playing `history`, e.g. to undo a move. Each policy
+    /// is given the chance to invalidate whatever it had cached about the game.
+    pub fn restore(&mut self, state: S, history: Vec<(P, A)>) {
+        self.runner.restore(&mut self.state, &mut self.rng, &mut (), state, history);
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.outcome().is_some()
+    }
+
+    /// the state's outcome, or the resignation of whoever resigned
+    pub fn outcome(&self) -> Option<Outcome<P>> {
+        self.runner.outcome(&self.state)
+    }
+}
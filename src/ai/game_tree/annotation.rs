@@ -0,0 +1,26 @@
+use crate::Action;
+
+/// A label attached to an action in the search results, for front ends to render.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Annotation {
+    /// the only legal action
+    OnlyMove,
+    /// the action immediately wins the game for the player taking it
+    ProvenWin,
+    /// the action immediately loses the game for the player taking it
+    ProvenLoss,
+    /// the action received the overwhelming majority of the search's visits
+    Dominant,
+    /// the action was played from an opening book
+    BookMove,
+    /// any label the game wants to attach, e.g. "sacrifice"
+    Custom(String),
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnnotatedAction<A> where A: Action {
+    pub action: A,
+    pub num_visits: u32,
+    pub annotations: Vec<Annotation>,
+}
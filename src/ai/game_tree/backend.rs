@@ -0,0 +1,205 @@
+use std::ops::Range;
+use petgraph::prelude::*;
+
+/// The storage a `GameTree` keeps its nodes and edges in.
+///
+/// Nodes are only ever added as children of an existing node (besides the root), so every backend
+/// holds a tree rather than a general graph.
+pub trait TreeBackend<N, E>: Default {
+    fn add_root(&mut self, node: N) -> NodeIndex;
+    fn add_child(&mut self, parent_idx: NodeIndex, edge: E, node: N) -> (EdgeIndex, NodeIndex);
+
+    fn node(&self, node_idx: NodeIndex) -> Option<&N>;
+    fn node_mut(&mut self, node_idx: NodeIndex) -> Option<&mut N>;
+    fn edge(&self, edge_idx: EdgeIndex) -> Option<&E>;
+    fn edge_mut(&mut self, edge_idx: EdgeIndex) -> Option<&mut E>;
+    fn edge_target(&self, edge_idx: EdgeIndex) -> Option<NodeIndex>;
+
+    /// A node's child edges, in no particular order. Edge indices grow as children are added, so
+    /// sorting them recovers the order the children were added in.
+    fn child_edges(&self, node_idx: NodeIndex) -> impl Iterator<Item = EdgeIndex> + '_;
+    fn num_children(&self, node_idx: NodeIndex) -> usize;
+    fn node_count(&self) -> usize;
+
+    /// Removes every node and edge while keeping the allocated capacity.
+    fn clear(&mut self);
+}
+
+/// Stores the tree in a petgraph `Graph`.
+pub struct GraphBackend<N, E> {
+    graph: Graph<N, E, Directed>,
+}
+
+impl<N, E> GraphBackend<N, E> {
+    pub fn graph(&self) -> &Graph<N, E, Directed> {
+        &self.graph
+    }
+}
+
+impl<N, E> Default for GraphBackend<N, E> {
+    fn default() -> Self {
+        Self {
+            graph: Graph::new(),
+        }
+    }
+}
+
+impl<N, E> TreeBackend<N, E> for GraphBackend<N, E> {
+    fn add_root(&mut self, node: N) -> NodeIndex {
+        self.graph.add_node(node)
+    }
+
+    fn add_child(&mut self, parent_idx: NodeIndex, edge: E, node: N) -> (EdgeIndex, NodeIndex) {
+        let node_idx = self.graph.add_node(node);
+        let edge_idx = self.graph.add_edge(parent_idx, node_idx, edge);
+        (edge_idx, node_idx)
+    }
+
+    fn node(&self, node_idx: NodeIndex) -> Option<&N> {
+        self.graph.node_weight(node_idx)
+    }
+
+    fn node_mut(&mut self, node_idx: NodeIndex) -> Option<&mut N> {
+        self.graph.node_weight_mut(node_idx)
+    }
+
+    fn edge(&self, edge_idx: EdgeIndex) -> Option<&E> {
+        self.graph.edge_weight(edge_idx)
+    }
+
+    fn edge_mut(&mut self, edge_idx: EdgeIndex) -> Option<&mut E> {
+        self.graph.edge_weight_mut(edge_idx)
+    }
+
+    fn edge_target(&self, edge_idx: EdgeIndex) -> Option<NodeIndex> {
+        self.graph.edge_endpoints(edge_idx).map(|(_, target)| target)
+    }
+
+    fn child_edges(&self, node_idx: NodeIndex) -> impl Iterator<Item = EdgeIndex> + '_ {
+        // petgraph walks a node's edges newest first
+        self.graph
+            .edges_directed(
+                node_idx,
+                Outgoing,
+            )
+            .map(|edge| edge.id())
+    }
+
+    fn num_children(&self, node_idx: NodeIndex) -> usize {
+        self.graph.edges_directed(node_idx, Outgoing).count()
+    }
+
+    fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    fn clear(&mut self) {
+        self.graph.clear();
+    }
+}
+
+struct ArenaNode<N, E> {
+    weight: N,
+    /// the edge leading into this node, every node but the root has exactly one
+    edge: Option<E>,
+    /// the range of `ArenaBackend::children` holding this node's children
+    children: Range<u32>,
+}
+
+/// Stores the tree in flat vectors, without the per-edge bookkeeping of a general graph.
+///
+/// Each node owns the edge leading into it, so an edge shares its index with its target node.
+/// Children are kept in a contiguous range of a shared vector. Expanding a node all at once keeps
+/// that range at the end of the vector, otherwise it's moved there before it grows.
+pub struct ArenaBackend<N, E> {
+    nodes: Vec<ArenaNode<N, E>>,
+    children: Vec<NodeIndex>,
+}
+
+impl<N, E> Default for ArenaBackend<N, E> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+}
+
+impl<N, E> TreeBackend<N, E> for ArenaBackend<N, E> {
+    fn add_root(&mut self, node: N) -> NodeIndex {
+        self.nodes.push(ArenaNode {
+            weight: node,
+            edge: None,
+            children: 0..0,
+        });
+
+        NodeIndex::new(self.nodes.len() - 1)
+    }
+
+    fn add_child(&mut self, parent_idx: NodeIndex, edge: E, node: N) -> (EdgeIndex, NodeIndex) {
+        let node_idx = NodeIndex::new(self.nodes.len());
+        self.nodes.push(ArenaNode {
+            weight: node,
+            edge: Some(edge),
+            children: 0..0,
+        });
+
+        let end = self.children.len() as u32;
+        let parent = &mut self.nodes[parent_idx.index()];
+        if parent.children.is_empty() {
+            parent.children = end..end;
+        } else if parent.children.end != end {
+            // move the existing children to the end so the range can grow
+            let existing = parent.children.start as usize..parent.children.end as usize;
+            parent.children = end..end + existing.len() as u32;
+            self.children.extend_from_within(existing);
+        }
+
+        let parent = &mut self.nodes[parent_idx.index()];
+        parent.children.end += 1;
+        self.children.push(node_idx);
+
+        (EdgeIndex::new(node_idx.index()), node_idx)
+    }
+
+    fn node(&self, node_idx: NodeIndex) -> Option<&N> {
+        self.nodes.get(node_idx.index()).map(|node| &node.weight)
+    }
+
+    fn node_mut(&mut self, node_idx: NodeIndex) -> Option<&mut N> {
+        self.nodes.get_mut(node_idx.index()).map(|node| &mut node.weight)
+    }
+
+    fn edge(&self, edge_idx: EdgeIndex) -> Option<&E> {
+        self.nodes.get(edge_idx.index())?.edge.as_ref()
+    }
+
+    fn edge_mut(&mut self, edge_idx: EdgeIndex) -> Option<&mut E> {
+        self.nodes.get_mut(edge_idx.index())?.edge.as_mut()
+    }
+
+    fn edge_target(&self, edge_idx: EdgeIndex) -> Option<NodeIndex> {
+        self.nodes.get(edge_idx.index())?.edge.as_ref()?;
+        Some(NodeIndex::new(edge_idx.index()))
+    }
+
+    fn child_edges(&self, node_idx: NodeIndex) -> impl Iterator<Item = EdgeIndex> + '_ {
+        let children = self.nodes[node_idx.index()].children.clone();
+        self.children[children.start as usize..children.end as usize]
+            .iter()
+            .map(|child_idx| EdgeIndex::new(child_idx.index()))
+    }
+
+    fn num_children(&self, node_idx: NodeIndex) -> usize {
+        self.nodes[node_idx.index()].children.len()
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.children.clear();
+    }
+}
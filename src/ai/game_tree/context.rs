@@ -0,0 +1,24 @@
+use petgraph::graph::EdgeIndex;
+
+/// Scratch buffers which the search's hot loops reuse rather than collecting fresh `Vec`s on every
+/// call. A `GameTree` keeps its own for expansion, selection and rollouts, and one can be handed to
+/// `random_rollout_with_context` to reuse it across playouts elsewhere.
+pub struct SearchContext<A> {
+    pub(crate) actions: Vec<A>,
+    pub(crate) edges: Vec<EdgeIndex>,
+}
+
+impl<A> SearchContext<A> {
+    pub fn new() -> Self {
+        Self {
+            actions: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+}
+
+impl<A> Default for SearchContext<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
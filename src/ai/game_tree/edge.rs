@@ -1,15 +1,46 @@
-use crate::Action;
+use std::collections::HashMap;
+use crate::{Action, Player};
+use crate::ai::game_tree::annotation::Annotation;
+use crate::ai::game_tree::score::ScoreValue;
 
-pub struct GameTreeEdge<A> where A: Action {
+/// A state-action pair, holding the statistics gathered for taking `action` from the source node.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameTreeEdge<A, P> where A: Action, P: Player {
     pub action: A,
     pub num_visits: u32,
+    pub scores: HashMap<P, ScoreValue>,
+    /// annotations attached from outside the search, e.g. book hits
+    pub annotations: Vec<Annotation>,
+    /// the probability of the outcome the edge leads to, when its source is a chance node, whose
+    /// outcome edges all carry the action which led to it
+    pub probability: Option<ScoreValue>,
+    /// the target's heuristic value for the player choosing the edge, which progressive bias
+    /// weighs into selection
+    pub bias: ScoreValue,
+    /// the action's share of the priors of the actions expanded along with it, which PUCT
+    /// selection explores by
+    pub prior: ScoreValue,
 }
 
-impl<A> GameTreeEdge<A> where A: Action {
+impl<A, P> GameTreeEdge<A, P> where A: Action, P: Player {
     pub fn new(action: A) -> Self {
         Self {
             action,
-            num_visits: 1,
+            num_visits: 0,
+            scores: Default::default(),
+            annotations: Vec::new(),
+            probability: None,
+            bias: 0.0,
+            prior: 0.0,
         }
     }
-}
\ No newline at end of file
+
+    pub fn get_player_score(&self, player: P) -> ScoreValue {
+        if let Some(value) = self.scores.get(&player) {
+            *value
+        } else {
+            0.0
+        }
+    }
+}
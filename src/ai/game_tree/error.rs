@@ -0,0 +1,86 @@
+use std::fmt::Debug;
+use petgraph::graph::NodeIndex;
+use thiserror::Error;
+use crate::Describe;
+
+/// Why a search stopped. `E` is the searched state's own error and `A` the searched action, which
+/// ISMCTS searches, whose determinizations are searched apart, report formatted as `String`s.
+#[derive(Error, Debug)]
+pub enum SearchError<E: Debug = String, A = String> {
+    #[error("a non-terminal state has no actions to expand into")]
+    NoActions,
+
+    #[error("a state offers the same action more than once")]
+    DuplicateAction,
+
+    #[error("inconsistent outcome: {0}")]
+    InconsistentOutcome(&'static str),
+
+    #[error("the root is a chance node, which has no decision to search")]
+    ChanceRoot,
+
+    #[error("the search found no action to play, as the root is terminal or wasn't searched")]
+    NoDecision,
+
+    #[error("a player without a tree moved during a multi-observer search, pass every player to MultiObserverTrees::new")]
+    NoObserverTree,
+
+    #[error("applying an action failed: {error:?}")]
+    State {
+        error: E,
+        /// the node whose state the action was applied to, or which the failing rollout started
+        /// from, see `GameTree::node_state`, or None for a determinization of an information set
+        /// search
+        node: Option<NodeIndex>,
+        /// the failing action, or None when it was applied during a rollout
+        action: Option<A>,
+    },
+}
+
+impl<E: Debug, A> SearchError<E, A> {
+    pub(crate) fn state(error: E) -> Self {
+        SearchError::State {
+            error,
+            node: None,
+            action: None,
+        }
+    }
+
+    /// the error of applying `action` outside of a tree's nodes
+    pub(crate) fn applying(error: E, action: A) -> Self {
+        SearchError::State {
+            error,
+            node: None,
+            action: Some(action),
+        }
+    }
+
+    /// Formats the state's error with `Debug`, e.g. to report the errors of searches over
+    /// different state types alike. The failing action is only kept by `into_described`, for
+    /// actions which can be described.
+    pub fn into_formatted(self) -> SearchError {
+        self.formatted_with(|_| None)
+    }
+
+    /// Like `into_formatted`, keeping the failing action's description.
+    pub fn into_described(self) -> SearchError where A: Describe {
+        self.formatted_with(|action| Some(action.describe()))
+    }
+
+    /// formats the state's error, and the failing action with `describe`
+    fn formatted_with<F: FnOnce(A) -> Option<String>>(self, describe: F) -> SearchError {
+        match self {
+            SearchError::NoActions => SearchError::NoActions,
+            SearchError::DuplicateAction => SearchError::DuplicateAction,
+            SearchError::InconsistentOutcome(problem) => SearchError::InconsistentOutcome(problem),
+            SearchError::ChanceRoot => SearchError::ChanceRoot,
+            SearchError::NoObserverTree => SearchError::NoObserverTree,
+            SearchError::NoDecision => SearchError::NoDecision,
+            SearchError::State { error, node, action } => SearchError::State {
+                error: format!("{error:?}"),
+                node,
+                action: action.and_then(describe),
+            },
+        }
+    }
+}
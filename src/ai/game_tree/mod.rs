@@ -1,45 +1,496 @@
 pub mod node;
 pub mod edge;
 pub mod score;
+pub mod annotation;
+pub mod backend;
+pub mod error;
+pub mod summary;
+pub mod transposition;
+pub mod context;
 
-use petgraph::graph::EdgeReference;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::cmp::Reverse;
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
 use petgraph::prelude::*;
 use rand::Rng;
-use crate::{Action, Outcome, Player, State};
+use crate::{Action, Describe, Outcome, Player, State};
+use crate::ai::game_tree::annotation::{AnnotatedAction, Annotation};
+use crate::ai::game_tree::backend::{ArenaBackend, GraphBackend, TreeBackend};
+use crate::ai::game_tree::context::SearchContext;
 use crate::ai::game_tree::edge::GameTreeEdge;
+use crate::ai::game_tree::error::SearchError;
 use crate::ai::game_tree::node::GameTreeNode;
-use crate::ai::game_tree::score::Score;
-use crate::ai::random_rollout::random_rollout;
+use crate::ai::game_tree::score::{Score, ScoreValue};
+use crate::ai::game_tree::summary::{ActionSummary, SolverStatus, TreeSummary};
+use crate::ai::game_tree::transposition::{add_outcome, TranspositionEntry, TranspositionTable};
+use crate::ai::evaluator::Evaluator;
+use crate::ai::perspective::Perspective;
+use crate::ai::random_rollout::add_pass_action;
+use crate::ai::rollout::{weighted_choice, RandomRollout, RolloutPolicy};
+use crate::ai::strictness::{strictness, Strictness};
 
-pub struct GameTree<S, A, P> where S: State<A, P>, A: Action, P: Player {
+/// the share of the root's visits an action needs to be annotated as dominant
+const DOMINANT_VISIT_SHARE: ScoreValue = 0.9;
+
+/// Exponentially decays the statistics gathered by a tree, so in a tree which is retained and
+/// reused over a long game, early low quality simulations don't permanently anchor its values.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatisticsDecay {
+    /// the number of search iterations between each decay
+    pub interval: u32,
+    /// what the visits and scores are multiplied by at each decay
+    pub factor: ScoreValue,
+}
+
+/// How a `GameTree` credits an `Outcome::WinnerBy`, so that it prefers crushing wins and narrow
+/// losses. The margin is scaled by `full_margin` to between 0 and 1, then the winning team gets
+/// `1 - weight + weight * margin` and every other player of `State::players` gets
+/// `weight * (1 - margin)`. A draw then falls between the narrowest win and loss, crediting each
+/// drawing team with 0.5 rather than a full point.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarginScaling {
+    /// the share of a reward which depends on the margin, kept below 0.5 so the narrowest win is
+    /// still worth more than the narrowest loss
+    pub weight: ScoreValue,
+    /// the margin at and beyond which a win counts fully
+    pub full_margin: ScoreValue,
+}
+
+/// How a `GameTree` expands chance nodes, the states with `State::chance_outcomes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChanceExpansion {
+    /// a child for every outcome
+    #[default]
+    Enumerate,
+    /// a child for each of this many outcomes sampled by probability, which are then equally
+    /// likely, for chance events with too many outcomes to enumerate, e.g. shuffling a deck
+    Sample(usize),
+}
+
+/// What happened during a single search iteration, handed to `GameTree::search_with_observer`.
+pub struct IterationReport<'a, A, P> where A: Action, P: Player {
+    pub iteration: u32,
+    /// the number of edges traversed from the root to the node the rollout started from
+    pub path_length: usize,
+    pub outcome: &'a Outcome<P>,
+    pub best_action: Option<&'a A>,
+}
+
+/// A snapshot of a search in progress, sent by `GameTree::search_n_with_progress`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchProgress<A> where A: Action {
+    pub iterations: u32,
+    pub best_action: Option<A>,
+    /// the best action's average value for the player to move at the root
+    pub value: ScoreValue,
+}
+
+/// Returns the exact outcome of a state, if it's known without searching it, e.g. from an endgame
+/// tablebase or book.
+pub type ExactEvaluator<S, P> = Arc<dyn Fn(&S) -> Option<Outcome<P>> + Send + Sync>;
+
+/// Returns the best action of a state along with the outcome it leads to, if it's proven without
+/// searching, e.g. by an endgame tablebase or a brute force search of a small enough position.
+pub type EndgameSolver<S, A, P> = Arc<dyn Fn(&S) -> Option<(A, Outcome<P>)> + Send + Sync>;
+
+/// A `GameTree` stored in flat vectors rather than a petgraph `Graph`.
+pub type ArenaGameTree<S, A, P> = GameTree<S, A, P, ArenaBackend<GameTreeNode<S, A, P>, GameTreeEdge<A, P>>>;
+
+pub struct GameTree<S, A, P, B = GraphBackend<GameTreeNode<S, A, P>, GameTreeEdge<A, P>>>
+    where
+        S: State<A, P>,
+        A: Action,
+        P: Player,
+        B: TreeBackend<GameTreeNode<S, A, P>, GameTreeEdge<A, P>>,
+{
     root_node_idx: NodeIndex,
-    graph: Graph<GameTreeNode<S, A, P>, GameTreeEdge<A>, Directed>,
-    constant_of_exploration: f32,
+    backend: B,
+    constant_of_exploration: ScoreValue,
+    tie_breaking_noise: Option<ScoreValue>,
+    decay: Option<StatisticsDecay>,
+    iterations_since_decay: u32,
+    max_depth: usize,
+    exact_evaluator: Option<ExactEvaluator<S, P>>,
+    rollout_policy: Arc<dyn RolloutPolicy<S, A, P>>,
+    expansion_cap: Option<usize>,
+    transpositions: Option<TranspositionTable<P>>,
+    chance_expansion: ChanceExpansion,
+    context: SearchContext<A>,
+    progressive_bias: Option<ScoreValue>,
+    puct: Option<ScoreValue>,
+    margin_scaling: Option<MarginScaling>,
+    evaluator: Option<Arc<dyn Evaluator<S, A, P>>>,
+    /// the evaluator's values of the leaf expanded last, which the search backs up from it
+    leaf_values: Option<Vec<(P, ScoreValue)>>,
+    endgame_solver: Option<EndgameSolver<S, A, P>>,
+    /// the root's proven best action and outcome, once the endgame solver has been consulted
+    solution: Option<(A, Outcome<P>)>,
+    solver_consulted: bool,
+    _phantom_data: PhantomData<(S, A, P)>,
 }
 
 impl<S, A, P> GameTree<S, A, P> where S: State<A, P>, A: Action, P: Player + 'static {
     pub fn new(state: S) -> Self {
-        let mut graph: Graph<GameTreeNode<S, A, P>, GameTreeEdge<A>, Directed> = Graph::new();
-        let root_node_idx = graph.add_node(GameTreeNode::new(state));
+        Self::with_backend(state)
+    }
+
+    pub fn graph(&self) -> &Graph<GameTreeNode<S, A, P>, GameTreeEdge<A, P>, Directed> {
+        self.backend.graph()
+    }
+}
+
+impl<S, A, P, B> GameTree<S, A, P, B>
+    where
+        S: State<A, P>,
+        A: Action,
+        P: Player + 'static,
+        B: TreeBackend<GameTreeNode<S, A, P>, GameTreeEdge<A, P>>,
+{
+    /// Creates a tree stored in the backend `B`, e.g. `ArenaGameTree::with_backend(state)`.
+    pub fn with_backend(state: S) -> Self {
+        let mut backend = B::default();
+        let root_node_idx = backend.add_root(GameTreeNode::new(state));
         Self {
             root_node_idx,
-            graph,
-            constant_of_exploration: 2f32.sqrt(),
+            backend,
+            constant_of_exploration: ScoreValue::sqrt(2.0),
+            tie_breaking_noise: None,
+            decay: None,
+            iterations_since_decay: 0,
+            max_depth: 0,
+            exact_evaluator: None,
+            rollout_policy: Arc::new(RandomRollout::new()),
+            expansion_cap: None,
+            transpositions: None,
+            chance_expansion: ChanceExpansion::default(),
+            context: SearchContext::new(),
+            progressive_bias: None,
+            puct: None,
+            margin_scaling: None,
+            evaluator: None,
+            leaf_values: None,
+            endgame_solver: None,
+            solution: None,
+            solver_consulted: false,
+            _phantom_data: Default::default(),
         }
     }
 
-    pub fn graph(&self) -> &Graph<GameTreeNode<S, A, P>, GameTreeEdge<A>, Directed> {
-        &self.graph
+    /// Discards the whole tree and starts over from `state`, keeping the memory allocated for the
+    /// previous tree so it can be reused turn after turn.
+    pub fn reset(&mut self, state: S) {
+        self.backend.clear();
+        self.root_node_idx = self.backend.add_root(GameTreeNode::new(state));
+        self.max_depth = 0;
+        self.forget_solution();
     }
 
+    /// Makes the child reached by `action` the new root, keeping the statistics already gathered
+    /// for its subtree and discarding the rest of the tree. Returns false, leaving the tree
+    /// untouched, if the root has no such action.
+    pub fn advance_root(&mut self, action: &A) -> bool {
+        let Some(edge_idx) = self.root_child_edge(action) else {
+            return false;
+        };
 
-    fn select(&self, node_idx: NodeIndex, perspective_player: P) -> NodeIndex {
-        let children = self.node_children(node_idx);
+        let old_root_idx = self.edge_target(edge_idx);
+        let mut backend = B::default();
+        let mut new_root = self.get_node(old_root_idx).clone();
+        new_root.parent = None;
+        let new_root_idx = backend.add_root(new_root);
 
-        let selected = children.iter().fold((None, f32::MIN), |acc, child_idx| {
-            let ucb = self.ucbt_value(*child_idx, perspective_player);
-            if ucb > acc.1 {
-                (Some(*child_idx), ucb)
+        let mut stack = vec![(old_root_idx, new_root_idx)];
+        while let Some((old_node_idx, new_node_idx)) = stack.pop() {
+            for old_edge_idx in self.node_child_edges(old_node_idx) {
+                let old_child_idx = self.edge_target(old_edge_idx);
+                let edge = self.get_edge(old_edge_idx).clone();
+                let child = self.get_node(old_child_idx).clone();
+
+                let (new_edge_idx, new_child_idx) = backend.add_child(new_node_idx, edge, child);
+                backend.node_mut(new_child_idx).unwrap().parent = Some((new_node_idx, new_edge_idx));
+
+                stack.push((old_child_idx, new_child_idx));
+            }
+        }
+
+        self.backend = backend;
+        self.root_node_idx = new_root_idx;
+        self.max_depth = self.max_depth.saturating_sub(1);
+        self.forget_solution();
+        true
+    }
+
+    /// the solver is consulted again about a new root
+    fn forget_solution(&mut self) {
+        self.solution = None;
+        self.solver_consulted = false;
+    }
+
+    /// Adds the statistics of `other` into this tree, matching nodes by the path of actions
+    /// leading to them from the root. Parts of `other` which this tree hasn't explored are copied
+    /// over. Both trees are expected to be rooted at the same state, e.g. when aggregating
+    /// root-parallel workers or searches run on other machines.
+    pub fn merge<B2: TreeBackend<GameTreeNode<S, A, P>, GameTreeEdge<A, P>>>(&mut self, other: &GameTree<S, A, P, B2>) {
+        self.get_node_mut(self.root_node_idx).num_visits += other.get_node(other.root_node_idx).num_visits;
+        self.max_depth = self.max_depth.max(other.max_depth);
+
+        let mut stack = vec![(self.root_node_idx, other.root_node_idx)];
+        while let Some((node_idx, other_node_idx)) = stack.pop() {
+            for other_edge_idx in other.node_child_edges(other_node_idx) {
+                let other_edge = other.get_edge(other_edge_idx);
+                let other_child_idx = other.edge_target(other_edge_idx);
+
+                let matching_edge_idx = self
+                    .node_child_edges(node_idx)
+                    .into_iter()
+                    .find(|edge_idx| self.get_edge(*edge_idx).action == other_edge.action);
+
+                let Some(edge_idx) = matching_edge_idx else {
+                    self.copy_subtree(other, other_edge_idx, node_idx);
+                    continue;
+                };
+
+                let edge = self.get_edge_mut(edge_idx);
+                edge.num_visits += other_edge.num_visits;
+                for (player, score) in &other_edge.scores {
+                    *edge.scores.entry(player.clone()).or_insert(0.0) += *score;
+                }
+
+                let child_idx = self.edge_target(edge_idx);
+                self.get_node_mut(child_idx).num_visits += other.get_node(other_child_idx).num_visits;
+
+                stack.push((child_idx, other_child_idx));
+            }
+        }
+    }
+
+    /// copies the edge `other_edge_idx` of `other`, and the subtree below it, under `parent_idx`
+    fn copy_subtree<B2: TreeBackend<GameTreeNode<S, A, P>, GameTreeEdge<A, P>>>(&mut self, other: &GameTree<S, A, P, B2>, other_edge_idx: EdgeIndex, parent_idx: NodeIndex) {
+        let other_child_idx = other.edge_target(other_edge_idx);
+        let child_idx = self.copy_child(other, other_edge_idx, parent_idx);
+
+        // children are copied a node at a time, which keeps them contiguous in an arena
+        let mut stack = vec![(other_child_idx, child_idx)];
+        while let Some((other_node_idx, node_idx)) = stack.pop() {
+            for other_edge_idx in other.node_child_edges(other_node_idx) {
+                let child_idx = self.copy_child(other, other_edge_idx, node_idx);
+                stack.push((other.edge_target(other_edge_idx), child_idx));
+            }
+        }
+    }
+
+    fn copy_child<B2: TreeBackend<GameTreeNode<S, A, P>, GameTreeEdge<A, P>>>(&mut self, other: &GameTree<S, A, P, B2>, other_edge_idx: EdgeIndex, parent_idx: NodeIndex) -> NodeIndex {
+        let edge = other.get_edge(other_edge_idx).clone();
+        let child = other.get_node(other.edge_target(other_edge_idx)).clone();
+
+        let (edge_idx, child_idx) = self.backend.add_child(parent_idx, edge, child);
+        self.get_node_mut(child_idx).parent = Some((parent_idx, edge_idx));
+        child_idx
+    }
+
+    pub fn root_state(&self) -> &S {
+        &self.get_node(self.root_node_idx).state
+    }
+
+    /// The state of the node `node`, e.g. one a `SearchError::State` reports.
+    pub fn node_state(&self, node: NodeIndex) -> Option<&S> {
+        self.try_get_node(node).map(|node| &node.state)
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.backend.node_count()
+    }
+
+    /// Sets the constant weighting exploration against exploitation in the UCB formula, which is
+    /// the square root of 2 by default.
+    pub fn with_constant_of_exploration(mut self, constant_of_exploration: ScoreValue) -> Self {
+        self.constant_of_exploration = constant_of_exploration;
+        self
+    }
+
+    pub fn with_statistics_decay(mut self, decay: StatisticsDecay) -> Self {
+        self.decay = Some(decay);
+        self
+    }
+
+    /// Scales the rewards of `Outcome::WinnerBy` by their margin, see `MarginScaling`. Without it
+    /// a win by any margin counts like an `Outcome::Winner`.
+    pub fn with_margin_scaling(mut self, scaling: MarginScaling) -> Self {
+        self.margin_scaling = Some(scaling);
+        self
+    }
+
+    /// Scales down the visits and scores of every node and edge by `factor`. Scores are scaled by
+    /// the same ratio as the (rounded) visits, so average values are unchanged while the weight
+    /// of everything searched so far shrinks relative to future simulations.
+    pub fn decay_statistics(&mut self, factor: ScoreValue) {
+        let decay_visits = |num_visits: u32| -> u32 {
+            if num_visits == 0 {
+                return 0;
+            }
+
+            ((num_visits as ScoreValue * factor).round() as u32).max(1)
+        };
+
+        let mut stack = vec![self.root_node_idx];
+        while let Some(node_idx) = stack.pop() {
+            let node = self.get_node_mut(node_idx);
+            node.num_visits = decay_visits(node.num_visits);
+
+            for edge_idx in self.node_child_edges(node_idx) {
+                let edge = self.get_edge_mut(edge_idx);
+                let num_visits = decay_visits(edge.num_visits);
+                if edge.num_visits > 0 {
+                    let ratio = num_visits as ScoreValue / edge.num_visits as ScoreValue;
+                    for score in edge.scores.values_mut() {
+                        *score *= ratio;
+                    }
+                }
+                edge.num_visits = num_visits;
+
+                stack.push(self.edge_target(edge_idx));
+            }
+        }
+    }
+
+    /// Adds up to `magnitude` of random noise (drawn from the rng passed to `search`) to each UCB
+    /// value, so equally valued children aren't always selected in the order they were inserted.
+    pub fn with_tie_breaking_noise(mut self, magnitude: ScoreValue) -> Self {
+        self.tie_breaking_noise = Some(magnitude);
+        self
+    }
+
+    /// Uses `evaluator` to value states without rollouts. A state it returns an outcome for is never
+    /// expanded, every visit to it back propagates that outcome instead, while states it returns
+    /// None for are searched as normal.
+    pub fn with_exact_evaluator<F: Fn(&S) -> Option<Outcome<P>> + Send + Sync + 'static>(mut self, evaluator: F) -> Self {
+        self.exact_evaluator = Some(Arc::new(evaluator));
+        self
+    }
+
+    /// Values newly expanded nodes by playing them out with `policy` rather than uniformly randomly.
+    pub fn with_rollout_policy<T: RolloutPolicy<S, A, P> + 'static>(mut self, policy: T) -> Self {
+        self.rollout_policy = Arc::new(policy);
+        self
+    }
+
+    /// Generates at most `max_actions` of a node's actions (through `State::actions_chunk`) each
+    /// time it's expanded, bounding the latency of a single iteration in states with very many
+    /// actions. A node is expanded further on each visit until all of its actions are generated.
+    pub fn with_expansion_cap(mut self, max_actions: usize) -> Self {
+        self.expansion_cap = Some(max_actions.max(1));
+        self
+    }
+
+    /// Shares statistics for states with a `State::transposition_key` through `table`. Selection
+    /// values a child by the table's statistics once they hold more visits than the tree's own,
+    /// while exploration is still driven by the tree's own visits.
+    pub fn with_transposition_table(mut self, table: TranspositionTable<P>) -> Self {
+        self.transpositions = Some(table);
+        self
+    }
+
+    pub fn with_chance_expansion(mut self, chance_expansion: ChanceExpansion) -> Self {
+        self.chance_expansion = chance_expansion;
+        self
+    }
+
+    /// Adds `weight` times an edge's `State::heuristic_value` to its selection value, divided by
+    /// its visits plus one, so the heuristic guides the search early on and the search's own
+    /// results take over as the edge is visited.
+    pub fn with_progressive_bias(mut self, weight: ScoreValue) -> Self {
+        self.progressive_bias = Some(weight);
+        self
+    }
+
+    /// Selects by PUCT rather than UCB1, exploring each edge in proportion to its
+    /// `State::action_priors` share, with `constant` in place of the constant of exploration.
+    /// Unvisited edges aren't all tried first, so a strong prior can be followed straight away.
+    pub fn with_puct(mut self, constant: ScoreValue) -> Self {
+        self.puct = Some(constant);
+        self
+    }
+
+    /// Consults `solver` about the root before searching it. Once it proves a best action the
+    /// searches are skipped, `best_action` returns the proven action and `summary` reports the
+    /// root as solved.
+    pub fn with_endgame_solver<F: Fn(&S) -> Option<(A, Outcome<P>)> + Send + Sync + 'static>(mut self, solver: F) -> Self {
+        self.endgame_solver = Some(Arc::new(solver));
+        self.forget_solution();
+        self
+    }
+
+    /// Judges states with `evaluator` rather than playing them out: a leaf's value is the
+    /// evaluator's, and its children are selected by PUCT with the evaluator's priors, with
+    /// `puct_constant` as in `with_puct`. The outcomes of chance nodes are still played out by
+    /// the rollout policy.
+    pub fn with_evaluator<T: Evaluator<S, A, P> + 'static>(mut self, evaluator: T, puct_constant: ScoreValue) -> Self {
+        self.evaluator = Some(Arc::new(evaluator));
+        self.puct = Some(puct_constant);
+        self
+    }
+
+    fn transposition(&self, node_idx: NodeIndex) -> Option<TranspositionEntry<P>> {
+        let table = self.transpositions.as_ref()?;
+        let key = self.get_node(node_idx).state.transposition_key()?;
+        table.get(&key)
+    }
+
+    fn exact_outcome(&self, node_idx: NodeIndex) -> Option<Outcome<P>> {
+        let evaluator = self.exact_evaluator.as_ref()?;
+        evaluator(&self.get_node(node_idx).state)
+    }
+
+    /// selects the edge which is best from the perspective of the player making the decision at
+    /// this node, so opponents are assumed to play their own best moves, and partners their team's
+    /// as wins are credited to the whole team
+    fn select<R: Rng>(&mut self, rng: &mut R, node_idx: NodeIndex) -> EdgeIndex {
+        let mut children = mem::take(&mut self.context.edges);
+        children.clear();
+        children.extend(self.backend.child_edges(node_idx));
+
+        let selected = self.select_from(rng, node_idx, &children);
+        self.context.edges = children;
+        selected
+    }
+
+    fn select_from<R: Rng>(&self, rng: &mut R, node_idx: NodeIndex, children: &[EdgeIndex]) -> EdgeIndex {
+        if children.first().is_some_and(|edge_idx| self.get_edge(*edge_idx).probability.is_some()) {
+            return self.select_chance_outcome(children);
+        }
+
+        let node = self.get_node(node_idx);
+        let perspective_player = node.state.current_player();
+        if perspective_player.is_chance() {
+            // nature doesn't maximize anything, its actions are sampled by their priors, taken in
+            // the order they were added so the sample doesn't depend on the backend
+            let mut outcomes = children.to_vec();
+            outcomes.sort_unstable();
+            let priors: Vec<ScoreValue> = outcomes.iter().map(|edge_idx| self.get_edge(*edge_idx).prior).collect();
+            return outcomes[weighted_choice(&priors, rng)];
+        }
+
+        let selected = children.iter().fold((None, ScoreValue::MIN, 0.0), |acc, edge_idx| {
+            // a small amount of noise helps to avoid ties
+            let noise = match self.tie_breaking_noise {
+                Some(magnitude) => rng.gen::<ScoreValue>() * magnitude,
+                None => 0.0,
+            };
+
+            // unvisited edges are all valued at the maximum, so the noise alone decides among them,
+            // and without noise the oldest edge, whichever order the backend lists them in
+            let ucb = self.ucbt_value(*edge_idx, node.num_visits, perspective_player.clone()) + noise;
+            let older = acc.0.is_some_and(|best| *edge_idx < best);
+            if ucb > acc.1 || (ucb == acc.1 && (noise > acc.2 || (noise == acc.2 && older))) {
+                (Some(*edge_idx), ucb, noise)
             } else {
                 acc
             }
@@ -52,121 +503,438 @@ impl<S, A, P> GameTree<S, A, P> where S: State<A, P>, A: Action, P: Player + 'st
         }
     }
 
-    fn expand<R: Rng>(&mut self, rng: &mut R, node_idx: NodeIndex) {
-        let actions = {
+    /// selects the chance outcome whose share of the node's visits lags furthest behind its
+    /// probability, so outcomes are visited, and their results backed up, in proportion to their
+    /// probabilities
+    fn select_chance_outcome(&self, children: &[EdgeIndex]) -> EdgeIndex {
+        let total_probability: ScoreValue = children.iter().map(|edge_idx| self.get_edge(*edge_idx).probability.unwrap_or(0.0)).sum();
+        let num_visits: u32 = children.iter().map(|edge_idx| self.get_edge(*edge_idx).num_visits).sum();
+
+        let deficit = |edge_idx: &EdgeIndex| {
+            let edge = self.get_edge(*edge_idx);
+            let share = edge.probability.unwrap_or(0.0) / total_probability;
+            share * (num_visits + 1) as ScoreValue - edge.num_visits as ScoreValue
+        };
+
+        *children
+            .iter()
+            // ties go to the oldest outcome
+            .max_by(|a, b| deficit(a).total_cmp(&deficit(b)).then(b.cmp(a)))
+            .expect("a chance node has at least one outcome")
+    }
+
+    /// adds a child for each of the node's actions, or for the next chunk of them when there's an
+    /// expansion cap, returns false if none were added and the strictness allows carrying on
+    fn expand<R: Rng>(&mut self, rng: &mut R, node_idx: NodeIndex) -> Result<bool, SearchError<S::Error, A>> {
+        let chance_outcomes = self.get_node(node_idx).state.chance_outcomes();
+        if !chance_outcomes.is_empty() {
+            return self.expand_chance(rng, node_idx, chance_outcomes);
+        }
+
+        let mut actions = mem::take(&mut self.context.actions);
+        actions.clear();
+
+        let start = {
             let node = self.get_node(node_idx);
-            node.state.actions()
+            match self.expansion_cap {
+                Some(max_actions) => actions.extend(node.state.actions_chunk(node.num_expanded_actions, max_actions)),
+                None => node.state.actions_into(&mut actions),
+            }
+
+            if node.num_expanded_actions == 0 {
+                add_pass_action(&node.state, &mut actions);
+            }
+
+            node.num_expanded_actions
+        };
+
+        let expanded = self.expand_actions(rng, node_idx, &mut actions, start);
+        self.context.actions = actions;
+        expanded
+    }
+
+    /// adds a child for each of the actions generated for the node, draining them
+    fn expand_actions<R: Rng>(&mut self, rng: &mut R, node_idx: NodeIndex, actions: &mut Vec<A>, start: usize) -> Result<bool, SearchError<S::Error, A>> {
+        let fully_expanded = match self.expansion_cap {
+            Some(max_actions) => actions.len() < max_actions,
+            None => true,
         };
 
-        if actions.len() == 0 {
-            panic!("no actions to expand into")
+        let node = self.get_node_mut(node_idx);
+        node.num_expanded_actions += actions.len();
+        node.fully_expanded = fully_expanded;
+
+        if actions.is_empty() {
+            // a partially expanded node has already been checked for having actions
+            if start > 0 {
+                return Ok(false);
+            }
+
+            return match strictness() {
+                Strictness::Strict => Err(SearchError::NoActions),
+                Strictness::Lenient => {
+                    log::warn!("a non-terminal state has no actions, treating it as an escape");
+                    Ok(false)
+                }
+            };
         }
 
-        for action in actions {
+        // duplicates only split an action's statistics between two edges, so they're only worth
+        // the cost of looking for in strict mode
+        if strictness() == Strictness::Strict {
+            for (idx, action) in actions.iter().enumerate() {
+                if actions[..idx].contains(action) {
+                    return Err(SearchError::DuplicateAction);
+                }
+            }
+        }
+
+        let state = &self.get_node(node_idx).state;
+        let priors = match &self.evaluator {
+            Some(evaluator) => {
+                let evaluation = evaluator.evaluate(state, actions);
+
+                // later chunks of a capped node's actions are expanded once it's no longer a leaf
+                if start == 0 {
+                    self.leaf_values = Some(evaluation.values);
+                }
+
+                evaluation.priors
+            }
+            None => state.action_priors(actions),
+        };
+        let priors = normalized_priors(priors, actions.len());
+
+        // children are added most likely first, which is the order unvisited ones are tried in
+        let mut children: Vec<(A, ScoreValue)> = actions.drain(..).zip(priors).collect();
+        children.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        let mut num_added = 0;
+        for (action, prior) in children {
+            // a merged tree may already hold children for actions beyond the chunks generated here
+            if start > 0 && self.node_child_edges(node_idx).iter().any(|edge_idx| self.get_edge(*edge_idx).action == action) {
+                continue;
+            }
+
             let node = self.get_node(node_idx);
-            let state = node.state.apply_action(rng, &action).unwrap();
+            let state = node.state.apply_action(rng, &action).map_err(|error| SearchError::State {
+                error,
+                node: Some(node_idx),
+                action: Some(action.clone()),
+            })?;
+
+            let mut edge = GameTreeEdge::new(action);
+            edge.prior = prior;
+            if self.progressive_bias.is_some() {
+                let mover = node.state.current_player();
+                edge.bias = state
+                    .heuristic_value()
+                    .into_iter()
+                    .find(|(player, _)| *player == mover)
+                    .map_or(0.0, |(_, value)| value);
+            }
 
-            let new_node_idx = self.graph.add_node(GameTreeNode::new(state));
-            self.graph.add_edge(node_idx, new_node_idx, GameTreeEdge::new(action));
+            let (new_edge_idx, new_node_idx) = self.backend.add_child(node_idx, edge, GameTreeNode::new(state));
+            self.get_node_mut(new_node_idx).parent = Some((node_idx, new_edge_idx));
+            num_added += 1;
         }
+
+        Ok(num_added > 0)
     }
 
-    pub fn search_n<R: Rng>(&mut self, rng: &mut R, iterations: u32) {
+    /// adds a child for each of a chance node's outcomes, or for a sample of them
+    fn expand_chance<R: Rng>(&mut self, rng: &mut R, node_idx: NodeIndex, mut outcomes: Vec<(S, ScoreValue)>) -> Result<bool, SearchError<S::Error, A>> {
+        // nobody chooses between the outcomes, so their edges carry the action which led here
+        let Some((_, parent_edge_idx)) = self.get_node(node_idx).parent else {
+            return Err(SearchError::ChanceRoot);
+        };
+        let action = self.get_edge(parent_edge_idx).action.clone();
+
+        if let ChanceExpansion::Sample(num_samples) = self.chance_expansion {
+            let num_samples = num_samples.max(1);
+            let probabilities: Vec<ScoreValue> = outcomes.iter().map(|(_, probability)| *probability).collect();
+            outcomes = (0..num_samples)
+                .map(|_| (outcomes[weighted_choice(&probabilities, rng)].0.clone(), 1.0 / num_samples as ScoreValue))
+                .collect();
+        }
+
+        self.get_node_mut(node_idx).fully_expanded = true;
+
+        for (state, probability) in outcomes {
+            let mut edge = GameTreeEdge::new(action.clone());
+            edge.probability = Some(probability);
+
+            let (new_edge_idx, new_node_idx) = self.backend.add_child(node_idx, edge, GameTreeNode::new(state));
+            self.get_node_mut(new_node_idx).parent = Some((node_idx, new_edge_idx));
+        }
+
+        Ok(true)
+    }
+
+    pub fn search_n<R: Rng>(&mut self, rng: &mut R, iterations: u32) -> Result<(), SearchError<S::Error, A>> {
         for _ in 0..iterations {
-            self.search(rng);
+            self.search(rng)?;
         }
+
+        Ok(())
     }
 
-    pub fn search<R: Rng>(&mut self, rng: &mut R) {
-        let mut current_node_idx = self.root_node_idx;
+    /// Runs `iterations` searches, calling `observer` after each one, e.g. to instrument how
+    /// quickly the search converges.
+    pub fn search_with_observer<R: Rng, F: FnMut(&IterationReport<A, P>)>(&mut self, rng: &mut R, iterations: u32, mut observer: F) -> Result<(), SearchError<S::Error, A>> {
+        for iteration in 0..iterations {
+            let (path_length, outcome) = self.search_iteration(rng)?;
+
+            observer(&IterationReport {
+                iteration,
+                path_length,
+                outcome: &outcome,
+                best_action: self.best_action(),
+            });
+        }
 
-        // track visited nodes for back propagation
-        let mut visited_nodes = Vec::new();
-        visited_nodes.push(current_node_idx);
+        Ok(())
+    }
 
-        // Determine the perspective player
-        let perspective_player = self.get_node(current_node_idx).state.current_player();
+    /// Runs `iterations` searches, sending a snapshot of the progress every `interval` iterations
+    /// and once more at the end, e.g. so a GUI can display live thinking. A disconnected receiver
+    /// doesn't stop the search.
+    pub fn search_n_with_progress<R: Rng>(&mut self, rng: &mut R, iterations: u32, interval: u32, sender: &Sender<SearchProgress<A>>) -> Result<(), SearchError<S::Error, A>> {
+        let interval = interval.max(1);
+        for iteration in 1..=iterations {
+            self.search(rng)?;
 
-        // iteratively select an optimal node to expand
-        while self.is_leaf_node(current_node_idx) == false {
-            current_node_idx = self.select(current_node_idx, perspective_player);
-            visited_nodes.push(current_node_idx);
+            if iteration % interval == 0 || iteration == iterations {
+                let _ = sender.send(self.progress(iteration));
+            }
         }
 
-        // determine the outcome of the selected leaf node
-        let outcome = {
-            let node = self.get_node(current_node_idx);
-            let outcome = node.state.outcome();
-            if let Some(outcome) = outcome {
-                outcome
-            } else {
-                self.expand(rng, current_node_idx);
+        Ok(())
+    }
+
+    fn progress(&self, iterations: u32) -> SearchProgress<A> {
+        let root_player = self.get_node(self.root_node_idx).state.current_player();
+        let best_edge = self
+            .node_child_edges(self.root_node_idx)
+            .into_iter()
+            .map(|edge_idx| self.get_edge(edge_idx))
+            .max_by_key(|edge| edge.num_visits);
+
+        let Some(best_edge) = best_edge else {
+            return SearchProgress {
+                iterations,
+                best_action: None,
+                value: 0.0,
+            };
+        };
+
+        SearchProgress {
+            iterations,
+            best_action: Some(best_edge.action.clone()),
+            value: best_edge.get_player_score(root_player) / best_edge.num_visits.max(1) as ScoreValue,
+        }
+    }
+
+    pub fn search<R: Rng>(&mut self, rng: &mut R) -> Result<(), SearchError<S::Error, A>> {
+        self.search_iteration(rng)?;
+        Ok(())
+    }
+
+    /// runs a single search, returning the number of edges on the selected path and the outcome
+    /// which was back propagated along it
+    fn search_iteration<R: Rng>(&mut self, rng: &mut R) -> Result<(usize, Outcome<P>), SearchError<S::Error, A>> {
+        if let Some((_, outcome)) = self.root_solution() {
+            return Ok((0, outcome.clone()));
+        }
 
-                let new_node_idx = self.select(current_node_idx, perspective_player);
-                visited_nodes.push(new_node_idx);
+        let mut current_node_idx = self.root_node_idx;
+        let mut path_length = 0;
 
+        // iteratively select an optimal edge to follow, until reaching a node which can be expanded
+        let outcome = loop {
+            if self.is_leaf_node(current_node_idx) {
+                // determine the outcome of the selected leaf node
                 let node = self.get_node(current_node_idx);
-                random_rollout(&node.state, rng)
+                if let Some(outcome) = node.state.outcome() {
+                    break check_outcome(outcome)?;
+                }
+
+                if let Some(outcome) = self.exact_outcome(current_node_idx) {
+                    break check_outcome(outcome)?;
+                }
+
+                // anything left over from an expansion which failed part way
+                self.leaf_values = None;
+                if !self.expand(rng, current_node_idx)? {
+                    break Outcome::Escape("No actions available.".to_string());
+                }
+
+                if let Some(values) = self.leaf_values.take() {
+                    break Outcome::Scores(values.into_iter().collect());
+                }
+            } else if self.get_node(current_node_idx).fully_expanded || !self.expand(rng, current_node_idx)? {
+                let edge_idx = self.select(rng, current_node_idx);
+                current_node_idx = self.edge_target(edge_idx);
+                path_length += 1;
+                continue;
             }
+
+            // selects among all of the node's children, which is one of the new, unvisited ones
+            // unless PUCT prefers a child added by an earlier chunk of a capped expansion
+            let new_edge_idx = self.select(rng, current_node_idx);
+            current_node_idx = self.edge_target(new_edge_idx);
+            path_length += 1;
+
+            break match self.exact_outcome(current_node_idx) {
+                Some(outcome) => check_outcome(outcome)?,
+                None => {
+                    let mut context = mem::take(&mut self.context);
+                    let outcome = self.rollout_policy.rollout_with_context(&self.get_node(current_node_idx).state, rng, &mut context);
+                    self.context = context;
+                    outcome.map_err(|error| match error {
+                        SearchError::State { error, action, .. } => SearchError::State {
+                            error,
+                            node: Some(current_node_idx),
+                            action,
+                        },
+                        error => error,
+                    })?
+                }
+            };
         };
 
-        self.back_propagate(visited_nodes, outcome);
+        match self.scaled_by_margin(&outcome) {
+            Some(scaled) => self.back_propagate(current_node_idx, &scaled),
+            None => self.back_propagate(current_node_idx, &outcome),
+        }
+        self.max_depth = self.max_depth.max(path_length);
+
+        if let Some(decay) = self.decay {
+            self.iterations_since_decay += 1;
+            if self.iterations_since_decay >= decay.interval {
+                self.iterations_since_decay = 0;
+                self.decay_statistics(decay.factor);
+            }
+        }
+
+        Ok((path_length, outcome))
+    }
+
+    /// the root's proven best action and outcome, consulting the endgame solver the first time
+    fn root_solution(&mut self) -> Option<&(A, Outcome<P>)> {
+        if !self.solver_consulted {
+            self.solver_consulted = true;
+            self.solution = match &self.endgame_solver {
+                Some(solver) => solver(self.root_state()),
+                None => None,
+            };
+        }
+
+        self.solution.as_ref()
     }
 
-    /// This updates the num visits and each player's score for each visited node
-    fn back_propagate(&mut self, visited_nodes: Vec<NodeIndex>, outcome: Outcome<P>) {
-        for visited_node_idx in visited_nodes {
-            let node = self.get_node_mut(visited_node_idx);
+    /// the rewards a win by a margin, or a draw, is credited as, when the tree scales them
+    fn scaled_by_margin(&self, outcome: &Outcome<P>) -> Option<Outcome<P>> {
+        let scaling = self.margin_scaling?;
+        let rewards = match outcome {
+            Outcome::WinnerBy(winner, margin) => {
+                let margin = (*margin / scaling.full_margin).clamp(0.0, 1.0);
+                let winners = winner.teammates();
+                let mut rewards: HashMap<P, ScoreValue> = self
+                    .root_state()
+                    .players()
+                    .into_iter()
+                    .filter(|player| !winners.contains(player))
+                    .map(|player| (player, scaling.weight * (1.0 - margin)))
+                    .collect();
+                rewards.extend(winners.into_iter().map(|player| (player, 1.0 - scaling.weight + scaling.weight * margin)));
+                rewards
+            }
+            Outcome::Draw(drawing_players) => drawing_players
+                .iter()
+                .flat_map(|player| player.teammates())
+                .map(|player| (player, 0.5))
+                .collect(),
+            _ => return None,
+        };
+
+        Some(Outcome::Scores(rewards))
+    }
+
+    /// This walks up from the leaf to the root, updating the num visits of each visited node, and
+    /// the num visits and each player's score for each traversed edge
+    fn back_propagate(&mut self, leaf_node_idx: NodeIndex, outcome: &Outcome<P>) {
+        let mut current_node_idx = leaf_node_idx;
+
+        loop {
+            let is_root = current_node_idx == self.root_node_idx;
+            let node = self.get_node_mut(current_node_idx);
             node.num_visits += 1;
 
-            match &outcome {
-                Outcome::Winner(winner_player) => {
-                    *node.scores.entry(*winner_player).or_insert(0f32) += 1.0;
+            if is_root {
+                break;
+            }
 
-                    if let Some(edge) = self.edge_to_parent(visited_node_idx) {
-                        self.graph.edge_weight_mut(edge.id()).unwrap().num_visits += 1;
-                    }
-                }
-                Outcome::Draw(drawing_players) => {
-                    for drawing_player in drawing_players {
-                        *node.scores.entry(*drawing_player).or_insert(0f32) += 1.0;
-                    }
+            let Some((parent_node_idx, edge_idx)) = node.parent else {
+                break;
+            };
+
+            if let Some(table) = &self.transpositions {
+                if let Some(key) = self.get_node(current_node_idx).state.transposition_key() {
+                    table.update(key, TranspositionEntry::new, |entry| entry.add_outcome(outcome));
                 }
-                Outcome::Escape(_) => {}
             }
+
+            current_node_idx = parent_node_idx;
+
+            let edge = self.get_edge_mut(edge_idx);
+            edge.num_visits += 1;
+            add_outcome(&mut edge.scores, outcome);
         }
     }
 
 
     /// upper confidence bound 1 for trees
-    fn ucbt_value(&self, node_idx: NodeIndex, perspective_player: P) -> f32 {
-        let Some(node) = self.graph.node_weight(node_idx) else {
+    fn ucbt_value(&self, edge_idx: EdgeIndex, parent_visits: u32, perspective_player: P) -> ScoreValue {
+        let Some(edge) = self.backend.edge(edge_idx) else {
             return 0.0;
         };
 
-        if node.num_visits == 0 {
-            return f32::MAX;
+        if edge.num_visits == 0 && self.puct.is_none() {
+            return ScoreValue::MAX;
         }
 
-        let player_score = node.get_player_score(perspective_player);
+        // a transposition table also holds the visits of other trees which reached the same state
+        let (player_score, num_visits) = match self.transposition(self.edge_target(edge_idx)) {
+            Some(entry) if entry.num_visits > edge.num_visits => (entry.get_player_score(perspective_player), entry.num_visits),
+            _ => (edge.get_player_score(perspective_player), edge.num_visits),
+        };
 
         // first component of UCB1 formula corresponds to exploitation
         // as it is high for moves with a high average win ratio
-        // this is the average reward, or win ratio, of the node
-        let exploitation_component = player_score / node.num_visits as f32;
+        // this is the average reward, or win ratio, of the edge
+        let exploitation_component = if num_visits == 0 {
+            0.0
+        } else {
+            player_score / num_visits as ScoreValue
+        };
 
         // the second component corresponds to exploration
-        let parent_visits = self.parent_visits(node_idx);
-        let exploration_component = self.constant_of_exploration * ((parent_visits as f32 + 1.0).ln() / node.num_visits as f32).sqrt();
+        let exploration_component = match self.puct {
+            Some(constant) => constant * edge.prior * (parent_visits as ScoreValue).sqrt() / (edge.num_visits as ScoreValue + 1.0),
+            None => self.constant_of_exploration * ((parent_visits as ScoreValue + 1.0).ln() / edge.num_visits as ScoreValue).sqrt(),
+        };
 
-        // a small amount of noise helps to avoid ties
-        // let noise = rng.next_u32() as f32 * 1e-6;
+        let bias_component = match self.progressive_bias {
+            Some(weight) => weight * edge.bias / (edge.num_visits as ScoreValue + 1.0),
+            None => 0.0,
+        };
 
-        exploitation_component + exploration_component // + noise
+        exploitation_component + exploration_component + bias_component
     }
 
     fn try_get_node(&self, node_idx: NodeIndex) -> Option<&GameTreeNode<S, A, P>> {
-        self.graph.node_weight(node_idx)
+        self.backend.node(node_idx)
     }
 
     fn get_node(&self, node_idx: NodeIndex) -> &GameTreeNode<S, A, P> {
@@ -174,85 +942,376 @@ impl<S, A, P> GameTree<S, A, P> where S: State<A, P>, A: Action, P: Player + 'st
     }
 
     fn try_get_node_mut(&mut self, node_idx: NodeIndex) -> Option<&mut GameTreeNode<S, A, P>> {
-        self.graph.node_weight_mut(node_idx)
+        self.backend.node_mut(node_idx)
     }
 
     fn get_node_mut(&mut self, node_idx: NodeIndex) -> &mut GameTreeNode<S, A, P> {
         self.try_get_node_mut(node_idx).unwrap()
     }
 
-    fn node_children(&self, node_idx: NodeIndex) -> Vec<NodeIndex> {
-        self.graph
-            .edges_directed(
-                node_idx,
-                Outgoing,
-            )
-            .map(|edge| edge.target())
-            .collect()
+    fn get_edge(&self, edge_idx: EdgeIndex) -> &GameTreeEdge<A, P> {
+        self.backend.edge(edge_idx).unwrap()
     }
 
-    fn parent_node_idx(&self, node_idx: NodeIndex) -> Option<NodeIndex> {
-        let edge_to_parent = self.edge_to_parent(node_idx)?;
-        Some(edge_to_parent.source())
+    fn get_edge_mut(&mut self, edge_idx: EdgeIndex) -> &mut GameTreeEdge<A, P> {
+        self.backend.edge_mut(edge_idx).unwrap()
     }
 
-    fn edge_to_parent(&self, node_idx: NodeIndex) -> Option<EdgeReference<GameTreeEdge<A>>> {
-        let incoming_edges: Vec<EdgeReference<GameTreeEdge<A>>> = self.graph.edges_directed(node_idx, Incoming).collect();
-        if incoming_edges.len() == 0 {
-            return None;
-        }
-
-        Some(incoming_edges[0])
+    fn edge_target(&self, edge_idx: EdgeIndex) -> NodeIndex {
+        self.backend.edge_target(edge_idx).unwrap()
     }
 
-    fn parent_visits(&self, node_idx: NodeIndex) -> u32 {
-        let Some(parent_idx) = self.parent_node_idx(node_idx) else {
-            return 0;
-        };
+    /// the node's child edges in the order they were added, for reports and merges rather than
+    /// the selection loop
+    fn node_child_edges(&self, node_idx: NodeIndex) -> Vec<EdgeIndex> {
+        let mut edges: Vec<EdgeIndex> = self.backend.child_edges(node_idx).collect();
+        edges.sort_unstable();
+        edges
+    }
 
-        if let Some(node) = self.graph.node_weight(parent_idx) {
-            node.num_visits
-        } else {
-            0
-        }
+    fn root_child_edge(&self, action: &A) -> Option<EdgeIndex> {
+        self.backend
+            .child_edges(self.root_node_idx)
+            .find(|edge_idx| self.get_edge(*edge_idx).action == *action)
     }
 
     fn is_leaf_node(&self, node_idx: NodeIndex) -> bool {
-        self.graph.edges_directed(node_idx, Outgoing).count() == 0
+        self.backend.num_children(node_idx) == 0
     }
 
     pub fn root_scores(&self) -> Vec<Score<A, P>> {
-        let children = self.node_children(self.root_node_idx);
-        children.iter().flat_map(|child_node_idx| {
-            let child_node = self.get_node(*child_node_idx);
-            let num_visits = child_node.num_visits;
+        // the state's players are listed even before they've scored, then any others which have
+        let players = self.root_state().players();
+
+        let child_edges = self.node_child_edges(self.root_node_idx);
+        child_edges.iter().flat_map(|edge_idx| {
+            let edge = self.get_edge(*edge_idx);
+            let num_visits = edge.num_visits;
+            let action = edge.action.clone();
 
-            let mut edges = self.graph.edges_connecting(self.root_node_idx, *child_node_idx);
-            let edge_weight = edges.next().unwrap().weight();
-            let action = edge_weight.action.clone();
+            let listed = players.iter().map(|player| (player.clone(), edge.get_player_score(player.clone())));
+            let unlisted = edge.scores
+                .iter()
+                .filter(|(player, _)| !players.contains(player))
+                .map(|(player, score)| (player.clone(), *score));
 
-            child_node.scores.iter().map(move |(player, score)| {
+            listed.chain(unlisted).map(move |(player, score)| {
                 Score {
                     action: action.clone(),
-                    player: *player,
-                    score: *score,
+                    player,
+                    score,
                     num_visits,
                 }
+            }).collect::<Vec<_>>()
+        }).collect()
+    }
+
+    /// The average value of each of the root's actions for `player`, converted to the given
+    /// perspective.
+    pub fn root_values(&self, perspective: Perspective, player: P) -> Vec<(A, ScoreValue)> {
+        self.node_child_edges(self.root_node_idx).iter().map(|edge_idx| {
+            let edge = self.get_edge(*edge_idx);
+            let value = if edge.num_visits == 0 {
+                0.0
+            } else {
+                perspective.value(&edge.scores, player.clone()) / edge.num_visits as ScoreValue
+            };
+
+            (edge.action.clone(), value)
+        }).collect()
+    }
+
+    /// The share of the root's visits each of its actions got, e.g. as the target of a policy
+    /// network trained on the search. Empty before the root has been searched.
+    pub fn root_visit_distribution(&self) -> Vec<(A, ScoreValue)> {
+        let child_edges = self.node_child_edges(self.root_node_idx);
+        let total_visits: u32 = child_edges.iter().map(|edge_idx| self.get_edge(*edge_idx).num_visits).sum();
+        if total_visits == 0 {
+            return Vec::new();
+        }
+
+        child_edges.iter().map(|edge_idx| {
+            let edge = self.get_edge(*edge_idx);
+            (edge.action.clone(), edge.num_visits as ScoreValue / total_visits as ScoreValue)
+        }).collect()
+    }
+
+    /// Follows `path` from the root and lists the actions available from the node reached, along
+    /// with their resulting states and visit counts, most visited first. Returns an empty list if
+    /// the path leaves the explored part of the tree.
+    pub fn ranked_children(&self, path: &[A]) -> Vec<(&A, &S, u32)> {
+        let mut node_idx = self.root_node_idx;
+        for action in path {
+            let child_edge_idx = self
+                .node_child_edges(node_idx)
+                .into_iter()
+                .find(|edge_idx| self.get_edge(*edge_idx).action == *action);
+
+            let Some(child_edge_idx) = child_edge_idx else {
+                return Vec::new();
+            };
+            node_idx = self.edge_target(child_edge_idx);
+        }
+
+        let mut children: Vec<(&A, &S, u32)> = self
+            .node_child_edges(node_idx)
+            .into_iter()
+            .map(|edge_idx| {
+                let edge = self.get_edge(edge_idx);
+                (&edge.action, &self.get_node(self.edge_target(edge_idx)).state, edge.num_visits)
             })
+            .collect();
+
+        children.sort_by_key(|(_, _, num_visits)| std::cmp::Reverse(*num_visits));
+        children
+    }
+
+    /// Attaches an annotation to one of the root's actions, e.g. when it was found in an opening
+    /// book. Returns false if the root has no such action.
+    pub fn annotate(&mut self, action: &A, annotation: Annotation) -> bool {
+        let Some(edge_idx) = self.root_child_edge(action) else {
+            return false;
+        };
+
+        self.get_edge_mut(edge_idx).annotations.push(annotation);
+        true
+    }
+
+    /// Lists the root's actions with the annotations attached to them, along with those derived
+    /// from the state of the tree.
+    pub fn annotated_actions(&self) -> Vec<AnnotatedAction<A>> {
+        let root_node = self.get_node(self.root_node_idx);
+        let mover = root_node.state.current_player();
+        let child_edges = self.node_child_edges(self.root_node_idx);
+
+        child_edges.iter().map(|edge_idx| {
+            let edge = self.get_edge(*edge_idx);
+            let child_node = self.get_node(self.edge_target(*edge_idx));
+            let mut annotations = edge.annotations.clone();
+
+            if child_edges.len() == 1 {
+                annotations.push(Annotation::OnlyMove);
+            }
+
+            match child_node.state.outcome() {
+                Some(Outcome::Winner(winner) | Outcome::WinnerBy(winner, _)) if mover.teammates().contains(&winner) => annotations.push(Annotation::ProvenWin),
+                Some(Outcome::Winner(_) | Outcome::WinnerBy(..)) => annotations.push(Annotation::ProvenLoss),
+                _ => {}
+            }
+
+            if root_node.num_visits > 0 && edge.num_visits as ScoreValue >= DOMINANT_VISIT_SHARE * root_node.num_visits as ScoreValue {
+                annotations.push(Annotation::Dominant);
+            }
+
+            AnnotatedAction {
+                action: edge.action.clone(),
+                num_visits: edge.num_visits,
+                annotations,
+            }
         }).collect()
     }
 
     /// selects the best action from the current state of the decision tree
     pub fn best_action(&self) -> Option<&A> {
-        let children = self.node_children(self.root_node_idx);
-        if let Some(most_visited_child_node_idx) = children.iter().max_by_key(|node_idx| self.get_node(**node_idx).num_visits) {
-            if let Some(edge_to_parent) = self.edge_to_parent(*most_visited_child_node_idx) {
-                Some(&edge_to_parent.weight().action)
-            } else {
-                None
+        if let Some((action, _)) = &self.solution {
+            return Some(action);
+        }
+
+        let child_edges = self.node_child_edges(self.root_node_idx);
+        child_edges
+            .iter()
+            .max_by_key(|edge_idx| self.get_edge(**edge_idx).num_visits)
+            .map(|edge_idx| &self.get_edge(*edge_idx).action)
+    }
+
+    /// A compact overview of the search, see `TreeSummary`.
+    pub fn summary(&self) -> TreeSummary<A> {
+        let root_node = self.get_node(self.root_node_idx);
+        let mover = root_node.state.current_player();
+
+        let mut edges: Vec<&GameTreeEdge<A, P>> = self
+            .node_child_edges(self.root_node_idx)
+            .into_iter()
+            .map(|edge_idx| self.get_edge(edge_idx))
+            .collect();
+        edges.sort_by_key(|edge| Reverse(edge.num_visits));
+
+        let top_actions = std::array::from_fn(|idx| {
+            edges.get(idx).map(|edge| ActionSummary {
+                action: edge.action.clone(),
+                num_visits: edge.num_visits,
+                value: edge.get_player_score(mover.clone()) / edge.num_visits.max(1) as ScoreValue,
+            })
+        });
+
+        TreeSummary {
+            top_actions,
+            num_root_visits: root_node.num_visits,
+            max_depth: self.max_depth,
+            node_count: self.node_count(),
+            solver_status: self.solver_status(),
+        }
+    }
+
+    /// Exports the tree down to `max_depth` as a Graphviz DOT graph, with each node labelled by
+    /// its player to move and visits, and each edge by its action and visits.
+    pub fn to_dot(&self, max_depth: usize) -> String where A: Describe, P: Describe {
+        let mut dot = String::from("digraph {\n");
+        let mut stack = vec![(self.root_node_idx, 0)];
+        while let Some((node_idx, depth)) = stack.pop() {
+            let node = self.get_node(node_idx);
+            dot.push_str(&format!(
+                "    n{} [label=\"{}\\n{} visits\"];\n",
+                node_idx.index(),
+                dot_escape(&node.state.current_player().describe()),
+                node.num_visits
+            ));
+
+            if depth == max_depth {
+                continue;
+            }
+
+            for edge_idx in self.node_child_edges(node_idx) {
+                let edge = self.get_edge(edge_idx);
+                let child_idx = self.edge_target(edge_idx);
+                dot.push_str(&format!(
+                    "    n{} -> n{} [label=\"{} ({})\"];\n",
+                    node_idx.index(),
+                    child_idx.index(),
+                    dot_escape(&edge.action.describe()),
+                    edge.num_visits
+                ));
+                stack.push((child_idx, depth + 1));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// the root is whatever the endgame solver proved it to be, otherwise it's won if any action
+    /// leads to a known win for its mover, and lost if every action has been generated and leads
+    /// to a known loss
+    fn solver_status(&self) -> SolverStatus {
+        let root_node = self.get_node(self.root_node_idx);
+        let mover = root_node.state.current_player();
+
+        match &self.solution {
+            Some((_, Outcome::Winner(winner) | Outcome::WinnerBy(winner, _))) if mover.teammates().contains(winner) => return SolverStatus::ProvenWin,
+            Some((_, Outcome::Winner(_) | Outcome::WinnerBy(..))) => return SolverStatus::ProvenLoss,
+            Some((_, Outcome::Draw(_))) => return SolverStatus::ProvenDraw,
+            _ => {}
+        }
+
+        let child_edges = self.node_child_edges(self.root_node_idx);
+
+        let mut all_lost = root_node.fully_expanded && !child_edges.is_empty();
+        for edge_idx in child_edges {
+            let child_idx = self.edge_target(edge_idx);
+            let outcome = self
+                .get_node(child_idx)
+                .state
+                .outcome()
+                .or_else(|| self.exact_outcome(child_idx));
+
+            match outcome {
+                Some(Outcome::Winner(winner) | Outcome::WinnerBy(winner, _)) if mover.teammates().contains(&winner) => return SolverStatus::ProvenWin,
+                Some(Outcome::Winner(_) | Outcome::WinnerBy(..)) => {}
+                _ => all_lost = false,
             }
+        }
+
+        if all_lost {
+            SolverStatus::ProvenLoss
         } else {
-            None
+            SolverStatus::Unsolved
+        }
+    }
+}
+
+fn dot_escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// checks an outcome reported by a state for inconsistencies, which are errors in strict mode and
+/// are corrected where possible in lenient mode
+pub(crate) fn check_outcome<P: Player, E: Debug, A>(outcome: Outcome<P>) -> Result<Outcome<P>, SearchError<E, A>> {
+    match outcome {
+        Outcome::Draw(drawing_players) => check_draw(drawing_players),
+        Outcome::Scores(rewards) => check_scores(rewards),
+        Outcome::WinnerBy(winner, margin) => check_margin(winner, margin),
+        outcome => Ok(outcome),
+    }
+}
+
+fn check_margin<P: Player, E: Debug, A>(winner: P, margin: ScoreValue) -> Result<Outcome<P>, SearchError<E, A>> {
+    if margin.is_finite() && margin >= 0.0 {
+        return Ok(Outcome::WinnerBy(winner, margin));
+    }
+
+    let problem = "a win by a margin which isn't a finite, non-negative number";
+    match strictness() {
+        Strictness::Strict => Err(SearchError::InconsistentOutcome(problem)),
+        Strictness::Lenient => {
+            log::warn!("inconsistent outcome: {problem}");
+
+            // the win itself still stands
+            Ok(Outcome::Winner(winner))
+        }
+    }
+}
+
+fn check_draw<P: Player, E: Debug, A>(drawing_players: Vec<P>) -> Result<Outcome<P>, SearchError<E, A>> {
+    let problem = if drawing_players.is_empty() {
+        "a draw between no players"
+    } else if drawing_players.iter().enumerate().any(|(idx, player)| drawing_players[..idx].contains(player)) {
+        "a draw listing the same player more than once"
+    } else {
+        return Ok(Outcome::Draw(drawing_players));
+    };
+
+    match strictness() {
+        Strictness::Strict => Err(SearchError::InconsistentOutcome(problem)),
+        Strictness::Lenient => {
+            log::warn!("inconsistent outcome: {problem}");
+
+            let mut deduplicated_players: Vec<P> = Vec::with_capacity(drawing_players.len());
+            for player in drawing_players {
+                if !deduplicated_players.contains(&player) {
+                    deduplicated_players.push(player);
+                }
+            }
+
+            Ok(Outcome::Draw(deduplicated_players))
         }
     }
 }
+
+fn check_scores<P: Player, E: Debug, A>(mut rewards: HashMap<P, ScoreValue>) -> Result<Outcome<P>, SearchError<E, A>> {
+    if rewards.values().all(|reward| reward.is_finite()) {
+        return Ok(Outcome::Scores(rewards));
+    }
+
+    let problem = "a reward which isn't a finite number";
+    match strictness() {
+        Strictness::Strict => Err(SearchError::InconsistentOutcome(problem)),
+        Strictness::Lenient => {
+            log::warn!("inconsistent outcome: {problem}");
+
+            // one bad reward would poison every average it's backed up into
+            rewards.retain(|_, reward| reward.is_finite());
+            Ok(Outcome::Scores(rewards))
+        }
+    }
+}
+
+/// each action's share of the priors, or an equal share when the state gave none, or priors which
+/// don't fit its actions
+fn normalized_priors(priors: Vec<ScoreValue>, num_actions: usize) -> Vec<ScoreValue> {
+    let total: ScoreValue = priors.iter().map(|prior| prior.max(0.0)).sum();
+    if priors.len() != num_actions || !(total > 0.0 && total.is_finite()) {
+        return vec![1.0 / num_actions as ScoreValue; num_actions];
+    }
+
+    priors.into_iter().map(|prior| prior.max(0.0) / total).collect()
+}
@@ -1,12 +1,22 @@
-use std::collections::HashMap;
 use std::marker::PhantomData;
+use petgraph::graph::{EdgeIndex, NodeIndex};
 use crate::{Action, Player, State};
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GameTreeNode<S, A, P> where S: State<A, P>, A: Action, P: Player {
     pub state: S,
+    /// the number of times a search has passed through this node, the statistics of each action
+    /// taken from here are held by the outgoing edges
     pub num_visits: u32,
-    pub scores: HashMap<P, f32>,
-    _phantom_data: PhantomData<A>,
+    /// the parent node and the edge leading from it to this node, cached on creation so walking
+    /// up the tree doesn't need to scan incoming edges
+    pub parent: Option<(NodeIndex, EdgeIndex)>,
+    /// the number of the state's actions which have been generated for expansion so far
+    pub num_expanded_actions: usize,
+    /// whether all of the state's actions have been generated, so the node needs no more expanding
+    pub fully_expanded: bool,
+    _phantom_data: PhantomData<(A, P)>,
 }
 
 impl<S, A, P> GameTreeNode<S, A, P> where S: State<A, P>, A: Action, P: Player {
@@ -14,16 +24,10 @@ impl<S, A, P> GameTreeNode<S, A, P> where S: State<A, P>, A: Action, P: Player {
         Self {
             state,
             num_visits: 0,
-            scores: Default::default(),
+            parent: None,
+            num_expanded_actions: 0,
+            fully_expanded: false,
             _phantom_data: Default::default(),
         }
     }
-
-    pub fn get_player_score(&self, player: P) -> f32 {
-        if let Some(value) = self.scores.get(&player) {
-            *value
-        } else {
-            0.0
-        }
-    }
 }
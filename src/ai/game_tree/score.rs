@@ -1,8 +1,25 @@
-use crate::{Action, Player};
+use std::fmt::{self, Display, Formatter};
+use crate::{Action, Describe, Player};
 
+/// The type scores are accumulated in. Enable the `f64-scores` feature when running enough
+/// simulations for `f32` accumulation to lose precision.
+#[cfg(not(feature = "f64-scores"))]
+pub type ScoreValue = f32;
+
+#[cfg(feature = "f64-scores")]
+pub type ScoreValue = f64;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Score<A, P> where A: Action, P: Player {
     pub action: A,
     pub player: P,
-    pub score: f32,
+    pub score: ScoreValue,
     pub num_visits: u32,
-}
\ No newline at end of file
+}
+
+impl<A, P> Display for Score<A, P> where A: Action + Describe, P: Player + Describe {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} scored {} over {} visits", self.action.describe(), self.player.describe(), self.score, self.num_visits)
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt::{self, Display, Formatter};
+use crate::{Action, Describe};
+use crate::ai::game_tree::score::ScoreValue;
+
+/// the number of root actions kept in a `TreeSummary`
+pub const SUMMARY_ACTIONS: usize = 5;
+
+/// Whether the result of the game from the root is already known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SolverStatus {
+    Unsolved,
+    /// the player to move at the root has an action which wins
+    ProvenWin,
+    /// every action of the player to move at the root loses
+    ProvenLoss,
+    /// the best the player to move at the root can do is draw, as proven by an endgame solver
+    ProvenDraw,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActionSummary<A> where A: Action {
+    pub action: A,
+    pub num_visits: u32,
+    /// the average value for the player to move at the root
+    pub value: ScoreValue,
+}
+
+/// A small, fixed size overview of a search, cheap enough to log after every move.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TreeSummary<A> where A: Action {
+    /// the most visited root actions, most visited first
+    pub top_actions: [Option<ActionSummary<A>>; SUMMARY_ACTIONS],
+    pub num_root_visits: u32,
+    /// the length of the longest path selected from the root, before any rollout
+    pub max_depth: usize,
+    pub node_count: usize,
+    pub solver_status: SolverStatus,
+}
+
+impl<A> Display for TreeSummary<A> where A: Action + Describe {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} root visits, {} nodes, depth {}, {:?}",
+            self.num_root_visits, self.node_count, self.max_depth, self.solver_status
+        )?;
+
+        for action in self.top_actions.iter().flatten() {
+            writeln!(f, "{:>12} {:>8} visits {:>8.3}", action.action.describe(), action.num_visits, action.value)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use crate::{Outcome, Player};
+use crate::ai::cache::SharedCache;
+use crate::ai::game_tree::score::ScoreValue;
+
+/// The statistics every tree sharing a `TranspositionTable` has gathered for a state.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TranspositionEntry<P> where P: Player {
+    pub num_visits: u32,
+    pub scores: HashMap<P, ScoreValue>,
+}
+
+/// Statistics keyed by `State::transposition_key`, shared between trees, e.g. every
+/// determinization of an ISMCTS search, so what one tree learns about a position the others
+/// reach too carries over to them.
+pub type TranspositionTable<P> = SharedCache<u64, TranspositionEntry<P>>;
+
+impl<P> TranspositionEntry<P> where P: Player {
+    pub fn new() -> Self {
+        Self {
+            num_visits: 0,
+            scores: HashMap::new(),
+        }
+    }
+
+    pub fn get_player_score(&self, player: P) -> ScoreValue {
+        self.scores.get(&player).copied().unwrap_or(0.0)
+    }
+
+    pub(crate) fn add_outcome(&mut self, outcome: &Outcome<P>) {
+        self.num_visits += 1;
+        add_outcome(&mut self.scores, outcome);
+    }
+}
+
+impl<P> Default for TranspositionEntry<P> where P: Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// credits each member of a winning or drawing team with a point, whatever the margin of the win,
+/// or each player with their reward
+pub(crate) fn add_outcome<P: Player>(scores: &mut HashMap<P, ScoreValue>, outcome: &Outcome<P>) {
+    match outcome {
+        Outcome::Winner(winner_player) | Outcome::WinnerBy(winner_player, _) => {
+            for player in winner_player.teammates() {
+                *scores.entry(player).or_insert(0.0) += 1.0;
+            }
+        }
+        Outcome::Draw(drawing_players) => {
+            // partners who both drew still only get a point each
+            let mut credited: Vec<P> = Vec::new();
+            for player in drawing_players.iter().flat_map(|drawing_player| drawing_player.teammates()) {
+                if !credited.contains(&player) {
+                    *scores.entry(player.clone()).or_insert(0.0) += 1.0;
+                    credited.push(player);
+                }
+            }
+        }
+        Outcome::Scores(rewards) => {
+            for (player, reward) in rewards {
+                *scores.entry(player.clone()).or_insert(0.0) += reward;
+            }
+        }
+        Outcome::Escape(_) | Outcome::Resignation(_) => {}
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use crate::{Action, Outcome, Player, State};
+use crate::ai::game_tree::check_outcome;
+use crate::ai::game_tree::error::SearchError;
+use crate::ai::game_tree::score::{Score, ScoreValue};
+use crate::ai::game_tree::transposition::add_outcome;
+use crate::ai::ismcts::Determinable;
+use crate::ai::random_rollout::{add_pass_action, nature_choice, no_actions_outcome, random_rollout_with_max_length, resolve_chance};
+
+/// How actions look to each player, for games where some actions are hidden, e.g. which card was
+/// discarded face down.
+pub trait Observable<A: Action, P: Player> {
+    /// The action as `observer` sees it being played in this state. Every hidden action which looks
+    /// the same to the observer should map to the same action, while a player's own actions should
+    /// be returned unchanged.
+    fn observe(&self, action: &A, observer: P) -> A;
+}
+
+/// What a player can see of a state, e.g. their own hand, the table and how many cards the others
+/// hold, for keying on the information a player actually has rather than on the full state. Two
+/// states with the same observation for a player belong to the same information set for them.
+pub trait ObservableState<A: Action, P: Player>: State<A, P> {
+    type Observation: Clone + Eq + Hash;
+
+    fn observation(&self, player: P) -> Self::Observation;
+}
+
+/// The statistics for taking `action` from an information set.
+struct InformationSetEdge<A, P> where A: Action, P: Player {
+    action: A,
+    child_idx: usize,
+    num_visits: u32,
+    /// the number of times the action was legal when its information set was visited
+    num_available: u32,
+    scores: HashMap<P, ScoreValue>,
+}
+
+struct InformationSetNode<A, P> where A: Action, P: Player {
+    edges: Vec<InformationSetEdge<A, P>>,
+}
+
+/// A search tree whose nodes are information sets rather than states, as used by single-observer
+/// ISMCTS.
+///
+/// Each search samples a fresh determinization of the root and only follows the actions which are
+/// legal in it, so statistics for an action are shared between every determinization it's legal
+/// in, rather than being split between a separate tree per determinization.
+pub struct InformationSetTree<A, P> where A: Action, P: Player {
+    nodes: Vec<InformationSetNode<A, P>>,
+    constant_of_exploration: ScoreValue,
+    max_rollout_length: usize,
+}
+
+impl<A, P> InformationSetTree<A, P> where A: Action, P: Player {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![InformationSetNode { edges: Vec::new() }],
+            constant_of_exploration: ScoreValue::sqrt(2.0),
+            max_rollout_length: usize::MAX,
+        }
+    }
+
+    /// Scores rollouts which haven't ended after `max_rollout_length` actions as an `Escape`, so
+    /// rules which can loop forever don't hang the search.
+    pub fn with_max_rollout_length(mut self, max_rollout_length: usize) -> Self {
+        self.max_rollout_length = max_rollout_length;
+        self
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Runs `iterations` searches from the information set of `state`, as seen by the player to
+    /// move in it.
+    pub fn search_n<R: Rng, S: State<A, P> + Determinable<S, A, P>>(&mut self, rng: &mut R, state: &S, iterations: u32) -> Result<(), SearchError<S::Error, A>> {
+        for _ in 0..iterations {
+            self.search(rng, state)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn search<R: Rng, S: State<A, P> + Determinable<S, A, P>>(&mut self, rng: &mut R, state: &S) -> Result<(), SearchError<S::Error, A>> {
+        let mut current_state = state.determine(rng, state.current_player());
+        let mut node_idx = 0;
+        let mut path = Vec::new();
+
+        let outcome = loop {
+            if let Some(outcome) = current_state.outcome() {
+                break check_outcome(outcome)?;
+            }
+
+            // a determinization's chance events are hidden information like any other
+            if resolve_chance(&mut current_state, rng) {
+                continue;
+            }
+
+            let mut actions = current_state.actions();
+            add_pass_action(&current_state, &mut actions);
+            if actions.is_empty() {
+                break no_actions_outcome()?;
+            }
+
+            if current_state.current_player().is_chance() {
+                // nature isn't searched, its actions are sampled by their priors
+                let action = actions[nature_choice(&current_state, &actions, rng)].clone();
+                current_state.apply_action_mut(rng, &action).map_err(|error| SearchError::applying(error, action.clone()))?;
+
+                let edge = self.child(node_idx, &action);
+                path.push(edge);
+                node_idx = self.child_idx(edge);
+                continue;
+            }
+
+            self.mark_available(node_idx, &actions);
+
+            if let Some(action) = self.untried(node_idx, &actions).choose(rng) {
+                let action = (*action).clone();
+                current_state.apply_action_mut(rng, &action).map_err(|error| SearchError::applying(error, action.clone()))?;
+
+                path.push(self.child(node_idx, &action));
+
+                break random_rollout_with_max_length(&current_state, rng, self.max_rollout_length).map_err(SearchError::state)?;
+            }
+
+            let edge_pos = self.select(node_idx, &actions, current_state.current_player());
+            let edge = &self.nodes[node_idx].edges[edge_pos];
+            current_state.apply_action_mut(rng, &edge.action).map_err(|error| SearchError::applying(error, edge.action.clone()))?;
+
+            path.push((node_idx, edge_pos));
+            node_idx = edge.child_idx;
+        };
+
+        self.back_propagate(&path, &outcome);
+
+        Ok(())
+    }
+
+    /// every edge legal in this determinization was available to be selected
+    fn mark_available(&mut self, node_idx: usize, actions: &[A]) {
+        for edge in self.nodes[node_idx].edges.iter_mut() {
+            if actions.contains(&edge.action) {
+                edge.num_available += 1;
+            }
+        }
+    }
+
+    fn untried<'a>(&self, node_idx: usize, actions: &'a [A]) -> Vec<&'a A> {
+        actions
+            .iter()
+            .filter(|action| !self.nodes[node_idx].edges.iter().any(|edge| edge.action == **action))
+            .collect()
+    }
+
+    /// returns the node's edge for `action` and the node it leads to, adding them if needed
+    fn child(&mut self, node_idx: usize, action: &A) -> (usize, usize) {
+        if let Some(edge_pos) = self.nodes[node_idx].edges.iter().position(|edge| edge.action == *action) {
+            return (node_idx, edge_pos);
+        }
+
+        let child_idx = self.nodes.len();
+        self.nodes.push(InformationSetNode { edges: Vec::new() });
+        self.nodes[node_idx].edges.push(InformationSetEdge {
+            action: action.clone(),
+            child_idx,
+            num_visits: 0,
+            // the visit which added the edge is counted as it's made available
+            num_available: 1,
+            scores: HashMap::new(),
+        });
+
+        (node_idx, self.nodes[node_idx].edges.len() - 1)
+    }
+
+    fn child_idx(&self, (node_idx, edge_pos): (usize, usize)) -> usize {
+        self.nodes[node_idx].edges[edge_pos].child_idx
+    }
+
+    /// selects the best of the node's edges which are legal in the current determinization, using
+    /// each edge's availability in place of its parent's visits
+    fn select(&self, node_idx: usize, actions: &[A], perspective_player: P) -> usize {
+        self.nodes[node_idx]
+            .edges
+            .iter()
+            .enumerate()
+            .filter(|(_, edge)| actions.contains(&edge.action))
+            .map(|(edge_pos, edge)| (edge_pos, self.ucbt_value(edge, perspective_player.clone())))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(edge_pos, _)| edge_pos)
+            .expect("every legal action has an edge once there are no untried actions")
+    }
+
+    fn ucbt_value(&self, edge: &InformationSetEdge<A, P>, perspective_player: P) -> ScoreValue {
+        if edge.num_visits == 0 {
+            return ScoreValue::MAX;
+        }
+
+        let num_visits = edge.num_visits as ScoreValue;
+        let score = edge.scores.get(&perspective_player).copied().unwrap_or(0.0);
+        let exploitation = score / num_visits;
+        let exploration = ((edge.num_available as ScoreValue).ln() / num_visits).sqrt();
+
+        exploitation + self.constant_of_exploration * exploration
+    }
+
+    fn back_propagate(&mut self, path: &[(usize, usize)], outcome: &Outcome<P>) {
+        for (node_idx, edge_pos) in path {
+            let edge = &mut self.nodes[*node_idx].edges[*edge_pos];
+            edge.num_visits += 1;
+
+            add_outcome(&mut edge.scores, outcome);
+        }
+    }
+
+    /// the scores of each player for each of the root's actions
+    pub fn root_scores(&self) -> Vec<Score<A, P>> {
+        let mut scores = Vec::new();
+        for edge in &self.nodes[0].edges {
+            for (player, score) in &edge.scores {
+                scores.push(Score {
+                    action: edge.action.clone(),
+                    player: player.clone(),
+                    score: *score,
+                    num_visits: edge.num_visits,
+                });
+            }
+        }
+
+        scores
+    }
+
+    /// The most visited of the root's actions.
+    pub fn best_action(&self) -> Option<&A> {
+        self.nodes[0]
+            .edges
+            .iter()
+            .max_by_key(|edge| edge.num_visits)
+            .map(|edge| &edge.action)
+    }
+}
+
+impl<A, P> Default for InformationSetTree<A, P> where A: Action, P: Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Single-observer ISMCTS: searches one information set tree, sampling a fresh determinization of
+/// `state` for each of the `num_simulations` iterations.
+pub fn so_ismcts<
+    R: Rng + Clone,
+    S: State<A, P> + Determinable<S, A, P>,
+    A: Action,
+    P: Player,
+>(state: &S, rng: &R, num_simulations: u32) -> Result<A, SearchError<S::Error, A>> {
+    let mut rng = rng.clone();
+    let mut tree = InformationSetTree::new();
+    tree.search_n(&mut rng, state, num_simulations)?;
+
+    tree.best_action().cloned().ok_or(SearchError::NoDecision)
+}
+
+/// Multiple-observer ISMCTS: keeps an information set tree for each player, built from what that
+/// player can observe, so opponents' decisions are made from their own information rather than
+/// from the searching player's.
+pub struct MultiObserverTrees<A, P> where A: Action, P: Player {
+    trees: HashMap<P, InformationSetTree<A, P>>,
+    max_rollout_length: usize,
+}
+
+impl<A, P> MultiObserverTrees<A, P> where A: Action, P: Player {
+    pub fn new(players: &[P]) -> Self {
+        Self {
+            trees: players.iter().map(|player| (player.clone(), InformationSetTree::new())).collect(),
+            max_rollout_length: usize::MAX,
+        }
+    }
+
+    /// Scores rollouts which haven't ended after `max_rollout_length` actions as an `Escape`, so
+    /// rules which can loop forever don't hang the search.
+    pub fn with_max_rollout_length(mut self, max_rollout_length: usize) -> Self {
+        self.max_rollout_length = max_rollout_length;
+        self
+    }
+
+    /// The tree built from `player`'s observations.
+    pub fn tree(&self, player: P) -> Option<&InformationSetTree<A, P>> {
+        self.trees.get(&player)
+    }
+
+    pub fn search_n<R: Rng, S: State<A, P> + Determinable<S, A, P> + Observable<A, P>>(&mut self, rng: &mut R, state: &S, iterations: u32) -> Result<(), SearchError<S::Error, A>> {
+        for _ in 0..iterations {
+            self.search(rng, state)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn search<R: Rng, S: State<A, P> + Determinable<S, A, P> + Observable<A, P>>(&mut self, rng: &mut R, state: &S) -> Result<(), SearchError<S::Error, A>> {
+        let mut current_state = state.determine(rng, state.current_player());
+        let mut node_idxs: HashMap<P, usize> = self.trees.keys().map(|player| (player.clone(), 0)).collect();
+        let mut paths: HashMap<P, Vec<(usize, usize)>> = self.trees.keys().map(|player| (player.clone(), Vec::new())).collect();
+
+        let outcome = loop {
+            if let Some(outcome) = current_state.outcome() {
+                break check_outcome(outcome)?;
+            }
+
+            // a determinization's chance events are hidden information like any other
+            if resolve_chance(&mut current_state, rng) {
+                continue;
+            }
+
+            let mut actions = current_state.actions();
+            add_pass_action(&current_state, &mut actions);
+            if actions.is_empty() {
+                break no_actions_outcome()?;
+            }
+
+            let mover = current_state.current_player();
+            let (action_pos, is_expansion) = if mover.is_chance() {
+                // nature has no tree, its actions are sampled by their priors
+                (nature_choice(&current_state, &actions, rng), false)
+            } else {
+                let Some(mover_tree) = self.trees.get_mut(&mover) else {
+                    return Err(SearchError::NoObserverTree);
+                };
+
+                // the mover decides from their own tree, using the actions as they see them
+                let observed: Vec<A> = actions.iter().map(|action| current_state.observe(action, mover.clone())).collect();
+                let mover_node_idx = node_idxs[&mover];
+                mover_tree.mark_available(mover_node_idx, &observed);
+
+                let untried_action = mover_tree.untried(mover_node_idx, &observed).choose(rng).map(|action| (*action).clone());
+                let is_expansion = untried_action.is_some();
+                let observed_action = match untried_action {
+                    Some(action) => action,
+                    None => {
+                        let edge_pos = mover_tree.select(mover_node_idx, &observed, mover);
+                        mover_tree.nodes[mover_node_idx].edges[edge_pos].action.clone()
+                    }
+                };
+
+                let action_pos = observed.iter().position(|action| *action == observed_action).expect("the action was observed in this state");
+                (action_pos, is_expansion)
+            };
+            let action = &actions[action_pos];
+
+            // every player's tree follows the action as they observed it
+            for (player, tree) in self.trees.iter_mut() {
+                let node_idx = node_idxs.get_mut(player).expect("every tree has a node");
+                let edge = tree.child(*node_idx, &current_state.observe(action, player.clone()));
+                *node_idx = tree.child_idx(edge);
+                paths.get_mut(player).expect("every tree has a path").push(edge);
+            }
+
+            current_state.apply_action_mut(rng, action).map_err(|error| SearchError::applying(error, action.clone()))?;
+
+            if is_expansion {
+                break random_rollout_with_max_length(&current_state, rng, self.max_rollout_length).map_err(SearchError::state)?;
+            }
+        };
+
+        for (player, tree) in self.trees.iter_mut() {
+            tree.back_propagate(&paths[player], &outcome);
+        }
+
+        Ok(())
+    }
+}
+
+/// Searches `state` with a tree for each of `players` (which must include everyone who can move
+/// during the search), returning the action chosen from the tree of the player to move.
+pub fn mo_ismcts<
+    R: Rng + Clone,
+    S: State<A, P> + Determinable<S, A, P> + Observable<A, P>,
+    A: Action,
+    P: Player,
+>(state: &S, rng: &R, players: &[P], num_simulations: u32) -> Result<A, SearchError<S::Error, A>> {
+    let mut rng = rng.clone();
+    let mut trees = MultiObserverTrees::new(players);
+    trees.search_n(&mut rng, state, num_simulations)?;
+
+    let tree = trees.tree(state.current_player()).ok_or(SearchError::NoObserverTree)?;
+    tree.best_action().cloned().ok_or(SearchError::NoDecision)
+}
@@ -1,24 +1,584 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::marker::PhantomData;
-use std::sync::{Arc, Mutex};
-use std::thread;
-use rand::{Rng};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use rand::{Rng, RngCore, SeedableRng};
+use rand::rngs::StdRng;
 use thiserror::Error;
-use crate::{Action, GameTree, Outcome, Player, State};
-use crate::ai::game_tree::score::Score;
+use crate::{Action, Describe, GameTree, Outcome, Player, State};
+use crate::ai::game_tree::error::SearchError;
+use crate::ai::game_tree::score::{Score, ScoreValue};
+use crate::ai::game_tree::transposition::TranspositionTable;
+use crate::ai::agents::{check_search_budget, Agent as AnyAgent, AgentError, Decision};
+use crate::ai::batch::{default_parallelism, run_batch};
+use crate::ai::game::{Decided, GameError, Resignation, Runner, Seats, TimeControl};
+use crate::ai::belief::{BeliefState, InferenceModel};
+use crate::ai::perspective::{scores_by_action, Perspective};
+use crate::ai::information_set::{Observable, ObservableState};
+use crate::ai::policy::Policy;
+use crate::ai::replay::{Recording, Replay};
+use crate::ai::rng::{seeded_rng, stream_seed, StreamRng};
+use crate::ai::skill::Skill;
 
 pub trait Determinable<S: State<A, P>, A: Action, P: Player> {
     fn determine<R: Rng>(&self, rng: &mut R, perspective_player: P) -> S;
+
+    /// Determines a state along with how likely its hidden information is, relative to the other
+    /// determinizations. `ismcts` weights each determinization's scores by it, so implementations
+    /// which don't know the likelihoods can leave this as the default weight of 1.
+    fn determine_weighted<R: Rng>(&self, rng: &mut R, perspective_player: P) -> (S, ScoreValue) {
+        (self.determine(rng, perspective_player), 1.0)
+    }
+
+    /// Determines a state consistent with the actions played so far, oldest first along with the
+    /// player who played them, as recorded by `MultithreadedInformationSetGame`.
+    fn determine_with_history<R: Rng>(&self, rng: &mut R, perspective_player: P, _history: &[(P, A)]) -> (S, ScoreValue) {
+        self.determine_weighted(rng, perspective_player)
+    }
+}
+
+/// Picks an action given the root scores of every determinization's tree, from the perspective of
+/// the player to move. The scores are already multiplied by their determinization's weight.
+pub type CustomAggregation<A, P> = Arc<dyn Fn(&[Vec<Score<A, P>>], P) -> Option<A> + Send + Sync>;
+
+/// Called with each determinization as soon as it's been searched, from whichever thread searched
+/// it.
+pub type DeterminizationCallback<A, P> = Arc<dyn Fn(&Determinization<A, P>) + Send + Sync>;
+
+/// How the results of each determinization are combined into a single decision.
+#[derive(Clone, Default)]
+pub enum Aggregation<A, P> where A: Action, P: Player {
+    /// the action with the highest total score, as valued by the search's `Perspective`
+    #[default]
+    SumOfScores,
+    /// the action with the most visits in total, which is robust to a few lucky determinizations
+    SumOfVisits,
+    /// the action which was most visited in the most determinizations, ties are broken by visits
+    MajorityVote,
+    /// the action with the highest value averaged over all of its visits, as valued by the search's
+    /// `Perspective`
+    VisitWeightedMean,
+    Custom(CustomAggregation<A, P>),
+}
+
+/// Stops adding determinizations once the decision has settled, i.e. the action each of the last
+/// `window` determinizations searched most agrees with the overall choice often enough.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdaptiveStopping {
+    pub window: u32,
+    /// the share of the window which has to agree, between 0 and 1
+    pub agreement: ScoreValue,
+}
+
+impl AdaptiveStopping {
+    fn is_settled<A: Action, P: Player>(&self, determinizations: &[Determinization<A, P>], config: &IsmctsConfig<A, P>, player: P) -> bool {
+        let window = self.window.max(1) as usize;
+        if determinizations.len() < window {
+            return false;
+        }
+
+        let Some(choice) = config.aggregation.choose(determinizations, player, config.perspective) else {
+            return false;
+        };
+
+        let num_agreeing = determinizations[determinizations.len() - window..]
+            .iter()
+            .filter(|determinization| determinization.best_action().as_ref() == Some(&choice))
+            .count();
+
+        num_agreeing as ScoreValue >= self.agreement * window as ScoreValue
+    }
+}
+
+/// How the simulation budget, `num_determinizations * num_simulations`, is spread over the
+/// determinizations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SimulationAllocation {
+    /// every determinization gets `num_simulations`
+    #[default]
+    Uniform,
+    /// the budget is spent in rounds, after each of which only the half of the determinizations
+    /// whose two best actions are closest in value keep being searched, so the determinizations
+    /// where the decision is clear don't use up simulations the uncertain ones could use
+    SuccessiveHalving,
+}
+
+/// Stops a running search from another thread, e.g. when a move times out. Clones share the same
+/// flag, so the search can be handed one while the caller keeps another.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// The settings of an ISMCTS search, e.g.
+/// `IsmctsConfig::new(8, 500).with_aggregation(Aggregation::SumOfVisits)`.
+#[derive(Clone)]
+pub struct IsmctsConfig<A, P> where A: Action, P: Player {
+    num_determinizations: u32,
+    num_simulations: u32,
+    constant_of_exploration: ScoreValue,
+    aggregation: Aggregation<A, P>,
+    parallelism: usize,
+    adaptive_stopping: Option<AdaptiveStopping>,
+    perspective: Perspective,
+    cancellation: Option<CancellationToken>,
+    on_determinization: Option<DeterminizationCallback<A, P>>,
+    transpositions: Option<TranspositionTable<P>>,
+    allocation: SimulationAllocation,
+}
+
+impl<A, P> IsmctsConfig<A, P> where A: Action, P: Player {
+    /// Searches `num_determinizations` determinizations with `num_simulations` simulations each.
+    pub fn new(num_determinizations: u32, num_simulations: u32) -> Self {
+        Self {
+            num_determinizations,
+            num_simulations,
+            constant_of_exploration: ScoreValue::sqrt(2.0),
+            aggregation: Aggregation::default(),
+            parallelism: default_parallelism(),
+            adaptive_stopping: None,
+            perspective: Perspective::default(),
+            cancellation: None,
+            on_determinization: None,
+            transpositions: None,
+            allocation: SimulationAllocation::default(),
+        }
+    }
+
+    pub fn with_constant_of_exploration(mut self, constant_of_exploration: ScoreValue) -> Self {
+        self.constant_of_exploration = constant_of_exploration;
+        self
+    }
+
+    pub fn with_aggregation(mut self, aggregation: Aggregation<A, P>) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
+    /// How the aggregation values an action's per-player scores, e.g.
+    /// `Perspective::RelativeToBestOpponent` to maximize the margin over the best opponent rather
+    /// than the raw score, which matters in games with three or more players. Custom aggregations
+    /// are given the raw scores.
+    pub fn with_perspective(mut self, perspective: Perspective) -> Self {
+        self.perspective = perspective;
+        self
+    }
+
+    /// The number of worker threads `ismcts_mt_with_config` searches on, which defaults to the
+    /// available parallelism. Single threaded searches ignore it.
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Treats the number of determinizations as a maximum, stopping early once the decision has
+    /// settled. Only determinizations searched one after the other can stop early, so
+    /// `ismcts_mt_with_config` ignores it.
+    pub fn with_adaptive_stopping(mut self, adaptive_stopping: AdaptiveStopping) -> Self {
+        self.adaptive_stopping = Some(adaptive_stopping);
+        self
+    }
+
+    /// Checks `cancellation` between simulations. Once it's cancelled, the search stops promptly and
+    /// decides from the determinizations which were searched completely.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Calls `on_determinization` as each determinization completes, e.g. so a long search can
+    /// report its progress and each determinization's best action as it goes.
+    pub fn with_on_determinization(mut self, on_determinization: DeterminizationCallback<A, P>) -> Self {
+        self.on_determinization = Some(on_determinization);
+        self
+    }
+
+    /// Shares statistics between the determinizations' trees through `table`, for the states which
+    /// have a `State::transposition_key`, e.g. positions many determinizations agree on.
+    pub fn with_transposition_table(mut self, table: TranspositionTable<P>) -> Self {
+        self.transpositions = Some(table);
+        self
+    }
+
+    /// Spreads the simulations over the determinizations as `allocation` says. Allocations other
+    /// than `Uniform` search every determinization, so adaptive stopping doesn't apply to them.
+    pub fn with_simulation_allocation(mut self, allocation: SimulationAllocation) -> Self {
+        self.allocation = allocation;
+        self
+    }
+
+    pub fn num_determinizations(&self) -> u32 {
+        self.num_determinizations
+    }
+
+    pub fn num_simulations(&self) -> u32 {
+        self.num_simulations
+    }
+
+    fn tree<S: State<A, P>>(&self, state: S) -> GameTree<S, A, P> {
+        let tree = GameTree::new(state).with_constant_of_exploration(self.constant_of_exploration);
+        match &self.transpositions {
+            Some(table) => tree.with_transposition_table(table.clone()),
+            None => tree,
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Runs `num_simulations` simulations on `tree`, returning whether they all ran before the
+    /// search was cancelled.
+    fn search_tree<S: State<A, P>, R: Rng>(&self, tree: &mut GameTree<S, A, P>, rng: &mut R, num_simulations: u32) -> Result<bool, SearchError> {
+        if self.cancellation.is_none() {
+            tree.search_n(rng, num_simulations).map_err(SearchError::into_formatted)?;
+            return Ok(true);
+        }
+
+        for _ in 0..num_simulations {
+            if self.is_cancelled() {
+                return Ok(false);
+            }
+
+            tree.search(rng).map_err(SearchError::into_formatted)?;
+        }
+
+        Ok(true)
+    }
+
+    fn completed(&self, determinization: &Determinization<A, P>) {
+        if let Some(on_determinization) = &self.on_determinization {
+            on_determinization(determinization);
+        }
+    }
+
+    fn aggregate(&self, determinizations: Determinizations<A, P>, player: P) -> IsmctsResult<A, P> {
+        self.aggregation.aggregate(determinizations, player, self.perspective)
+    }
 }
 
 type Determinizations<A, P> = Vec<Determinization<A, P>>;
 
-struct Determinization<A, P> where A: Action, P: Player {
-    #[allow(dead_code)]
-    determinization_idx: u32,
-    scores: Vec<Score<A, P>>,
+/// How a player sees the game, captured by `with_tree_reuse` so only games reusing trees have to be
+/// observable: the action they saw played, and whether two states look the same to them.
+struct Observer<S, A, P> {
+    observe: fn(&S, &A, P) -> A,
+    same_observation: fn(&S, &S, P) -> bool,
+}
+
+impl<S, A, P> Observer<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    /// Moves one of `player`'s kept trees onto its most visited child whose action looks like
+    /// `observed` to them, e.g. whichever card an opponent discarded face down in that
+    /// determinization. Returns false if it has no such child, or the new root contradicts what
+    /// `player` sees of `state`, e.g. as the determinization drew another card than the one really
+    /// drawn, in which case the tree can't be reused.
+    fn advance(&self, tree: &mut GameTree<S, A, P>, player: P, observed: &A, state: &S) -> bool {
+        let action = tree
+            .ranked_children(&[])
+            .into_iter()
+            .map(|(action, _, _)| action)
+            .find(|action| (self.observe)(tree.root_state(), action, player.clone()) == *observed)
+            .cloned();
+
+        let Some(action) = action else {
+            return false;
+        };
+
+        tree.advance_root(&action) && (self.same_observation)(tree.root_state(), state, player)
+    }
+}
+
+/// carries a tracked belief forward through an action, see `BeliefState::track`, captured by
+/// `with_belief_tracking` so only games tracking beliefs have to be observable
+type TrackBelief<S, A, P> = fn(&mut BeliefState<S>, &mut dyn RngCore, &S, &S, P, &A, Option<&dyn InferenceModel<S, A, P>>);
+
+/// determinization trees kept between turns, along with their weights and the seeds they were
+/// determined with
+type RetainedTrees<S, A, P> = Vec<(GameTree<S, A, P>, ScoreValue, u64)>;
+
+/// The result of searching a single determinization.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Determinization<A, P> where A: Action, P: Player {
+    pub determinization_idx: u32,
+    /// the seed of the rng the determinization was determined and searched with
+    pub seed: u64,
+    pub weight: ScoreValue,
+    /// the root scores of the determinization's tree, not multiplied by its weight
+    pub scores: Vec<Score<A, P>>,
+}
+
+impl<A, P> Determinization<A, P> where A: Action, P: Player {
+    /// A fresh copy of the rng the determinization was made with, so that calling
+    /// `Determinable::determine_weighted` with it replays the determinization exactly, e.g. to
+    /// debug a bad decision. Trees kept between turns by `MultithreadedInformationSetGame::with_tree_reuse`
+    /// were determined with it using the history at the time, but searched with other rngs since.
+    pub fn rng(&self) -> StdRng {
+        seeded_rng(self.seed)
+    }
+
+    /// The action this determinization's tree visited most, i.e. its vote in `MajorityVote`.
+    pub fn best_action(&self) -> Option<A> {
+        most_visited_action(&self.scores)
+    }
+}
+
+/// An action's statistics over every determinization it was searched in.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActionStatistics<A, P> where A: Action, P: Player {
+    pub action: A,
+    pub num_visits: u32,
+    /// each player's total score, with each determinization's scores multiplied by its weight
+    pub scores: HashMap<P, ScoreValue>,
+}
+
+/// Everything an ISMCTS search found, rather than just the action it chose.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IsmctsResult<A, P> where A: Action, P: Player {
+    pub best_action: Option<A>,
+    /// every action searched, in the order they were first found
+    pub actions: Vec<ActionStatistics<A, P>>,
+    /// the determinizations which were searched successfully, in order
+    pub determinizations: Vec<Determinization<A, P>>,
+}
+
+impl<A, P> IsmctsResult<A, P> where A: Action, P: Player {
+    /// The determinizations whose own best action isn't the one chosen, e.g. to find which
+    /// deals of the hidden cards led a bot astray.
+    pub fn disagreeing_determinizations(&self) -> impl Iterator<Item = &Determinization<A, P>> {
+        self.determinizations
+            .iter()
+            .filter(|determinization| determinization.best_action() != self.best_action)
+    }
+
+    /// The share of the determinizations whose own best action is the one chosen.
+    pub fn agreement(&self) -> ScoreValue {
+        if self.determinizations.is_empty() {
+            return 0.0;
+        }
+
+        let num_disagreeing = self.disagreeing_determinizations().count();
+        1.0 - num_disagreeing as ScoreValue / self.determinizations.len() as ScoreValue
+    }
+
+    /// The share of the visits over every determinization each action got, e.g. as the target of
+    /// a policy network trained on the search.
+    pub fn visit_distribution(&self) -> Vec<(A, ScoreValue)> {
+        let total_visits: u32 = self.actions.iter().map(|statistics| statistics.num_visits).sum();
+        if total_visits == 0 {
+            return Vec::new();
+        }
+
+        self.actions
+            .iter()
+            .map(|statistics| (statistics.action.clone(), statistics.num_visits as ScoreValue / total_visits as ScoreValue))
+            .collect()
+    }
+
+    /// The chosen action's average score for `player` over every determinization, each weighted
+    /// by its determinization's weight. None if no action was chosen, or it was never visited.
+    pub fn value(&self, player: &P) -> Option<ScoreValue> {
+        self.action_value(self.best_action.as_ref()?, player)
+    }
+
+    /// Like `value`, for any of the actions searched.
+    pub fn action_value(&self, searched_action: &A, player: &P) -> Option<ScoreValue> {
+        let mut total_score = 0.0;
+        let mut total_visits = 0.0;
+        for determinization in &self.determinizations {
+            for (action, num_visits, scores) in scores_by_action(&determinization.scores) {
+                if action == *searched_action {
+                    total_score += determinization.weight * scores.get(player).copied().unwrap_or(0.0);
+                    total_visits += determinization.weight * num_visits as ScoreValue;
+                }
+            }
+        }
+
+        (total_visits > 0.0).then(|| total_score / total_visits)
+    }
+
+    /// the chosen action as `player`'s decision, if one was chosen
+    pub(crate) fn decision(&self, player: &P) -> Option<Decision<A>> {
+        Some(Decision {
+            action: self.best_action.clone()?,
+            visits: self.visit_distribution(),
+            value: self.value(player),
+        })
+    }
+}
+
+/// an action's totals over every determinization, each weighted by its determinization's weight
+struct ActionTotals<A> {
+    action: A,
+    score: ScoreValue,
+    num_visits: ScoreValue,
+    num_votes: ScoreValue,
+}
+
+impl<A, P> Aggregation<A, P> where A: Action, P: Player {
+    fn aggregate(&self, mut determinizations: Determinizations<A, P>, player: P, perspective: Perspective) -> IsmctsResult<A, P> {
+        // determinizations searched in parallel finish in any order
+        determinizations.sort_by_key(|determinization| determinization.determinization_idx);
+
+        IsmctsResult {
+            best_action: self.choose(&determinizations, player, perspective),
+            actions: action_statistics(&determinizations),
+            determinizations,
+        }
+    }
+
+    fn choose(&self, determinizations: &[Determinization<A, P>], player: P, perspective: Perspective) -> Option<A> {
+        if let Aggregation::Custom(aggregate) = self {
+            let scores: Vec<Vec<Score<A, P>>> = determinizations
+                .iter()
+                .map(|determinization| {
+                    let mut scores = determinization.scores.clone();
+                    for score in scores.iter_mut() {
+                        score.score *= determinization.weight;
+                    }
+                    scores
+                })
+                .collect();
+
+            return aggregate(&scores, player);
+        }
+
+        let mut totals: Vec<ActionTotals<A>> = Vec::new();
+        for determinization in determinizations {
+            let weight = determinization.weight;
+            let grouped = scores_by_action(&determinization.scores);
+            let vote = determinization.best_action();
+
+            for (action, num_visits, scores) in grouped {
+                let idx = match totals.iter().position(|totals| totals.action == action) {
+                    Some(idx) => idx,
+                    None => {
+                        totals.push(ActionTotals {
+                            action: action.clone(),
+                            score: 0.0,
+                            num_visits: 0.0,
+                            num_votes: 0.0,
+                        });
+                        totals.len() - 1
+                    }
+                };
+
+                let action_totals = &mut totals[idx];
+                action_totals.score += weight * perspective.value(&scores, player.clone());
+                action_totals.num_visits += weight * num_visits as ScoreValue;
+                if vote.as_ref() == Some(&action) {
+                    action_totals.num_votes += weight;
+                }
+            }
+        }
+
+        let best = totals.into_iter().max_by(|a, b| match self {
+            Aggregation::SumOfScores => a.score.total_cmp(&b.score),
+            Aggregation::SumOfVisits => a.num_visits.total_cmp(&b.num_visits),
+            Aggregation::MajorityVote => a.num_votes.total_cmp(&b.num_votes).then(a.num_visits.total_cmp(&b.num_visits)),
+            Aggregation::VisitWeightedMean => mean_value(a).total_cmp(&mean_value(b)),
+            Aggregation::Custom(_) => unreachable!("custom aggregations return early"),
+        })?;
+
+        Some(best.action)
+    }
+}
+
+fn action_statistics<A: Action, P: Player>(determinizations: &[Determinization<A, P>]) -> Vec<ActionStatistics<A, P>> {
+    let mut statistics: Vec<ActionStatistics<A, P>> = Vec::new();
+    for determinization in determinizations {
+        for (action, num_visits, scores) in scores_by_action(&determinization.scores) {
+            let idx = match statistics.iter().position(|statistics| statistics.action == action) {
+                Some(idx) => idx,
+                None => {
+                    statistics.push(ActionStatistics {
+                        action,
+                        num_visits: 0,
+                        scores: HashMap::new(),
+                    });
+                    statistics.len() - 1
+                }
+            };
+
+            let action_statistics = &mut statistics[idx];
+            action_statistics.num_visits += num_visits;
+            for (player, score) in scores {
+                *action_statistics.scores.entry(player).or_insert(0.0) += determinization.weight * score;
+            }
+        }
+    }
+
+    statistics
+}
+
+fn most_visited_action<A: Action, P: Player>(scores: &[Score<A, P>]) -> Option<A> {
+    scores_by_action(scores)
+        .into_iter()
+        .max_by_key(|(_, num_visits, _)| *num_visits)
+        .map(|(action, _, _)| action)
+}
+
+fn mean_value<A>(totals: &ActionTotals<A>) -> ScoreValue {
+    if totals.num_visits > 0.0 {
+        totals.score / totals.num_visits
+    } else {
+        0.0
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum IsmctsError {
+    #[error("the state is already terminal")]
+    Terminal,
+
+    #[error("the player to move has no legal actions")]
+    NoActions,
+
+    #[error("no determinization could be searched: {0}")]
+    Search(#[from] SearchError),
+
+    #[error("the aggregation didn't choose an action")]
+    NoDecision,
+
+    #[error("the search was cancelled before any determinization was searched")]
+    Cancelled,
+}
+
+/// Checks there's a decision to make in `state` before determinizing it.
+fn check_decidable<S: State<A, P>, A: Action, P: Player>(state: &S) -> Result<(), IsmctsError> {
+    if state.outcome().is_some() {
+        return Err(IsmctsError::Terminal);
+    }
+
+    if state.actions().is_empty() && state.pass_action().is_none() {
+        return Err(IsmctsError::NoActions);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn best_action<A: Action, P: Player>(result: Result<IsmctsResult<A, P>, IsmctsError>) -> Result<A, IsmctsError> {
+    result?.best_action.ok_or(IsmctsError::NoDecision)
 }
 
 pub fn ismcts<
@@ -26,57 +586,286 @@ pub fn ismcts<
     S: State<A, P> + Determinable<S, A, P>,
     A: Action + Eq + Hash,
     P: Player,
->(state: &S, rng: &R, num_determinizations: u32, num_simulations: u32) -> Option<A> {
+>(state: &S, rng: &R, num_determinizations: u32, num_simulations: u32) -> Result<A, IsmctsError> {
+    best_action(ismcts_with_config(state, rng, &IsmctsConfig::new(num_determinizations, num_simulations)))
+}
+
+pub fn ismcts_with_aggregation<
+    R: Rng + Clone,
+    S: State<A, P> + Determinable<S, A, P>,
+    A: Action + Eq + Hash,
+    P: Player,
+>(state: &S, rng: &R, num_determinizations: u32, num_simulations: u32, aggregation: &Aggregation<A, P>) -> Result<A, IsmctsError> {
+    let config = IsmctsConfig::new(num_determinizations, num_simulations).with_aggregation(aggregation.clone());
+    best_action(ismcts_with_config(state, rng, &config))
+}
+
+/// Like `ismcts`, but returns the statistics of every action and determinization along with the
+/// chosen action, e.g. to display evaluations or log training data.
+pub fn ismcts_with_statistics<
+    R: Rng + Clone,
+    S: State<A, P> + Determinable<S, A, P>,
+    A: Action + Eq + Hash,
+    P: Player,
+>(state: &S, rng: &R, num_determinizations: u32, num_simulations: u32, aggregation: &Aggregation<A, P>) -> Result<IsmctsResult<A, P>, IsmctsError> {
+    let config = IsmctsConfig::new(num_determinizations, num_simulations).with_aggregation(aggregation.clone());
+    ismcts_with_config(state, rng, &config)
+}
+
+/// Searches the determinizations one after the other, as configured by `config`.
+pub fn ismcts_with_config<
+    R: Rng + Clone,
+    S: State<A, P> + Determinable<S, A, P>,
+    A: Action + Eq + Hash,
+    P: Player,
+>(state: &S, rng: &R, config: &IsmctsConfig<A, P>) -> Result<IsmctsResult<A, P>, IsmctsError> {
+    check_decidable(state)?;
+
+    let perspective_player = state.current_player();
+    search_determinizations(rng, config, perspective_player.clone(), |rng, _| {
+        state.determine_weighted(rng, perspective_player.clone())
+    })
+}
+
+/// Like `ismcts`, but searches for `budget` rather than a fixed number of simulations. The
+/// determinizations are searched round-robin, so each gets an equal share of the time however
+/// long it turns out to be.
+pub fn ismcts_for<
+    R: Rng + Clone,
+    S: State<A, P> + Determinable<S, A, P>,
+    A: Action + Eq + Hash,
+    P: Player,
+>(state: &S, rng: &R, num_determinizations: u32, budget: Duration) -> Result<A, IsmctsError> {
+    check_decidable(state)?;
+
+    let deadline = Instant::now() + budget;
+    let perspective_player = state.current_player();
+
+    let mut searches: Vec<_> = (0..num_determinizations)
+        .map(|determinization_idx| {
+            let seed = stream_seed(rng, determinization_idx);
+            let mut rng = seeded_rng(seed);
+            let (game, weight) = state.determine_weighted(&mut rng, perspective_player.clone());
+
+            (determinization_idx, seed, rng, GameTree::<S, A, P>::new(game), weight)
+        })
+        .collect();
+
+    let mut last_error = None;
+    while !searches.is_empty() && Instant::now() < deadline {
+        // a determinization which can't be searched doesn't contribute any scores
+        searches.retain_mut(|(_, _, rng, decision_tree, _)| match decision_tree.search(rng) {
+            Ok(()) => true,
+            Err(error) => {
+                last_error = Some(error.into_formatted());
+                false
+            }
+        });
+    }
+
+    if let (true, Some(error)) = (searches.is_empty(), last_error) {
+        return Err(IsmctsError::Search(error));
+    }
+
+    let determinizations = searches
+        .into_iter()
+        .map(|(determinization_idx, seed, _, decision_tree, weight)| Determinization {
+            determinization_idx,
+            seed,
+            weight,
+            scores: decision_tree.root_scores(),
+        })
+        .collect();
+
+    best_action(Ok(Aggregation::default().aggregate(determinizations, perspective_player, Perspective::default())))
+}
+
+/// Searches `config.num_determinizations` weighted states made by
+/// `determine(rng, determinization_idx)` one after the other, and aggregates their results for
+/// `player`.
+pub(crate) fn search_determinizations<
+    R: Rng + Clone,
+    S: State<A, P>,
+    A: Action,
+    P: Player,
+    F: FnMut(&mut StreamRng, u32) -> (S, ScoreValue),
+>(rng: &R, config: &IsmctsConfig<A, P>, player: P, mut determine: F) -> Result<IsmctsResult<A, P>, IsmctsError> {
+    if config.allocation == SimulationAllocation::SuccessiveHalving {
+        let searches = (0..config.num_determinizations)
+            .map(|determinization_idx| {
+                let seed = stream_seed(rng, determinization_idx);
+                let mut rng = seeded_rng(seed);
+                let (game, weight) = determine(&mut rng, determinization_idx);
+
+                RoundSearch { determinization_idx, seed, weight, rng, tree: config.tree(game) }
+            })
+            .collect();
+
+        return search_successive_halving(searches, config, player, |batch| {
+            batch.into_iter().map(|(search, num_simulations)| search.run(config, num_simulations)).collect()
+        });
+    }
+
     let mut determinizations: Determinizations<A, P> = Vec::new();
+    let mut last_error = None;
 
-    for determinization_idx in 0..num_determinizations {
+    for determinization_idx in 0..config.num_determinizations {
         {
-            let mut rng = clone_and_advance_rng(rng, determinization_idx);
-            let game = state.determine(&mut rng, state.current_player());
+            let seed = stream_seed(rng, determinization_idx);
+            let mut rng = seeded_rng(seed);
+            let (game, weight) = determine(&mut rng, determinization_idx);
 
-            let mut decision_tree = GameTree::new(game);
+            let mut decision_tree = config.tree(game);
+
+            // a determinization which can't be searched doesn't contribute any scores
+            match config.search_tree(&mut decision_tree, &mut rng, config.num_simulations) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(error) => {
+                    last_error = Some(error);
+                    continue;
+                }
+            }
 
-            decision_tree.search_n(&mut rng, num_simulations);
+            let determinization = Determinization {
+                determinization_idx,
+                seed,
+                weight,
+                scores: decision_tree.root_scores(),
+            };
 
-            determinizations
-                .push(Determinization {
-                    determinization_idx,
-                    scores: decision_tree.root_scores(),
-                });
+            config.completed(&determinization);
+            determinizations.push(determinization);
+
+            if let Some(adaptive_stopping) = config.adaptive_stopping {
+                if adaptive_stopping.is_settled(&determinizations, config, player.clone()) {
+                    break;
+                }
+            }
         }
     }
 
-    let current_player = state.current_player();
+    if determinizations.is_empty() {
+        if let Some(error) = last_error {
+            return Err(IsmctsError::Search(error));
+        }
 
-    let mut total_action_scores: HashMap<&A, HashMap<P, f32>> = HashMap::default();
-    for determinization in &determinizations {
-        for score in &determinization.scores {
-            total_action_scores
-                .entry(&score.action)
-                .and_modify(|map| {
-                    map.entry(score.player)
-                        .and_modify(|s| *s += score.score)
-                        .or_insert(score.score);
-                }).or_insert({
-                let mut map = HashMap::new();
-                map.insert(score.player, score.score);
-                map
-            });
+        if config.is_cancelled() {
+            return Err(IsmctsError::Cancelled);
         }
     }
 
-    let best_action = total_action_scores.iter().max_by(|a, b| {
-        let a_score = a.1.get(&current_player).unwrap_or(&0f32);
-        let b_score = b.1.get(&current_player).unwrap_or(&0f32);
+    Ok(config.aggregate(determinizations, player))
+}
+
+/// a determinization searched over several rounds of a `SimulationAllocation`
+struct RoundSearch<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    determinization_idx: u32,
+    seed: u64,
+    weight: ScoreValue,
+    rng: StreamRng,
+    tree: GameTree<S, A, P>,
+}
+
+type RoundResult<S, A, P> = (RoundSearch<S, A, P>, Result<bool, SearchError>);
+
+impl<S, A, P> RoundSearch<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    fn run(mut self, config: &IsmctsConfig<A, P>, num_simulations: u32) -> RoundResult<S, A, P> {
+        let result = config.search_tree(&mut self.tree, &mut self.rng, num_simulations);
+        (self, result)
+    }
 
-        // todo: maximize the difference between their best action the sum of other players' actions.
+    /// how much better the best action looks than the next best, the smaller the less certain
+    fn value_gap(&self, config: &IsmctsConfig<A, P>, player: P) -> ScoreValue {
+        let mut values: Vec<ScoreValue> = self.tree
+            .root_values(config.perspective, player)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+        values.sort_by(|a, b| b.total_cmp(a));
 
-        a_score.total_cmp(&b_score)
-    }).unwrap();
+        match values[..] {
+            [best, next_best, ..] => best - next_best,
+            _ => ScoreValue::MAX,
+        }
+    }
 
-    let best_action = *(best_action.0);
-    let best_action = best_action.clone();
-    Some(best_action)
+    fn determinization(&self) -> Determinization<A, P> {
+        Determinization {
+            determinization_idx: self.determinization_idx,
+            seed: self.seed,
+            weight: self.weight,
+            scores: self.tree.root_scores(),
+        }
+    }
+}
+
+/// Spends the simulation budget in rounds, searching the more uncertain half of the
+/// determinizations again after each round. `run_round` searches each of a round's
+/// determinizations for the given number of simulations.
+fn search_successive_halving<S, A, P, F>(searches: Vec<RoundSearch<S, A, P>>, config: &IsmctsConfig<A, P>, player: P, run_round: F) -> Result<IsmctsResult<A, P>, IsmctsError>
+    where
+        S: State<A, P>,
+        A: Action,
+        P: Player,
+        F: Fn(Vec<(RoundSearch<S, A, P>, u32)>) -> Vec<RoundResult<S, A, P>>,
+{
+    let num_rounds = searches.len().max(2).next_power_of_two().trailing_zeros();
+    let budget = config.num_determinizations as u64 * config.num_simulations as u64;
+    let round_budget = budget / num_rounds as u64;
+
+    let mut active = searches;
+    let mut finished = Vec::with_capacity(active.len());
+    let mut last_error = None;
+    for round in 0..num_rounds {
+        if active.is_empty() || config.is_cancelled() {
+            break;
+        }
+
+        let num_simulations = (round_budget / active.len() as u64).clamp(1, u32::MAX as u64) as u32;
+        let batch = active.into_iter().map(|search| (search, num_simulations)).collect();
+
+        let mut searched = Vec::new();
+        for (search, result) in run_round(batch) {
+            match result {
+                Ok(true) => searched.push(search),
+                // a determinization cut short in the first round is left out rather than skewing
+                // the result, later rounds only added to a complete search
+                Ok(false) if round > 0 => finished.push(search),
+                Ok(false) => {}
+                // a determinization which can't be searched doesn't contribute any scores
+                Err(error) => last_error = Some(error),
+            }
+        }
+
+        let mut searched: Vec<_> = searched
+            .into_iter()
+            .map(|search| (search.value_gap(config, player.clone()), search))
+            .collect();
+        searched.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        let num_kept = searched.len().div_ceil(2);
+        finished.extend(searched.drain(num_kept..).map(|(_, search)| search));
+        active = searched.into_iter().map(|(_, search)| search).collect();
+    }
+    finished.extend(active);
+
+    if finished.is_empty() {
+        if let Some(error) = last_error {
+            return Err(IsmctsError::Search(error));
+        }
+
+        if config.is_cancelled() {
+            return Err(IsmctsError::Cancelled);
+        }
+    }
+
+    let determinizations: Determinizations<A, P> = finished.iter().map(RoundSearch::determinization).collect();
+    for determinization in &determinizations {
+        config.completed(determinization);
+    }
+
+    Ok(config.aggregate(determinizations, player))
 }
 
 pub fn ismcts_mt<
@@ -84,166 +873,532 @@ pub fn ismcts_mt<
     S: State<A, P> + Determinable<S, A, P> + Send,
     A: Action + Send + Sync + Eq + Hash,
     P: Player + Send + Sync,
->(state: &S, rng: &R, num_determinizations: u32, num_simulations: u32) -> Option<A> {
-    let determinizations: Arc<Mutex<Determinizations<A, P>>> = Arc::new(Mutex::new(Vec::new()));
+>(state: &S, rng: &R, num_determinizations: u32, num_simulations: u32) -> Result<A, IsmctsError> {
+    best_action(ismcts_mt_with_config(state, rng, &IsmctsConfig::new(num_determinizations, num_simulations)))
+}
 
-    thread::scope(|scope| {
-        for determinization_idx in 0..num_determinizations {
-            {
-                let mut rng = clone_and_advance_rng(rng, determinization_idx);
+pub fn ismcts_mt_with_aggregation<
+    R: Rng + Clone + Send,
+    S: State<A, P> + Determinable<S, A, P> + Send,
+    A: Action + Send + Sync + Eq + Hash,
+    P: Player + Send + Sync,
+>(state: &S, rng: &R, num_determinizations: u32, num_simulations: u32, aggregation: &Aggregation<A, P>) -> Result<A, IsmctsError> {
+    let config = IsmctsConfig::new(num_determinizations, num_simulations).with_aggregation(aggregation.clone());
+    best_action(ismcts_mt_with_config(state, rng, &config))
+}
 
-                let determinization_scores = determinizations.clone();
+/// Searches the determinizations on a pool of at most `parallelism` worker threads, rather than a
+/// thread for each determinization.
+pub fn ismcts_mt_with_parallelism<
+    R: Rng + Clone + Send,
+    S: State<A, P> + Determinable<S, A, P> + Send,
+    A: Action + Send + Sync + Eq + Hash,
+    P: Player + Send + Sync,
+>(state: &S, rng: &R, num_determinizations: u32, num_simulations: u32, aggregation: &Aggregation<A, P>, parallelism: usize) -> Result<A, IsmctsError> {
+    best_action(ismcts_mt_with_statistics(state, rng, num_determinizations, num_simulations, aggregation, parallelism))
+}
 
-                let game = state.determine(&mut rng, state.current_player());
+/// Like `ismcts_mt_with_parallelism`, but returns the statistics of every action and
+/// determinization along with the chosen action.
+pub fn ismcts_mt_with_statistics<
+    R: Rng + Clone + Send,
+    S: State<A, P> + Determinable<S, A, P> + Send,
+    A: Action + Send + Sync + Eq + Hash,
+    P: Player + Send + Sync,
+>(state: &S, rng: &R, num_determinizations: u32, num_simulations: u32, aggregation: &Aggregation<A, P>, parallelism: usize) -> Result<IsmctsResult<A, P>, IsmctsError> {
+    let config = IsmctsConfig::new(num_determinizations, num_simulations)
+        .with_aggregation(aggregation.clone())
+        .with_parallelism(parallelism);
 
-                let mut decision_tree = GameTree::new(game);
+    ismcts_mt_with_config(state, rng, &config)
+}
 
-                scope.spawn(move || {
-                    decision_tree.search_n(&mut rng, num_simulations);
+/// Searches the determinizations on a pool of worker threads, as configured by `config`.
+pub fn ismcts_mt_with_config<
+    R: Rng + Clone + Send,
+    S: State<A, P> + Determinable<S, A, P> + Send,
+    A: Action + Send + Sync + Eq + Hash,
+    P: Player + Send + Sync,
+>(state: &S, rng: &R, config: &IsmctsConfig<A, P>) -> Result<IsmctsResult<A, P>, IsmctsError> {
+    check_decidable(state)?;
 
-                    determinization_scores
-                        .lock()
-                        .unwrap()
-                        .push(Determinization {
-                            determinization_idx,
-                            scores: decision_tree.root_scores(),
-                        });
-                });
-            }
-        }
-    });
+    if config.allocation == SimulationAllocation::SuccessiveHalving {
+        let searches = (0..config.num_determinizations)
+            .map(|determinization_idx| {
+                let seed = stream_seed(rng, determinization_idx);
+                let mut rng = seeded_rng(seed);
+                let (game, weight) = state.determine_weighted(&mut rng, state.current_player());
 
-    let current_player = state.current_player();
+                RoundSearch { determinization_idx, seed, weight, rng, tree: config.tree(game) }
+            })
+            .collect();
 
-    let mut total_action_scores: HashMap<&A, HashMap<P, f32>> = HashMap::default();
-    let determinizations = determinizations.lock().unwrap();
-    for determinization in determinizations.iter() {
-        for score in &determinization.scores {
-            total_action_scores
-                .entry(&score.action)
-                .and_modify(|map| {
-                    map.entry(score.player)
-                        .and_modify(|s| *s += score.score)
-                        .or_insert(score.score);
-                }).or_insert({
-                let mut map = HashMap::new();
-                map.insert(score.player, score.score);
-                map
-            });
-        }
+        return search_successive_halving(searches, config, state.current_player(), |batch| {
+            run_batch(batch, config.parallelism, |(search, num_simulations)| search.run(config, num_simulations))
+        });
     }
 
+    let items = (0..config.num_determinizations)
+        .map(|determinization_idx| {
+            let seed = stream_seed(rng, determinization_idx);
+            let mut rng = seeded_rng(seed);
+            let (game, weight) = state.determine_weighted(&mut rng, state.current_player());
 
-    let best_action = total_action_scores.iter().max_by(|a, b| {
-        let a_score = a.1.get(&current_player).unwrap_or(&0f32);
-        let b_score = b.1.get(&current_player).unwrap_or(&0f32);
+            (determinization_idx, seed, rng, game, weight)
+        })
+        .collect();
 
-        // todo: maximize the difference between their best action the sum of other players' actions.
+    let determinizations = run_batch(items, config.parallelism, |(determinization_idx, seed, mut rng, game, weight)| {
+        let mut decision_tree = config.tree(game);
 
-        a_score.total_cmp(&b_score)
-    }).unwrap();
+        // a determinization cut short by cancellation is left out rather than skewing the result
+        if !config.search_tree(&mut decision_tree, &mut rng, config.num_simulations)? {
+            return Ok(None);
+        }
 
-    let best_action = *(best_action.0);
-    let best_action = best_action.clone();
-    Some(best_action)
-}
+        let determinization = Determinization {
+            determinization_idx,
+            seed,
+            weight,
+            scores: decision_tree.root_scores(),
+        };
+
+        config.completed(&determinization);
+        Ok(Some(determinization))
+    });
 
-fn clone_and_advance_rng<R: Rng + Clone>(rng: &R, delta: u32) -> R {
-    // clone the rng so each thread has its own copy
-    let mut rng = rng.clone();
+    // a determinization which can't be searched doesn't contribute any scores
+    let mut completed = Vec::with_capacity(determinizations.len());
+    let mut last_error = None;
+    for determinization in determinizations {
+        match determinization {
+            Ok(Some(determinization)) => completed.push(determinization),
+            Ok(None) => {}
+            Err(error) => last_error = Some(error),
+        }
+    }
+
+    if completed.is_empty() {
+        if let Some(error) = last_error {
+            return Err(IsmctsError::Search(error));
+        }
 
-    // advance the RNG by jumping ahead 'determinization_idx' number of jumps before
-    // applying a determinization, that way each determinization is unique
-    for _ in 0..delta {
-        rng.next_u32();
+        if config.is_cancelled() {
+            return Err(IsmctsError::Cancelled);
+        }
     }
 
-    rng
+    Ok(config.aggregate(completed, state.current_player()))
 }
 
-pub trait IsMctsAgent<P: Player> {
+/// An agent which plays by searching determinizations one after the other. The trait is generic
+/// rather than its methods, so agents can be boxed as `dyn IsMctsAgent<R, S, A, P>`.
+pub trait IsMctsAgent<R, S, A, P>
+    where
+        R: Rng + Clone,
+        S: State<A, P> + Determinable<S, A, P>,
+        A: Action + Eq + Hash,
+        P: Player,
+{
     fn player(&self) -> P;
-    fn decide<
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A>;
+}
+
+/// Every `agents::Agent` can play wherever an `IsMctsAgent` is expected.
+impl<R, S, A, P, T> IsMctsAgent<R, S, A, P> for T
+    where
         R: Rng + Clone,
         S: State<A, P> + Determinable<S, A, P>,
         A: Action + Eq + Hash,
-    >(&self, rng: &mut R, state: &S) -> Option<A>;
+        P: Player,
+        T: AnyAgent<R, S, A, P>,
+{
+    fn player(&self) -> P {
+        AnyAgent::player(self)
+    }
+
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A> {
+        AnyAgent::decide(self, rng, state)
+    }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Agent<P: Player> {
     player: P,
     num_determinations: u32,
     num_simulations: u32,
+    skill: Skill,
 }
 
-impl<P: Player> IsMctsAgent<P> for Agent<P> {
-    fn player(&self) -> P {
-        self.player
+impl<P: Player> Agent<P> {
+    /// Plays for `player` by searching `num_determinations` determinizations with
+    /// `num_simulations` simulations each, neither of which can be zero.
+    pub fn new(player: P, num_determinations: u32, num_simulations: u32) -> Result<Self, AgentError> {
+        check_search_budget(num_determinations, num_simulations)?;
+        Ok(Self { player, num_determinations, num_simulations, skill: Skill::default() })
     }
 
-    fn decide<
+    /// Weakens the agent to `skill`, e.g. `Skill::easy()`, whose simulation cap applies to each
+    /// determinization.
+    pub fn with_skill(mut self, skill: Skill) -> Self {
+        self.skill = skill;
+        self
+    }
+
+    pub fn num_determinations(&self) -> u32 {
+        self.num_determinations
+    }
+
+    pub fn num_simulations(&self) -> u32 {
+        self.num_simulations
+    }
+
+    pub fn skill(&self) -> &Skill {
+        &self.skill
+    }
+
+    /// searches the state and decides, among the most visited few actions if the agent is below
+    /// full strength
+    fn search_and_choose<R, S, A>(&self, rng: &mut R, state: &S) -> Option<Decision<A>>
+        where
+            R: Rng + Clone,
+            S: State<A, P> + Determinable<S, A, P>,
+            A: Action + Eq + Hash,
+    {
+        let config = IsmctsConfig::new(self.num_determinations, self.skill.simulations(self.num_simulations));
+        let mut result = ismcts_with_config(state, rng, &config).ok()?;
+        choose_by_skill(&self.skill, rng, &mut result, &self.player);
+        result.decision(&self.player)
+    }
+}
+
+/// Has an agent below full strength replace the result's best action by one chosen among the most
+/// visited few, see `Skill::choose`.
+fn choose_by_skill<R: Rng, A: Action, P: Player>(skill: &Skill, rng: &mut R, result: &mut IsmctsResult<A, P>, player: &P) {
+    if skill.is_full_strength() {
+        return;
+    }
+
+    let actions = result
+        .visit_distribution()
+        .into_iter()
+        .map(|(action, visit_share)| {
+            let value = result.action_value(&action, player).unwrap_or(0.0);
+            (action, visit_share, value)
+        })
+        .collect();
+
+    if let Some(action) = skill.choose(rng, actions) {
+        result.best_action = Some(action);
+    }
+}
+
+impl<R, S, A, P> AnyAgent<R, S, A, P> for Agent<P>
+    where
         R: Rng + Clone,
         S: State<A, P> + Determinable<S, A, P>,
         A: Action + Eq + Hash,
-    >(&self, rng: &mut R, state: &S) -> Option<A> {
-        ismcts(
-            state,
-            rng,
-            self.num_determinations,
-            self.num_simulations,
-        )
+        P: Player,
+{
+    fn player(&self) -> P {
+        self.player.clone()
+    }
+
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A> {
+        self.search_and_choose(rng, state).map(|decision| decision.action)
+    }
+
+    fn decide_in_detail(&self, rng: &mut R, state: &S) -> Option<Decision<A>> {
+        self.search_and_choose(rng, state)
     }
 }
 
-pub trait IsMctsMtAgent<P: Player> {
+/// An agent which plays by searching determinizations on worker threads, which can be boxed as
+/// `dyn IsMctsMtAgent<R, S, A, P>` like `IsMctsAgent`.
+pub trait IsMctsMtAgent<R, S, A, P>
+    where
+        R: Rng + Clone + Send,
+        S: State<A, P> + Determinable<S, A, P> + Send,
+        A: Action + Send + Sync + Eq + Hash,
+        P: Player + Send + Sync,
+{
     fn player(&self) -> P;
-    fn decide<
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A>;
+
+    /// Decides for many independent states at once, sharing one pool of worker threads between
+    /// them rather than searching each state in turn.
+    fn decide_batch(&self, rng: &mut R, states: &[S]) -> Vec<Option<A>> where Self: Sized + Sync, R: SeedableRng, S: Sync {
+        let items = states
+            .iter()
+            .enumerate()
+            .map(|(idx, state)| (R::seed_from_u64(stream_seed(rng, idx as u32)), state))
+            .collect();
+
+        run_batch(items, default_parallelism(), |(mut rng, state)| self.decide(&mut rng, state))
+    }
+}
+
+/// Every `agents::Agent` can play wherever an `IsMctsMtAgent` is expected.
+impl<R, S, A, P, T> IsMctsMtAgent<R, S, A, P> for T
+    where
         R: Rng + Clone + Send,
         S: State<A, P> + Determinable<S, A, P> + Send,
         A: Action + Send + Sync + Eq + Hash,
-    >(&self, rng: &mut R, state: &S) -> Option<A>;
+        P: Player + Send + Sync,
+        T: AnyAgent<R, S, A, P>,
+{
+    fn player(&self) -> P {
+        AnyAgent::player(self)
+    }
+
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A> {
+        AnyAgent::decide(self, rng, state)
+    }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MtAgent<P: Player> {
     pub player: P,
     pub num_determinations: u32,
     pub num_simulations: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub skill: Skill,
 }
 
-impl<P: Player> IsMctsMtAgent<P> for MtAgent<P> {
+impl<P: Player> MtAgent<P> {
+    /// Plays for `player` by searching `num_determinations` determinizations on worker threads with
+    /// `num_simulations` simulations each, neither of which can be zero.
+    pub fn new(player: P, num_determinations: u32, num_simulations: u32) -> Result<Self, AgentError> {
+        check_search_budget(num_determinations, num_simulations)?;
+        Ok(Self { player, num_determinations, num_simulations, skill: Skill::default() })
+    }
+
+    /// Weakens the agent to `skill`, e.g. `Skill::easy()`, whose simulation cap applies to each
+    /// determinization.
+    pub fn with_skill(mut self, skill: Skill) -> Self {
+        self.skill = skill;
+        self
+    }
+
+    /// searches the state on worker threads and decides, among the most visited few actions if the
+    /// agent is below full strength
+    fn search_and_choose<R, S, A>(&self, rng: &mut R, state: &S) -> Option<Decision<A>>
+        where
+            R: Rng + Clone + Send,
+            S: State<A, P> + Determinable<S, A, P> + Send,
+            A: Action + Send + Sync + Eq + Hash,
+            P: Send + Sync,
+    {
+        let config = IsmctsConfig::new(self.num_determinations, self.skill.simulations(self.num_simulations));
+        let mut result = ismcts_mt_with_config(state, rng, &config).ok()?;
+        choose_by_skill(&self.skill, rng, &mut result, &self.player);
+        result.decision(&self.player)
+    }
+}
+
+impl<R, S, A, P> AnyAgent<R, S, A, P> for MtAgent<P>
+    where
+        R: Rng + Clone + Send,
+        S: State<A, P> + Determinable<S, A, P> + Send,
+        A: Action + Send + Sync + Eq + Hash,
+        P: Player + Send + Sync,
+{
     fn player(&self) -> P {
-        self.player
+        self.player.clone()
     }
 
-    fn decide<
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A> {
+        self.search_and_choose(rng, state).map(|decision| decision.action)
+    }
+
+    fn decide_in_detail(&self, rng: &mut R, state: &S) -> Option<Decision<A>> {
+        self.search_and_choose(rng, state)
+    }
+}
+
+
+/// lets a searching agent be used wherever a policy is, e.g. as a `ScriptedAgent`'s fallback
+impl<R, S, A, P> Policy<R, S, A, P> for MtAgent<P>
+    where
         R: Rng + Clone + Send,
         S: State<A, P> + Determinable<S, A, P> + Send,
         A: Action + Send + Sync + Eq + Hash,
-    >(&self, rng: &mut R, state: &S) -> Option<A> {
-        ismcts(
-            state,
-            rng,
-            self.num_determinations,
-            self.num_simulations,
-        )
+        P: Player + Send + Sync,
+{
+    fn decide(&mut self, rng: &mut R, state: &S) -> Option<A> {
+        IsMctsMtAgent::<R, S, A, P>::decide(self, rng, state)
     }
 }
 
+/// The errors of a `MultithreadedInformationSetGame`, which are those of any other game runner.
+pub type MultithreadedInformationSetGameError<A, P> = GameError<A, P>;
 
-#[derive(Error, Debug)]
-pub enum MultithreadedInformationSetGameError<A: Action + Debug, P: Player + Debug> {
-    #[error("there is no agent mapped to player {0}")]
-    NoAgentForPlayer(P),
+/// What the `MtAgent`s' searches keep between turns: the tracked beliefs, the determinization trees
+/// kept for reuse, and how many rng streams have been handed out.
+struct Searches<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    beliefs: HashMap<P, BeliefState<S>>,
+    track_belief: Option<TrackBelief<S, A, P>>,
+    inference_model: Option<Box<dyn InferenceModel<S, A, P>>>,
+    retained_trees: HashMap<P, RetainedTrees<S, A, P>>,
+    tree_observer: Option<Observer<S, A, P>>,
+    num_streams: u32,
+}
 
-    #[error("agent {0} was unable to decide what to do")]
-    AgentDecisionError(MtAgent<P>),
+/// the seats of a `MultithreadedInformationSetGame`'s `MtAgent`s, which it searches for itself
+struct MtSeats<'a, S, A, P> where S: State<A, P>, A: Action, P: Player {
+    agents: &'a HashMap<P, MtAgent<P>>,
+    searches: &'a mut Searches<S, A, P>,
+}
 
-    #[error("unable to apply action {0}")]
-    ActionApplicationError(A)
+impl<R, S, A, P> Seats<R, S, A, P> for MtSeats<'_, S, A, P>
+    where
+        R: Rng + Clone + Send,
+        S: State<A, P> + Determinable<S, A, P> + Send,
+        A: Action + Send + Sync + Eq + Hash + Describe,
+        P: Player + Send + Sync + Describe,
+{
+    fn decide(&mut self, rng: &mut R, state: &S, history: &[(P, A)], player: &P, deadline: Option<Instant>) -> Result<Option<Decided<A>>, GameError<A, P>> {
+        let Some(agent) = self.agents.get(player) else {
+            return Ok(None);
+        };
+
+        let num_simulations = agent.skill.simulations(agent.num_simulations);
+        let mut result = self.searches.search_for_agent(rng, state, history, player.clone(), agent.num_determinations, num_simulations, deadline)?;
+        choose_by_skill(&agent.skill, rng, &mut result, player);
+
+        Ok(Some(match result.decision(player) {
+            Some(decision) => (Some(decision.action), decision.value),
+            None => (None, None),
+        }))
+    }
+
+    fn played(&mut self, rng: &mut R, previous: &S, state: &S, action: &A) {
+        let searches = &mut *self.searches;
+        if let Some(track_belief) = searches.track_belief {
+            for (player, belief) in searches.beliefs.iter_mut() {
+                track_belief(belief, rng, previous, state, player.clone(), action, searches.inference_model.as_deref());
+            }
+        }
+
+        if let Some(observer) = &searches.tree_observer {
+            for (player, trees) in searches.retained_trees.iter_mut() {
+                let observed = (observer.observe)(previous, action, player.clone());
+                trees.retain_mut(|(tree, _, _)| observer.advance(tree, player.clone(), &observed, state));
+            }
+        }
+    }
+
+    fn restored(&mut self, rng: &mut R, state: &S) {
+        // the particles were carried forward from a history which no longer happened
+        for (player, belief) in self.searches.beliefs.iter_mut() {
+            *belief = BeliefState::sample(rng, state, player.clone(), belief.num_particles());
+        }
+
+        self.searches.retained_trees.clear();
+    }
 }
 
+impl<S, A, P> Searches<S, A, P>
+    where
+        S: State<A, P> + Determinable<S, A, P> + Send,
+        A: Action + Send + Sync + Eq + Hash + Describe,
+        P: Player + Send + Sync + Describe,
+{
+    /// Searches for `player`'s `MtAgent`, over the particles of its tracked belief if it has one, and
+    /// otherwise over its determinization trees kept from previous turns topped up with fresh
+    /// determinizations, which are kept for the next turn if trees are reused. Under a clock the
+    /// search stops at `deadline`, deciding by what it found so far. Fails only if none of the
+    /// determinizations could be searched.
+    #[allow(clippy::too_many_arguments)]
+    fn search_for_agent<R: Rng + Clone>(&mut self, rng: &R, state: &S, history: &[(P, A)], player: P, num_determinizations: u32, num_simulations: u32, deadline: Option<Instant>) -> Result<IsmctsResult<A, P>, GameError<A, P>> {
+        let particles: Option<Vec<(S, ScoreValue)>> = self.beliefs
+            .get(&player)
+            .map(|belief| belief.particles().iter().map(|particle| (particle.state.clone(), particle.weight)).collect());
+
+        let is_belief = particles.is_some();
+        let mut trees: RetainedTrees<S, A, P> = match particles {
+            Some(particles) => particles
+                .into_iter()
+                .map(|(state, weight)| (GameTree::new(state), weight, self.next_stream_seed(rng)))
+                .collect(),
+            None => self.retained_trees.remove(&player).unwrap_or_default(),
+        };
+
+        while !is_belief && trees.len() < num_determinizations as usize {
+            let seed = self.next_stream_seed(rng);
+            let (game, weight) = state.determine_with_history(&mut seeded_rng(seed), player.clone(), history);
+            trees.push((GameTree::new(game), weight, seed));
+        }
+
+        let items: Vec<_> = trees
+            .into_iter()
+            .enumerate()
+            .map(|(determinization_idx, (tree, weight, seed))| (determinization_idx as u32, tree, weight, seed, seeded_rng(self.next_stream_seed(rng))))
+            .collect();
+
+        // each tree is searched on its own thread, checking the deadline between simulations, so
+        // it cuts every determinization's search equally short, though each is searched at least
+        // once to have something to decide by
+        let config = IsmctsConfig::<A, P>::new(num_determinizations, num_simulations);
+        let searched = run_batch(items, config.parallelism, |(determinization_idx, mut tree, weight, seed, mut rng)| {
+            for simulation_idx in 0..num_simulations {
+                if simulation_idx > 0 && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    break;
+                }
+
+                tree.search(&mut rng).map_err(SearchError::into_described)?;
+            }
+
+            Ok((determinization_idx, tree, weight, seed))
+        });
+
+        // a determinization which can't be searched doesn't contribute any scores
+        let num_searched = searched.len();
+        let mut searches = Vec::with_capacity(num_searched);
+        let mut last_error = None;
+        for search in searched {
+            match search {
+                Ok(search) => searches.push(search),
+                Err(error) => last_error = Some(error),
+            }
+        }
+
+        if let Some(error) = last_error {
+            if searches.is_empty() {
+                return Err(GameError::AgentSearchError(player, error));
+            }
+
+            log::warn!("{} of {num_searched} determinizations couldn't be searched, the last because {error}", num_searched - searches.len());
+        }
+
+        let determinizations = searches
+            .iter()
+            .map(|(determinization_idx, tree, weight, seed)| Determinization {
+                determinization_idx: *determinization_idx,
+                seed: *seed,
+                weight: *weight,
+                scores: tree.root_scores(),
+            })
+            .collect();
+
+        if !is_belief && self.tree_observer.is_some() {
+            let retained = searches.into_iter().map(|(_, tree, weight, seed)| (tree, weight, seed)).collect();
+            self.retained_trees.insert(player.clone(), retained);
+        }
+        Ok(Aggregation::default().aggregate(determinizations, player, Perspective::default()))
+    }
+
+    fn next_stream_seed<R: Rng + Clone>(&mut self, rng: &R) -> u64 {
+        self.num_streams = self.num_streams.wrapping_add(1);
+        stream_seed(rng, self.num_streams)
+    }
+}
+
+/// A game played out by agents of any kind, which searches for its own `MtAgent`s over
+/// determinizations, or tracked beliefs, of the hidden information, reusing their trees between
+/// turns if asked to.
 pub struct MultithreadedInformationSetGame<R, S, A, P>
     where
         R: Rng + Clone + Send,
@@ -254,62 +1409,189 @@ pub struct MultithreadedInformationSetGame<R, S, A, P>
     pub state: S,
     pub agents: HashMap<P, MtAgent<P>>,
     pub rng: R,
-    _phantom_a: PhantomData<A>
+    runner: Runner<R, S, A, P>,
+    searches: Searches<S, A, P>,
 }
 
 impl<R, S, A, P> MultithreadedInformationSetGame<R, S, A, P>
     where
         R: Rng + Clone + Send,
         S: State<A, P> + Determinable<S, A, P> + Send,
-        A: Action + Send + Sync + Eq + Hash + Debug,
-        P: Player + Send + Sync + Debug,
+        A: Action + Send + Sync + Eq + Hash + Describe,
+        P: Player + Send + Sync + Describe,
 {
     pub fn new(rng: R, state: S, agents: HashMap<P, MtAgent<P>>) -> Self {
         Self {
             state,
             agents,
             rng,
-            _phantom_a: Default::default(),
+            runner: Runner::new(HashMap::new()),
+            searches: Searches {
+                beliefs: HashMap::new(),
+                track_belief: None,
+                inference_model: None,
+                retained_trees: HashMap::new(),
+                tree_observer: None,
+                num_streams: 0,
+            },
         }
     }
 
-    pub fn run(&mut self) -> Result<(), MultithreadedInformationSetGameError<A, P>> {
-        loop {
-            if self.state.outcome().is_some() {
-                break;
-            }
-
-            self.step()?;
-        }
+    /// Seats an agent of any other kind for `player`, e.g. an `mcts::Agent` or a `RandomAgent`, so
+    /// agents of different kinds can play each other. An `MtAgent` mapped to the same player takes
+    /// precedence, and only `MtAgent`s reuse their trees between turns or search tracked beliefs.
+    pub fn with_agent<T: AnyAgent<R, S, A, P> + 'static>(mut self, player: P, agent: T) -> Self {
+        self.runner.agents.insert(player, Box::new(agent));
+        self
+    }
 
-        Ok(())
+    /// Has `policy` decide for `player` rather than a searching agent, e.g. a `PassThroughAgent`
+    /// for a pseudo-player representing the deck. An agent mapped to the same player takes
+    /// precedence.
+    pub fn with_policy<T: Policy<R, S, A, P> + 'static>(mut self, player: P, policy: T) -> Self {
+        self.runner.policies.insert(player, Box::new(policy));
+        self
     }
 
-    pub fn step(&mut self) -> Result<A, MultithreadedInformationSetGameError<A, P>> {
-        let current_player = self.state.current_player();
+    /// Tracks `player`'s belief about the hidden information with `num_particles` particles, which
+    /// are carried forward through every action as the player observed it, see
+    /// `BeliefState::track`. The player's agent then searches the particles rather than its own
+    /// determinizations.
+    pub fn with_belief_tracking(mut self, player: P, num_particles: usize) -> Self where S: ObservableState<A, P> + Observable<A, P> {
+        let belief = BeliefState::sample(&mut self.rng, &self.state, player.clone(), num_particles);
+        self.searches.beliefs.insert(player, belief);
 
-        let Some(current_agent) = self.agents.get(&current_player) else {
-            return Err(MultithreadedInformationSetGameError::NoAgentForPlayer(current_player))
+        let track_belief: TrackBelief<S, A, P> = |belief, mut rng, previous, state, player, action, model| {
+            belief.track(&mut rng, previous, state, player, action, model)
         };
+        self.searches.track_belief = Some(track_belief);
+        self
+    }
 
-        let Some(action) = current_agent.decide(&mut self.rng, &self.state) else {
-            return Err(MultithreadedInformationSetGameError::AgentDecisionError(current_agent.clone()))
-        };
+    /// Keeps each `MtAgent`'s determinization trees between its turns to reuse the search effort,
+    /// rather than determinizing and searching from scratch every turn. After each action a tree
+    /// moves on to the child reached by an action which looks the same to its player as the one
+    /// played, and is dropped if it has none or no longer matches what its player can see.
+    pub fn with_tree_reuse(mut self) -> Self where S: ObservableState<A, P> + Observable<A, P> {
+        self.searches.tree_observer = Some(Observer {
+            observe: |state, action, player| state.observe(action, player),
+            same_observation: |state, other, player| state.observation(player.clone()) == other.observation(player),
+        });
+        self
+    }
 
-        if let Ok(state) = self.state.apply_action(&mut self.rng, &action) {
-            self.state = state;
-        } else {
-            return Err(MultithreadedInformationSetGameError::ActionApplicationError(action))
-        }
+    pub fn belief(&self, player: P) -> Option<&BeliefState<S>> {
+        self.searches.beliefs.get(&player)
+    }
+
+    /// Conditions the tracked beliefs on each action played, through how likely `model` thinks
+    /// its player was to play it.
+    pub fn with_inference_model<M: InferenceModel<S, A, P> + 'static>(mut self, model: M) -> Self {
+        self.searches.inference_model = Some(Box::new(model));
+        self
+    }
+
+    /// Calls `on_turn_start` with the state and the player to move, before the player's agent or
+    /// policy decides what to do.
+    pub fn with_on_turn_start<F: FnMut(&S, &P) + 'static>(mut self, on_turn_start: F) -> Self {
+        self.runner.hooks.set_on_turn_start(on_turn_start);
+        self
+    }
+
+    /// Calls `on_action_applied` with the state an action led to, the player who played it and
+    /// the action, after each action is played.
+    pub fn with_on_action_applied<F: FnMut(&S, &P, &A) + 'static>(mut self, on_action_applied: F) -> Self {
+        self.runner.hooks.set_on_action_applied(on_action_applied);
+        self
+    }
+
+    /// Calls `on_outcome` with the final state and the outcome once an action ends the game, or an
+    /// agent resigns.
+    pub fn with_on_outcome<F: FnMut(&S, &Outcome<P>) + 'static>(mut self, on_outcome: F) -> Self {
+        self.runner.hooks.set_on_outcome(on_outcome);
+        self
+    }
+
+    /// Puts `player`'s agent on a clock, which each of its decisions is charged to. An `MtAgent`'s
+    /// search stops once its time is up, and other agents are told the time they have left through
+    /// `Agent::decide_within`.
+    pub fn with_time_control(mut self, player: P, time_control: TimeControl) -> Self {
+        self.runner.clocks.insert(player, time_control);
+        self
+    }
+
+    /// the time control of `player`'s agent, with the time it has left
+    pub fn time_control(&self, player: &P) -> Option<&TimeControl> {
+        self.runner.clocks.get(player)
+    }
+
+    /// Lets the agents resign lost games, which ends the game with an `Outcome::Resignation`.
+    /// Other kinds of agents on a clock decide within their budget instead, and don't resign.
+    pub fn with_resignation(mut self, resignation: Resignation) -> Self {
+        self.runner.resignations.set(resignation);
+        self
+    }
+
+    /// Records the game for replaying, drawing its random transitions from an rng seeded with
+    /// `seed` rather than the game's own, which the agents draw from too. `state_id` identifies
+    /// the initial state to whoever replays it. A game moved to another state with `restore`
+    /// doesn't replay its random transitions as they happened.
+    pub fn with_recording<I: Into<String>>(mut self, seed: u64, state_id: I) -> Self {
+        self.runner.recording = Some(Recording::new(seed, state_id.into()));
+        self
+    }
+
+    /// The game so far as a replay, which reproduces its random transitions if it was recorded
+    /// with `with_recording`.
+    pub fn replay(&self) -> Replay<A, P> {
+        self.runner.replay()
+    }
+
+    /// every action played so far, oldest first, along with the player who played it
+    pub fn history(&self) -> &[(P, A)] {
+        &self.runner.history
+    }
+
+    pub fn run(&mut self) -> Result<(), MultithreadedInformationSetGameError<A, P>> {
+        let mut seats = MtSeats { agents: &self.agents, searches: &mut self.searches };
+        self.runner.run(&mut self.state, &mut self.rng, &mut seats).map(|_| ())
+    }
+
+    /// Has the current player's agent or policy decide on an action and plays it. An agent which
+    /// resigns instead ends the game without playing anything.
+    pub fn step(&mut self) -> Result<A, MultithreadedInformationSetGameError<A, P>> {
+        let mut seats = MtSeats { agents: &self.agents, searches: &mut self.searches };
+        self.runner.step(&mut self.state, &mut self.rng, &mut seats)
+    }
+
+    /// Plays the current player's move decided outside of the game, e.g. by a network opponent or
+    /// in a GUI, in place of their agent or policy. Unlike `play` the action is checked to be
+    /// legal first.
+    pub fn step_with_action(&mut self, action: A) -> Result<(), MultithreadedInformationSetGameError<A, P>> {
+        let mut seats = MtSeats { agents: &self.agents, searches: &mut self.searches };
+        self.runner.step_with_action(&mut self.state, &mut self.rng, &mut seats, action)
+    }
+
+    /// Plays an action decided outside of the game's agents and policies, e.g. by a human. Any
+    /// chance events it leads to are resolved right away.
+    pub fn play(&mut self, action: A) -> Result<(), MultithreadedInformationSetGameError<A, P>> {
+        let mut seats = MtSeats { agents: &self.agents, searches: &mut self.searches };
+        self.runner.play(&mut self.state, &mut self.rng, &mut seats, action)
+    }
 
-        Ok(action)
+    /// Moves the game to `state`, reached by playing `history`, e.g. to undo a move. Each policy
+    /// is given the chance to invalidate whatever it had cached about the game.
+    pub fn restore(&mut self, state: S, history: Vec<(P, A)>) {
+        let mut seats = MtSeats { agents: &self.agents, searches: &mut self.searches };
+        self.runner.restore(&mut self.state, &mut self.rng, &mut seats, state, history);
     }
 
     pub fn is_terminated(&self) -> bool {
-        self.state.outcome().is_some()
+        self.outcome().is_some()
     }
 
+    /// the state's outcome, or the resignation of whoever resigned
     pub fn outcome(&self) -> Option<Outcome<P>> {
-        self.state.outcome()
+        self.runner.outcome(&self.state)
     }
 }
@@ -1,14 +1,21 @@
-use rand::{Rng};
-use crate::{Action, GameTree, Player, State};
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+use rand::{Rng, SeedableRng};
+use crate::{Action, GameTree, Player, SearchError, State};
+use crate::ai::agents::{Agent as AnyAgent, AgentError, Decision};
+use crate::ai::perspective::Perspective;
+use crate::ai::batch::{default_parallelism, run_batch};
+use crate::ai::rng::stream_seed;
+use crate::ai::skill::Skill;
 
 pub fn mcts<
     R: Rng,
     S: State<A, P>,
     A: Action,
     P: Player,
->(state: &S, rng: &mut R, num_simulations: u32) -> Option<A> {
-    let tree = build_monte_carlo_game_tree(state, rng, num_simulations);
-    tree.best_action().cloned()
+>(state: &S, rng: &mut R, num_simulations: u32) -> Result<A, SearchError<S::Error, A>> {
+    let tree = build_monte_carlo_game_tree(state, rng, num_simulations)?;
+    tree.best_action().cloned().ok_or(SearchError::NoDecision)
 }
 
 pub fn build_monte_carlo_game_tree<
@@ -16,41 +23,163 @@ pub fn build_monte_carlo_game_tree<
     S: State<A, P>,
     A: Action,
     P: Player,
->(state: &S, rng: &mut R, num_simulations: u32) -> GameTree<S, A, P> {
+>(state: &S, rng: &mut R, num_simulations: u32) -> Result<GameTree<S, A, P>, SearchError<S::Error, A>> {
     let mut tree = GameTree::new(state.clone());
-    tree.search_n(rng, num_simulations);
-    tree
+    tree.search_n(rng, num_simulations)?;
+    Ok(tree)
 }
 
 
-pub trait MctsAgent<P: Player> {
+/// An agent which plays by searching a game tree. The trait is generic rather than its methods,
+/// so agents of different kinds can be boxed as `dyn MctsAgent<R, S, A, P>` and stored together.
+pub trait MctsAgent<R, S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
     fn player(&self) -> P;
-    fn decide<
-        R: Rng,
-        S: State<A, P>,
-        A: Action,
-    >(&self, rng: &mut R, state: &S) -> Option<A>;
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A>;
+
+    /// Decides for many independent states at once, sharing one pool of worker threads between
+    /// them rather than searching each state in turn.
+    fn decide_batch(&self, rng: &mut R, states: &[S]) -> Vec<Option<A>>
+        where
+            Self: Sized + Sync,
+            R: Clone + Send + SeedableRng,
+            S: Sync,
+            A: Send,
+    {
+        let items = states
+            .iter()
+            .enumerate()
+            .map(|(idx, state)| (R::seed_from_u64(stream_seed(rng, idx as u32)), state))
+            .collect();
+
+        run_batch(items, default_parallelism(), |(mut rng, state)| self.decide(&mut rng, state))
+    }
+}
+
+/// Every `agents::Agent` can play wherever an `MctsAgent` is expected.
+impl<R, S, A, P, T> MctsAgent<R, S, A, P> for T where R: Rng, S: State<A, P>, A: Action, P: Player, T: AnyAgent<R, S, A, P> {
+    fn player(&self) -> P {
+        AnyAgent::player(self)
+    }
+
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A> {
+        AnyAgent::decide(self, rng, state)
+    }
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Agent<P: Player> {
     player: P,
     num_simulations: u32,
+    skill: Skill,
+}
+
+impl<P: Player> Agent<P> {
+    /// Plays for `player` by searching a tree with `num_simulations` simulations, which can't be
+    /// zero.
+    pub fn new(player: P, num_simulations: u32) -> Result<Self, AgentError> {
+        if num_simulations == 0 {
+            return Err(AgentError::NoSimulations);
+        }
+
+        Ok(Self { player, num_simulations, skill: Skill::default() })
+    }
+
+    /// Weakens the agent to `skill`, e.g. `Skill::easy()`.
+    pub fn with_skill(mut self, skill: Skill) -> Self {
+        self.skill = skill;
+        self
+    }
+
+    pub fn num_simulations(&self) -> u32 {
+        self.num_simulations
+    }
+
+    pub fn skill(&self) -> &Skill {
+        &self.skill
+    }
+
+    /// the action to play from a searched tree, which an agent below full strength chooses among
+    /// its most visited few
+    fn choose<R: Rng, S: State<A, P>, A: Action>(&self, rng: &mut R, tree: &GameTree<S, A, P>) -> Option<A> {
+        if self.skill.is_full_strength() {
+            return tree.best_action().cloned();
+        }
+
+        // both list the root's actions in the same order
+        let actions = tree
+            .root_visit_distribution()
+            .into_iter()
+            .zip(tree.root_values(Perspective::Absolute, self.player.clone()))
+            .map(|((action, visit_share), (_, value))| (action, visit_share, value))
+            .collect();
+
+        // a root solved without searching has no visits to choose by
+        self.skill.choose(rng, actions).or_else(|| tree.best_action().cloned())
+    }
+
+    /// Like `MctsAgent::decide`, returning why the search failed rather than only that it did.
+    pub fn try_decide<R: Rng, S: State<A, P>, A: Action>(&self, rng: &mut R, state: &S) -> Result<A, SearchError<S::Error, A>> {
+        let tree = build_monte_carlo_game_tree(state, rng, self.skill.simulations(self.num_simulations))?;
+        self.choose(rng, &tree).ok_or(SearchError::NoDecision)
+    }
+
+    /// Like `Agent::decide_in_detail`, returning why the search failed rather than only that it
+    /// did.
+    pub fn try_decide_in_detail<R: Rng, S: State<A, P>, A: Action>(&self, rng: &mut R, state: &S) -> Result<Decision<A>, SearchError<S::Error, A>> {
+        let tree = build_monte_carlo_game_tree(state, rng, self.skill.simulations(self.num_simulations))?;
+        let action = self.choose(rng, &tree).ok_or(SearchError::NoDecision)?;
+        let value = tree
+            .root_values(Perspective::Absolute, self.player.clone())
+            .into_iter()
+            .find(|(root_action, _)| *root_action == action)
+            .map(|(_, value)| value);
+
+        Ok(Decision {
+            action,
+            visits: tree.root_visit_distribution(),
+            value,
+        })
+    }
+
+    /// Like `Agent::decide_within`, returning why the search failed rather than only that it did.
+    pub fn try_decide_within<R: Rng, S: State<A, P>, A: Action>(&self, rng: &mut R, state: &S, budget: Duration) -> Result<A, SearchError<S::Error, A>> {
+        let deadline = Instant::now() + budget;
+        let mut tree = GameTree::new(state.clone());
+        for _ in 0..self.skill.simulations(self.num_simulations) {
+            if Instant::now() >= deadline {
+                break;
+            }
+
+            tree.search(rng)?;
+        }
+
+        self.choose(rng, &tree).ok_or(SearchError::NoDecision)
+    }
 }
 
-impl<P: Player> MctsAgent<P> for Agent<P> {
+/// logs why an agent's search failed, as the agent traits only return whether it did
+fn logged<T, E: Debug, A>(result: Result<T, SearchError<E, A>>) -> Option<T> {
+    result
+        .map_err(|error| log::warn!("an agent's search failed: {error}"))
+        .ok()
+}
+
+impl<R, S, A, P> AnyAgent<R, S, A, P> for Agent<P> where R: Rng, S: State<A, P>, A: Action, P: Player {
     fn player(&self) -> P {
-        self.player
-    }
-
-    fn decide<
-        R: Rng,
-        S: State<A, P>,
-        A: Action,
-    >(&self, rng: &mut R, state: &S) -> Option<A> {
-        mcts(
-            state,
-            rng,
-            self.num_simulations,
-        )
+        self.player.clone()
+    }
+
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A> {
+        logged(self.try_decide(rng, state))
+    }
+
+    fn decide_in_detail(&self, rng: &mut R, state: &S) -> Option<Decision<A>> {
+        logged(self.try_decide_in_detail(rng, state))
+    }
+
+    /// stops searching once the budget runs out, even if it hasn't run all of its simulations
+    fn decide_within(&self, rng: &mut R, state: &S, budget: Duration) -> Option<A> {
+        logged(self.try_decide_within(rng, state, budget))
     }
 }
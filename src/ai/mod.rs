@@ -1,4 +1,31 @@
 pub mod game_tree;
 pub mod random_rollout;
+pub mod rollout;
+pub mod evaluator;
 pub mod mcts;
+pub mod game;
+pub mod agents;
 pub mod ismcts;
+pub mod audit;
+pub mod scheduler;
+pub mod speculation;
+pub mod strictness;
+pub mod perspective;
+pub mod tournament;
+pub mod ratings;
+pub mod replay;
+pub mod selfplay;
+pub mod skill;
+pub mod calibration;
+pub mod ponder;
+pub mod cache;
+pub mod information_set;
+pub mod benchmark;
+pub mod policy;
+pub mod session;
+pub mod belief;
+pub mod determinize;
+#[cfg(feature = "tokio")]
+pub mod async_search;
+mod batch;
+mod rng;
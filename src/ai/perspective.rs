@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use crate::{Action, Player};
+use crate::ai::game_tree::score::{Score, ScoreValue};
+
+/// How a set of per-player scores is turned into a single value for one player.
+///
+/// Players who never scored have no entry in a set of scores, unless the state lists them through
+/// `State::players`. Without one they count as zero, but aren't part of the field's mean. The
+/// player's teammates are allies, so they're never counted as opponents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Perspective {
+    /// the player's own score
+    #[default]
+    Absolute,
+    /// the player's score minus the best opponent's score
+    RelativeToBestOpponent,
+    /// the player's score minus the mean of the opponents' scores
+    RelativeToField,
+}
+
+impl Perspective {
+    pub fn value<P: Player>(&self, scores: &HashMap<P, ScoreValue>, player: P) -> ScoreValue {
+        let own_score = scores.get(&player).copied().unwrap_or(0.0);
+        let teammates = player.teammates();
+        let opponent_scores = scores
+            .iter()
+            .filter(|(opponent, _)| **opponent != player && !teammates.contains(opponent))
+            .map(|(_, score)| *score);
+
+        match self {
+            Perspective::Absolute => own_score,
+            Perspective::RelativeToBestOpponent => {
+                match opponent_scores.fold(ScoreValue::NEG_INFINITY, ScoreValue::max) {
+                    ScoreValue::NEG_INFINITY => own_score,
+                    best_opponent_score => own_score - best_opponent_score,
+                }
+            }
+            Perspective::RelativeToField => {
+                let (total, count) = opponent_scores.fold((0.0, 0), |(total, count), score| (total + score, count + 1));
+                if count == 0 {
+                    own_score
+                } else {
+                    own_score - total / count as ScoreValue
+                }
+            }
+        }
+    }
+}
+
+/// Groups flat per-(action, player) scores, e.g. from `GameTree::root_scores`, into each action's
+/// per-player scores along with its visits.
+pub fn scores_by_action<A: Action, P: Player>(scores: &[Score<A, P>]) -> Vec<(A, u32, HashMap<P, ScoreValue>)> {
+    let mut grouped: Vec<(A, u32, HashMap<P, ScoreValue>)> = Vec::new();
+
+    for score in scores {
+        let idx = match grouped.iter().position(|(action, _, _)| *action == score.action) {
+            Some(idx) => idx,
+            None => {
+                grouped.push((score.action.clone(), score.num_visits, HashMap::new()));
+                grouped.len() - 1
+            }
+        };
+
+        *grouped[idx].2.entry(score.player.clone()).or_insert(0.0) += score.score;
+    }
+
+    grouped
+}
@@ -0,0 +1,136 @@
+use std::marker::PhantomData;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use crate::{Action, Player, State};
+use crate::ai::random_rollout::add_pass_action;
+
+/// Decides the actions of a player who isn't controlled by a searching agent, e.g. a pseudo-player
+/// standing in for the deck, or an empty seat.
+pub trait Policy<R, S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    fn decide(&mut self, rng: &mut R, state: &S) -> Option<A>;
+
+    /// Called when the game jumps to a state which doesn't follow from the previous decisions,
+    /// e.g. when a move is undone, so anything cached about the game so far can be discarded.
+    /// `history` is every action played to reach `state`, along with who played it.
+    fn restore(&mut self, _state: &S, _history: &[(P, A)]) {}
+}
+
+/// Plays a uniformly random legal action, without searching.
+///
+/// For a pseudo-player whose actions are the random events of the game (dealing a card, rolling a
+/// die) this is the game's own randomness, and for a dummy seat which only ever has the state's
+/// `pass_action` it simply passes.
+pub struct PassThroughAgent<P: Player> {
+    _phantom_p: PhantomData<P>,
+}
+
+impl<P: Player> PassThroughAgent<P> {
+    pub fn new() -> Self {
+        Self {
+            _phantom_p: Default::default(),
+        }
+    }
+}
+
+impl<P: Player> Default for PassThroughAgent<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R, S, A, P> Policy<R, S, A, P> for PassThroughAgent<P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    fn decide(&mut self, rng: &mut R, state: &S) -> Option<A> {
+        let mut actions = state.actions();
+        add_pass_action(state, &mut actions);
+        actions.choose(rng).cloned()
+    }
+}
+
+/// Plays a predefined list of actions, e.g. to reproduce a reported game move for move.
+///
+/// Once the script runs out, or its next action isn't legal (the game has diverged from the
+/// script), every remaining decision is made by the fallback policy instead.
+pub struct ScriptedAgent<R, S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    script: Vec<A>,
+    next_idx: usize,
+    diverged: bool,
+    /// the player the agent decides for, known once it has decided
+    player: Option<P>,
+    fallback: Box<dyn Policy<R, S, A, P>>,
+}
+
+impl<R, S, A, P> ScriptedAgent<R, S, A, P>
+    where
+        R: Rng + 'static,
+        S: State<A, P> + 'static,
+        A: Action + 'static,
+        P: Player,
+{
+    /// Plays `script`, falling back on a `PassThroughAgent`.
+    pub fn new(script: Vec<A>) -> Self {
+        Self {
+            script,
+            next_idx: 0,
+            diverged: false,
+            player: None,
+            fallback: Box::new(PassThroughAgent::new()),
+        }
+    }
+
+    pub fn with_fallback<T: Policy<R, S, A, P> + 'static>(mut self, fallback: T) -> Self {
+        self.fallback = Box::new(fallback);
+        self
+    }
+}
+
+impl<R, S, A, P> ScriptedAgent<R, S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    /// whether the game reached a point where the script's next action wasn't legal
+    pub fn has_diverged(&self) -> bool {
+        self.diverged
+    }
+
+    /// the number of scripted actions which have been played
+    pub fn num_played(&self) -> usize {
+        self.next_idx
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.next_idx >= self.script.len()
+    }
+}
+
+impl<R, S, A, P> Policy<R, S, A, P> for ScriptedAgent<R, S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    fn decide(&mut self, rng: &mut R, state: &S) -> Option<A> {
+        self.player = Some(state.current_player());
+
+        if !self.diverged {
+            if let Some(action) = self.script.get(self.next_idx) {
+                let mut actions = state.actions();
+                add_pass_action(state, &mut actions);
+                if actions.contains(action) {
+                    self.next_idx += 1;
+                    return Some(action.clone());
+                }
+
+                log::warn!("the game diverged from the script after {} actions, using the fallback policy", self.next_idx);
+                self.diverged = true;
+            }
+        }
+
+        self.fallback.decide(rng, state)
+    }
+
+    /// Rewinds the script to the actions the agent's player played in `history`, e.g. so undoing a
+    /// scripted move plays it again.
+    fn restore(&mut self, state: &S, history: &[(P, A)]) {
+        let played: Vec<&A> = match &self.player {
+            Some(player) => history.iter().filter(|(actor, _)| actor == player).map(|(_, action)| action).collect(),
+            None => Vec::new(),
+        };
+
+        self.next_idx = played.iter().zip(&self.script).take_while(|(played, scripted)| **played == *scripted).count();
+        // an action off the script was the fallback's, unless the script had run out
+        self.diverged = self.next_idx < played.len() && self.next_idx < self.script.len();
+        self.fallback.restore(state, history);
+    }
+}
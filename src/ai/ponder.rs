@@ -0,0 +1,81 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::thread::JoinHandle;
+use rand::Rng;
+use crate::{Action, GameTree, Player, State};
+
+/// Keeps a `GameTree` searching on a background thread while the opponent is thinking.
+///
+/// After moving, advance the tree past your own action and hand it to `Ponderer::start`. Once
+/// the opponent has moved, `resolve` stops the search and either re-roots the pondered tree on the
+/// opponent's action (a ponder hit) or starts a fresh tree (a miss).
+pub struct Ponderer<S, A, P, R> where S: State<A, P>, A: Action, P: Player {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<(GameTree<S, A, P>, R)>,
+}
+
+/// What's left once pondering has been resolved against the opponent's actual move.
+pub struct PonderResult<S, A, P, R> where S: State<A, P>, A: Action, P: Player {
+    pub tree: GameTree<S, A, P>,
+    pub rng: R,
+    /// whether the opponent's move had been searched, and its subtree reused
+    pub hit: bool,
+}
+
+impl<S, A, P, R> Ponderer<S, A, P, R>
+    where
+        S: State<A, P> + Send + 'static,
+        A: Action + Send + 'static,
+        P: Player + Send + Sync,
+        R: Rng + Send + 'static,
+{
+    /// Starts searching `tree`, whose root should be the state the opponent is deciding in.
+    pub fn start(mut tree: GameTree<S, A, P>, mut rng: R) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+
+        let handle = thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                if tree.search(&mut rng).is_err() {
+                    break;
+                }
+            }
+
+            (tree, rng)
+        });
+
+        Self {
+            stop,
+            handle,
+        }
+    }
+
+    /// Stops pondering and returns the tree as it was searched.
+    pub fn stop(self) -> (GameTree<S, A, P>, R) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.join().expect("the pondering thread doesn't panic")
+    }
+
+    /// Stops pondering and returns a tree rooted at `state`, the state reached by the opponent
+    /// playing `opponent_action`. On a hit the tree keeps the state it sampled while expanding,
+    /// which for games with random transitions may not be the state which actually occurred.
+    pub fn resolve(self, opponent_action: &A, state: S) -> PonderResult<S, A, P, R> {
+        let (mut tree, rng) = self.stop();
+
+        if tree.advance_root(opponent_action) {
+            PonderResult {
+                tree,
+                rng,
+                hit: true,
+            }
+        } else {
+            tree.reset(state);
+            PonderResult {
+                tree,
+                rng,
+                hit: false,
+            }
+        }
+    }
+}
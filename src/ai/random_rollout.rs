@@ -1,26 +1,225 @@
-use rand::Rng;
+use std::fmt::Debug;
+use rand::{Rng, RngCore};
 use crate::{Action, Outcome, Player, State};
+use crate::ai::batch::run_batch;
+use crate::ai::game_tree::context::SearchContext;
+use crate::ai::game_tree::error::SearchError;
+use crate::ai::game_tree::score::ScoreValue;
+use crate::ai::rng::split_rng;
+use crate::ai::rollout::{pseudo_outcome, weighted_choice};
+use crate::ai::strictness::{strictness, Strictness};
 
+/// Plays uniformly random actions until the game ends. A state failing to apply an action ends the
+/// rollout with its error, which the search reports as a `SearchError::State`.
 pub fn random_rollout<
     R: Rng + Sized,
     S: State<A, P> + Clone,
     A: Action,
     P: Player,
->(game: &S, rng: &mut R) -> Outcome<P> {
+>(game: &S, rng: &mut R) -> Result<Outcome<P>, S::Error> {
+    random_rollout_with_max_length(game, rng, usize::MAX)
+}
+
+/// Like `random_rollout`, but gives up on a playout which hasn't ended after `max_length` actions,
+/// e.g. under rules which can loop forever. The position it stopped in is valued by
+/// `State::heuristic_value`, or scored as an `Escape` which credits no player when there's none.
+pub fn random_rollout_with_max_length<
+    R: Rng + Sized,
+    S: State<A, P> + Clone,
+    A: Action,
+    P: Player,
+>(game: &S, rng: &mut R, max_length: usize) -> Result<Outcome<P>, S::Error> {
+    random_rollout_with_context(game, rng, max_length, &mut SearchContext::new())
+}
+
+/// Like `random_rollout_with_max_length`, but collects each state's actions into the buffer held
+/// by `context` rather than a fresh `Vec`, so many playouts can share it.
+pub fn random_rollout_with_context<
+    R: Rng + Sized,
+    S: State<A, P> + Clone,
+    A: Action,
+    P: Player,
+>(game: &S, rng: &mut R, max_length: usize, context: &mut SearchContext<A>) -> Result<Outcome<P>, S::Error> {
+    let mut game = game.clone();
+    let actions = &mut context.actions;
+
+    for _ in 0..max_length {
+        if let Some(outcome) = rollout_outcome(&game) {
+            return Ok(outcome);
+        }
+
+        if resolve_chance(&mut game, rng) {
+            continue;
+        }
+
+        actions.clear();
+        game.actions_into(actions);
+        add_pass_action(&game, actions);
+        let random_action = if game.current_player().is_chance() && !actions.is_empty() {
+            Some(&actions[nature_choice(&game, actions, rng)])
+        } else {
+            rand::seq::SliceRandom::choose(&actions[..], rng)
+        };
+
+        if let Some(action) = random_action {
+            game.apply_action_mut(rng, action)?;
+        } else {
+            return Ok(Outcome::Escape("No actions available.".to_string()));
+        }
+    }
+
+    Ok(rollout_outcome(&game).unwrap_or_else(|| cut_off_outcome(&game, rng, max_length)))
+}
+
+/// Plays each state out with `random_rollout` on a pool of at most `parallelism` worker threads,
+/// returning the outcomes in the same order as the states, e.g. for leaf-parallel searches or Monte
+/// Carlo evaluation. Each playout gets its own stream split from `rng`, so the outcomes don't
+/// depend on how the playouts are scheduled.
+pub fn rollout_batch<
+    R: Rng + Clone,
+    S: State<A, P> + Sync,
+    A: Action,
+    P: Player + Send,
+>(states: &[S], rng: &R, parallelism: usize) -> Vec<Result<Outcome<P>, S::Error>>
+    where
+        S::Error: Send,
+{
+    let items = states
+        .iter()
+        .enumerate()
+        .map(|(idx, state)| (split_rng(rng, idx as u32), state))
+        .collect();
+
+    run_batch(items, parallelism, |(mut rng, state)| random_rollout(state, &mut rng))
+}
+
+/// A single action played during a traced rollout.
+#[derive(Debug, Clone)]
+pub struct RolloutStep<T, A, P> where A: Action, P: Player {
+    /// the summary of the state the action was played from
+    pub summary: T,
+    pub action: A,
+    pub player: P,
+}
+
+/// Everything played during a traced rollout, in order, and how it ended.
+pub struct RolloutTrace<T, A, P> where A: Action, P: Player {
+    pub steps: Vec<RolloutStep<T, A, P>>,
+    pub outcome: Outcome<P>,
+}
+
+/// Like `random_rollout`, but records each action played along with who played it and
+/// `summarize` applied to the state it was played from, e.g. to debug the quality of rollouts or
+/// build statistics about them.
+pub fn random_rollout_traced<
+    R: Rng + Sized,
+    S: State<A, P> + Clone,
+    A: Action,
+    P: Player,
+    T,
+    F: Fn(&S) -> T,
+>(game: &S, rng: &mut R, summarize: F) -> Result<RolloutTrace<T, A, P>, S::Error> {
     let mut game = game.clone();
+    let mut steps = Vec::new();
 
     loop {
-        if let Some(outcome) = game.outcome() {
-            return outcome;
+        if let Some(outcome) = rollout_outcome(&game) {
+            return Ok(RolloutTrace { steps, outcome });
+        }
+
+        // chance events aren't anybody's action, so they aren't recorded as steps
+        if resolve_chance(&mut game, rng) {
+            continue;
         }
 
-        let actions = &game.actions()[..];
-        let random_action = rand::seq::SliceRandom::choose(actions, rng);
+        let mut actions = game.actions();
+        add_pass_action(&game, &mut actions);
+        let random_action = if game.current_player().is_chance() && !actions.is_empty() {
+            Some(&actions[nature_choice(&game, &actions, rng)])
+        } else {
+            rand::seq::SliceRandom::choose(&actions[..], rng)
+        };
 
         if let Some(action) = random_action {
-            game = game.apply_action(rng, action).unwrap();
+            let step = RolloutStep {
+                summary: summarize(&game),
+                action: action.clone(),
+                player: game.current_player(),
+            };
+            game.apply_action_mut(rng, action)?;
+            steps.push(step);
         } else {
-            return Outcome::Escape("No actions available.".to_string());
+            return Ok(RolloutTrace {
+                steps,
+                outcome: Outcome::Escape("No actions available.".to_string()),
+            });
+        }
+    }
+}
+
+/// the outcome a rollout stops at, which may be decided before the game ends
+pub(crate) fn rollout_outcome<S: State<A, P>, A: Action, P: Player>(state: &S) -> Option<Outcome<P>> {
+    state.outcome().or_else(|| state.quick_verdict())
+}
+
+/// values a rollout cut off at its maximum length by the state's heuristic, or as an escape for
+/// states without one
+pub(crate) fn cut_off_outcome<S: State<A, P>, A: Action, P: Player>(state: &S, rng: &mut dyn RngCore, max_length: usize) -> Outcome<P> {
+    let values = state.heuristic_value();
+    if values.is_empty() {
+        return Outcome::Escape(format!("The rollout didn't end within {max_length} actions."));
+    }
+
+    pseudo_outcome(&values, rng)
+}
+
+/// Replaces a chance node's state with one of its outcomes, sampled by probability, returning
+/// whether it was a chance node.
+pub(crate) fn resolve_chance<S: State<A, P>, A: Action, P: Player>(state: &mut S, rng: &mut dyn RngCore) -> bool {
+    let mut outcomes = state.chance_outcomes();
+    if outcomes.is_empty() {
+        return false;
+    }
+
+    let probabilities: Vec<ScoreValue> = outcomes.iter().map(|(_, probability)| *probability).collect();
+    *state = outcomes.swap_remove(weighted_choice(&probabilities, rng)).0;
+    true
+}
+
+/// Applies `action`, then resolves any chance events it leads to, as the game runners, `Replayer`
+/// and tracked beliefs move from one decision to the next.
+pub(crate) fn transition<R: Rng, S: State<A, P>, A: Action, P: Player>(state: &S, rng: &mut R, action: &A) -> Result<S, S::Error> {
+    let mut state = state.apply_action(rng, action)?;
+    while resolve_chance(&mut state, rng) {}
+    Ok(state)
+}
+
+/// the index of the action nature plays on a chance player's turn, sampled by the state's action
+/// priors, or uniformly when it gives none for these actions
+pub(crate) fn nature_choice<S: State<A, P>, A: Action, P: Player>(state: &S, actions: &[A], rng: &mut dyn RngCore) -> usize {
+    let priors = state.action_priors(actions);
+    if priors.len() == actions.len() {
+        weighted_choice(&priors, rng)
+    } else {
+        rng.gen_range(0..actions.len())
+    }
+}
+
+/// falls back to the state's pass action when it has no others
+pub(crate) fn add_pass_action<S: State<A, P>, A: Action, P: Player>(state: &S, actions: &mut Vec<A>) {
+    if actions.is_empty() {
+        actions.extend(state.pass_action());
+    }
+}
+
+/// how a search treats a non-terminal state without any actions, an error in strict mode and an
+/// escape otherwise
+pub(crate) fn no_actions_outcome<P: Player, E: Debug, A>() -> Result<Outcome<P>, SearchError<E, A>> {
+    match strictness() {
+        Strictness::Strict => Err(SearchError::NoActions),
+        Strictness::Lenient => {
+            log::warn!("a non-terminal state has no actions, treating it as an escape");
+            Ok(Outcome::Escape("No actions available.".to_string()))
         }
     }
 }
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::f64::consts::{LN_10, PI};
+use std::fmt::{self, Display, Formatter};
+use crate::ai::tournament::TournamentResults;
+
+/// The rating every entrant starts from.
+const INITIAL_RATING: f64 = 1500.0;
+
+/// The deviation of an entrant which hasn't played yet, which is also the most uncertain a rating
+/// can become.
+const MAX_DEVIATION: f64 = 350.0;
+
+const Q: f64 = LN_10 / 400.0;
+
+/// A Glicko rating, an Elo-like rating along with how uncertain it is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rating {
+    pub rating: f64,
+    /// the rating deviation, which shrinks the more games an entrant plays
+    pub deviation: f64,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            rating: INITIAL_RATING,
+            deviation: MAX_DEVIATION,
+        }
+    }
+}
+
+impl Rating {
+    /// The range the true rating lies within with 95% confidence, e.g. to tell whether two
+    /// configurations are really different in strength.
+    pub fn interval(&self) -> (f64, f64) {
+        (self.rating - 1.96 * self.deviation, self.rating + 1.96 * self.deviation)
+    }
+
+    /// The score expected against `opponent`, between 0 for a sure loss and 1 for a sure win.
+    pub fn expected_score(&self, opponent: &Rating) -> f64 {
+        let deviation = (self.deviation.powi(2) + opponent.deviation.powi(2)).sqrt();
+        expected_score(self.rating, opponent.rating, deviation)
+    }
+}
+
+/// how much a game against an opponent with the given deviation counts, less the less certain
+/// their rating is
+fn attenuation(deviation: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * Q.powi(2) * deviation.powi(2) / PI.powi(2)).sqrt()
+}
+
+fn expected_score(rating: f64, opponent_rating: f64, opponent_deviation: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-attenuation(opponent_deviation) * (rating - opponent_rating) / 400.0))
+}
+
+/// The ratings of tournament entrants, updated by the Glicko system, so changes to an agent's
+/// configuration, e.g. its exploration constant or number of simulations, can be compared by how
+/// far apart their ratings are rather than by eye.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ratings {
+    ratings: HashMap<String, Rating>,
+    deviation_growth: f64,
+}
+
+impl Ratings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How much each rating's deviation grows between tournaments, as the entrants may have been
+    /// changed since their last games. Defaults to 0, for entrants which stay the same.
+    pub fn with_deviation_growth(mut self, deviation_growth: f64) -> Self {
+        self.deviation_growth = deviation_growth;
+        self
+    }
+
+    /// The entrant's rating, or None if it hasn't been rated yet.
+    pub fn get(&self, name: &str) -> Option<&Rating> {
+        self.ratings.get(name)
+    }
+
+    /// Rates the games of a tournament as one rating period, every game being rated against the
+    /// ratings from before the tournament. Unfinished games aren't rated.
+    pub fn update(&mut self, results: &TournamentResults) {
+        for rating in self.ratings.values_mut() {
+            rating.deviation = (rating.deviation.powi(2) + self.deviation_growth.powi(2)).sqrt().min(MAX_DEVIATION);
+        }
+
+        // each entrant's games, as their opponent and score
+        let mut games: HashMap<&str, Vec<(&str, f64)>> = HashMap::new();
+        for pairing in &results.pairings {
+            let scores = [(pairing.wins, 1.0), (pairing.draws, 0.5), (pairing.losses, 0.0)];
+            for (num_games, score) in scores {
+                for _ in 0..num_games {
+                    games.entry(&pairing.first).or_default().push((&pairing.second, score));
+                    games.entry(&pairing.second).or_default().push((&pairing.first, 1.0 - score));
+                }
+            }
+        }
+
+        let period_start = self.ratings.clone();
+        let rating_of = |name: &str| period_start.get(name).copied().unwrap_or_default();
+        for (name, games) in games {
+            let Rating { rating, deviation } = rating_of(name);
+
+            let mut information = 0.0;
+            let mut surprise = 0.0;
+            for (opponent, score) in games {
+                let opponent = rating_of(opponent);
+                let attenuation = attenuation(opponent.deviation);
+                let expected_score = expected_score(rating, opponent.rating, opponent.deviation);
+                information += attenuation.powi(2) * expected_score * (1.0 - expected_score);
+                surprise += attenuation * (score - expected_score);
+            }
+
+            let precision = 1.0 / deviation.powi(2) + Q.powi(2) * information;
+            self.ratings.insert(name.to_string(), Rating {
+                rating: rating + Q / precision * surprise,
+                deviation: (1.0 / precision).sqrt(),
+            });
+        }
+    }
+
+    /// Every rated entrant, highest rated first.
+    pub fn standings(&self) -> Vec<(&str, &Rating)> {
+        let mut standings: Vec<_> = self.ratings.iter().map(|(name, rating)| (name.as_str(), rating)).collect();
+        standings.sort_by(|(_, a), (_, b)| b.rating.total_cmp(&a.rating));
+        standings
+    }
+}
+
+/// one line per entrant, highest rated first, e.g. "mcts 1000: 1687 ± 62"
+impl Display for Ratings {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (name, rating) in self.standings() {
+            writeln!(f, "{}: {:.0} ± {:.0}", name, rating.rating, rating.deviation)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::tournament::PairingResult;
+
+    fn pairing(first: &str, second: &str, wins: u32, losses: u32) -> PairingResult {
+        PairingResult {
+            first: first.to_string(),
+            second: second.to_string(),
+            wins,
+            losses,
+            ..Default::default()
+        }
+    }
+
+    /// the worked example from Glickman's description of the Glicko system
+    #[test]
+    fn rates_glickmans_example() {
+        let mut ratings = Ratings::new();
+        for (name, rating, deviation) in [("player", 1500.0, 200.0), ("a", 1400.0, 30.0), ("b", 1550.0, 100.0), ("c", 1700.0, 300.0)] {
+            ratings.ratings.insert(name.to_string(), Rating { rating, deviation });
+        }
+
+        ratings.update(&TournamentResults {
+            pairings: vec![pairing("player", "a", 1, 0), pairing("player", "b", 0, 1), pairing("player", "c", 0, 1)],
+        });
+
+        let rating = ratings.get("player").unwrap();
+        assert!((rating.rating - 1464.1).abs() < 0.5, "{}", rating.rating);
+        assert!((rating.deviation - 151.5).abs() < 0.5, "{}", rating.deviation);
+    }
+
+    #[test]
+    fn a_draw_between_equals_changes_no_rating() {
+        let mut ratings = Ratings::new();
+        ratings.update(&TournamentResults {
+            pairings: vec![PairingResult { draws: 1, ..pairing("a", "b", 0, 0) }],
+        });
+
+        assert_eq!(ratings.get("a").unwrap().rating, INITIAL_RATING);
+        assert!(ratings.get("a").unwrap().deviation < MAX_DEVIATION);
+    }
+}
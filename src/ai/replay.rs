@@ -0,0 +1,103 @@
+use thiserror::Error;
+use crate::{Action, Player, State};
+use crate::ai::random_rollout::transition;
+use crate::ai::rng::{seeded_rng, StreamRng};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    #[error("action {0} of the replay wasn't played by the player to move, or doesn't apply to the state")]
+    Diverged(usize),
+}
+
+/// A record of a game, enough to reproduce it from its initial state, e.g. to debug a game an
+/// agent played badly in. Games recorded with `with_recording` draw their random transitions from
+/// an rng of their own seeded with `seed`, which a `Replayer` draws them from again.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Replay<A, P> {
+    /// the seed of the game's random transitions, without which only games without random
+    /// transitions can be reproduced
+    pub seed: Option<u64>,
+    /// identifies the initial state to whoever replays the game, e.g. a scenario's name
+    pub state_id: Option<String>,
+    /// every action played, oldest first, along with the player who played it
+    pub actions: Vec<(P, A)>,
+}
+
+/// the seed and rng of a recorded game's random transitions, and the id of its initial state
+pub(crate) struct Recording {
+    pub(crate) seed: u64,
+    pub(crate) state_id: String,
+    pub(crate) rng: StreamRng,
+}
+
+impl Recording {
+    pub(crate) fn new(seed: u64, state_id: String) -> Self {
+        Self {
+            seed,
+            state_id,
+            rng: seeded_rng(seed),
+        }
+    }
+}
+
+/// Reproduces a recorded game one action at a time.
+pub struct Replayer<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    state: S,
+    rng: StreamRng,
+    actions: Vec<(P, A)>,
+    next_idx: usize,
+}
+
+impl<S, A, P> Replayer<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    /// Replays `replay` from `state`, which should be the initial state its `state_id` identifies.
+    pub fn new(replay: Replay<A, P>, state: S) -> Self {
+        Self {
+            state,
+            rng: seeded_rng(replay.seed.unwrap_or_default()),
+            actions: replay.actions,
+            next_idx: 0,
+        }
+    }
+
+    /// the state the game had reached after the actions replayed so far
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Plays the next action of the replay, returning it along with the player who played it, or
+    /// None once every action has been replayed. A game which doesn't go as recorded, e.g. as its
+    /// rules changed since, is an error.
+    pub fn step(&mut self) -> Result<Option<&(P, A)>, ReplayError> {
+        let Some((player, action)) = self.actions.get(self.next_idx) else {
+            return Ok(None);
+        };
+
+        if self.state.current_player() != *player {
+            return Err(ReplayError::Diverged(self.next_idx));
+        }
+
+        let Ok(state) = transition(&self.state, &mut self.rng, action) else {
+            return Err(ReplayError::Diverged(self.next_idx));
+        };
+        self.state = state;
+        self.next_idx += 1;
+
+        Ok(self.actions.get(self.next_idx - 1))
+    }
+
+    /// Replays every remaining action, returning the final state.
+    pub fn run(&mut self) -> Result<&S, ReplayError> {
+        while self.step()?.is_some() {}
+        Ok(&self.state)
+    }
+
+    /// the number of actions which have been replayed
+    pub fn num_played(&self) -> usize {
+        self.next_idx
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_idx >= self.actions.len()
+    }
+}
@@ -0,0 +1,31 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// The rng split off streams use, whatever type of rng they were split from.
+pub(crate) type StreamRng = StdRng;
+
+/// Derives the seed for stream `stream_idx` from `rng`, without advancing it.
+///
+/// Each stream is seeded from a hash of its index mixed with a draw from the rng, so streams are
+/// independent of each other rather than offset copies of the same sequence.
+pub(crate) fn stream_seed<R: Rng + Clone>(rng: &R, stream_idx: u32) -> u64 {
+    let base_seed = rng.clone().next_u64();
+    splitmix64(base_seed ^ splitmix64(stream_idx as u64))
+}
+
+/// An independent rng for stream `stream_idx`, e.g. one determinization or one worker thread.
+pub(crate) fn split_rng<R: Rng + Clone>(rng: &R, stream_idx: u32) -> StreamRng {
+    seeded_rng(stream_seed(rng, stream_idx))
+}
+
+/// The rng of the stream with the given seed, as derived by `stream_seed`.
+pub(crate) fn seeded_rng(seed: u64) -> StreamRng {
+    StreamRng::seed_from_u64(seed)
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,675 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use rand::{Rng, RngCore};
+use crate::{Action, History, Outcome, Player, State};
+use crate::ai::cache::SharedCache;
+use crate::ai::game_tree::score::ScoreValue;
+use crate::ai::game_tree::transposition::add_outcome;
+use crate::ai::game_tree::context::SearchContext;
+use crate::ai::game_tree::error::SearchError;
+use crate::ai::random_rollout::{add_pass_action, cut_off_outcome, nature_choice, no_actions_outcome, random_rollout_with_context, random_rollout_with_max_length, resolve_chance, rollout_outcome};
+
+/// Plays a state out to an outcome, valuing a newly expanded node. Games where uniformly random
+/// playouts say little about a position can supply heavier playouts through
+/// `GameTree::with_rollout_policy`. A state failing to apply an action ends the rollout with a
+/// `SearchError::State`, and one without any actions is treated as the search's strictness says.
+pub trait RolloutPolicy<S, A, P>: Send + Sync where S: State<A, P>, A: Action, P: Player {
+    fn rollout(&self, state: &S, rng: &mut dyn RngCore) -> Result<Outcome<P>, SearchError<S::Error, A>>;
+
+    /// Like `rollout`, but may reuse the buffers of the tree's `SearchContext` rather than
+    /// allocating its own. Defaults to `rollout`.
+    fn rollout_with_context(&self, state: &S, rng: &mut dyn RngCore, _context: &mut SearchContext<A>) -> Result<Outcome<P>, SearchError<S::Error, A>> {
+        self.rollout(state, rng)
+    }
+}
+
+/// Plays uniformly random actions until the game ends, the default rollout policy.
+#[derive(Debug, Clone, Copy)]
+pub struct RandomRollout {
+    max_length: usize,
+}
+
+impl RandomRollout {
+    pub fn new() -> Self {
+        Self {
+            max_length: usize::MAX,
+        }
+    }
+
+    /// Cuts off playouts which haven't ended after `max_length` actions, so rules which can loop
+    /// forever don't hang the search. They're valued by `State::heuristic_value`, or scored as an
+    /// `Escape` for states without one.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+}
+
+impl Default for RandomRollout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, A, P> RolloutPolicy<S, A, P> for RandomRollout where S: State<A, P>, A: Action, P: Player {
+    fn rollout(&self, state: &S, rng: &mut dyn RngCore) -> Result<Outcome<P>, SearchError<S::Error, A>> {
+        random_rollout_with_max_length(state, &mut &mut *rng, self.max_length).map_err(SearchError::state)
+    }
+
+    fn rollout_with_context(&self, state: &S, rng: &mut dyn RngCore, context: &mut SearchContext<A>) -> Result<Outcome<P>, SearchError<S::Error, A>> {
+        random_rollout_with_context(state, &mut &mut *rng, self.max_length, context).map_err(SearchError::state)
+    }
+}
+
+/// Plays actions sampled in proportion to a heuristic weight, e.g. so a wargame's playouts favour
+/// sensible moves over the many nonsense ones. Actions weighted zero or less are never played
+/// unless every action is, in which case one is picked uniformly.
+pub struct WeightedRollout<F> {
+    weight: F,
+    max_length: usize,
+}
+
+impl<F> WeightedRollout<F> {
+    /// Weights each action available in a state by `weight(state, action)`.
+    pub fn new(weight: F) -> Self {
+        Self {
+            weight,
+            max_length: usize::MAX,
+        }
+    }
+
+    /// Cuts off playouts which haven't ended after `max_length` actions, as
+    /// `RandomRollout::with_max_length` does.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+}
+
+impl<S, A, P, F> RolloutPolicy<S, A, P> for WeightedRollout<F>
+    where
+        S: State<A, P>,
+        A: Action,
+        P: Player,
+        F: Fn(&S, &A) -> ScoreValue + Send + Sync,
+{
+    fn rollout(&self, state: &S, rng: &mut dyn RngCore) -> Result<Outcome<P>, SearchError<S::Error, A>> {
+        play_out(state, rng, self.max_length, |state, actions, rng| {
+            let weights: Vec<ScoreValue> = actions
+                .iter()
+                .map(|action| (self.weight)(state, action).max(0.0))
+                .collect();
+
+            weighted_choice(&weights, rng)
+        })
+    }
+}
+
+/// Plays actions sampled in proportion to `State::action_priors`, e.g. a trained policy network's,
+/// and uniformly random ones in states without priors.
+#[derive(Debug, Clone, Copy)]
+pub struct PriorRollout {
+    max_length: usize,
+}
+
+impl PriorRollout {
+    pub fn new() -> Self {
+        Self {
+            max_length: usize::MAX,
+        }
+    }
+
+    /// Cuts off playouts which haven't ended after `max_length` actions, as
+    /// `RandomRollout::with_max_length` does.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+}
+
+impl Default for PriorRollout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, A, P> RolloutPolicy<S, A, P> for PriorRollout where S: State<A, P>, A: Action, P: Player {
+    fn rollout(&self, state: &S, rng: &mut dyn RngCore) -> Result<Outcome<P>, SearchError<S::Error, A>> {
+        play_out(state, rng, self.max_length, |state, actions, rng| {
+            let priors = state.action_priors(actions);
+            if priors.len() != actions.len() {
+                return rng.gen_range(0..actions.len());
+            }
+
+            let weights: Vec<ScoreValue> = priors.iter().map(|prior| prior.max(0.0)).collect();
+            weighted_choice(&weights, rng)
+        })
+    }
+}
+
+/// Plays the action `evaluate(state, action)` values highest with probability `1 - epsilon`, and a
+/// uniformly random action otherwise, a cheap way to get much stronger playouts from a simple
+/// evaluator while keeping some variety.
+pub struct EpsilonGreedyRollout<F> {
+    epsilon: ScoreValue,
+    evaluate: F,
+    max_length: usize,
+}
+
+impl<F> EpsilonGreedyRollout<F> {
+    pub fn new(epsilon: ScoreValue, evaluate: F) -> Self {
+        Self {
+            epsilon: epsilon.clamp(0.0, 1.0),
+            evaluate,
+            max_length: usize::MAX,
+        }
+    }
+
+    /// Cuts off playouts which haven't ended after `max_length` actions, as
+    /// `RandomRollout::with_max_length` does.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+}
+
+impl<S, A, P, F> RolloutPolicy<S, A, P> for EpsilonGreedyRollout<F>
+    where
+        S: State<A, P>,
+        A: Action,
+        P: Player,
+        F: Fn(&S, &A) -> ScoreValue + Send + Sync,
+{
+    fn rollout(&self, state: &S, rng: &mut dyn RngCore) -> Result<Outcome<P>, SearchError<S::Error, A>> {
+        play_out(state, rng, self.max_length, |state, actions, rng| {
+            if rng.gen::<ScoreValue>() < self.epsilon {
+                return rng.gen_range(0..actions.len());
+            }
+
+            actions
+                .iter()
+                .map(|action| (self.evaluate)(state, action))
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map_or(0, |(idx, _)| idx)
+        })
+    }
+}
+
+/// Plays uniformly random actions, and nature's by their odds, for at most `max_plies` actions,
+/// then values the position reached with `evaluate`, for games without a natural terminal
+/// horizon. The evaluation lists each player's value of zero or more, in a consistent order so
+/// seeded searches are reproducible, and is turned into a pseudo-outcome each player wins with
+/// probability proportional to their value, so averaged over many rollouts the backed up scores
+/// follow the evaluation.
+pub struct DepthLimitedRollout<F> {
+    max_plies: usize,
+    evaluate: F,
+}
+
+impl<F> DepthLimitedRollout<F> {
+    pub fn new(max_plies: usize, evaluate: F) -> Self {
+        Self { max_plies, evaluate }
+    }
+}
+
+impl<S, A, P, F> RolloutPolicy<S, A, P> for DepthLimitedRollout<F>
+    where
+        S: State<A, P>,
+        A: Action,
+        P: Player,
+        F: Fn(&S) -> Vec<(P, ScoreValue)> + Send + Sync,
+{
+    fn rollout(&self, state: &S, rng: &mut dyn RngCore) -> Result<Outcome<P>, SearchError<S::Error, A>> {
+        let choose = |_: &S, actions: &[A], rng: &mut dyn RngCore| rng.gen_range(0..actions.len());
+        play_out_with(state, rng, self.max_plies, choose, |state, rng| pseudo_outcome(&(self.evaluate)(state), rng))
+    }
+}
+
+/// samples a winner in proportion to each player's value, every player evaluated draws when none
+/// has a positive value
+pub(crate) fn pseudo_outcome<P: Player>(values: &[(P, ScoreValue)], rng: &mut dyn RngCore) -> Outcome<P> {
+    if values.is_empty() {
+        return Outcome::Escape("The evaluation valued no players.".to_string());
+    }
+
+    let (players, weights): (Vec<P>, Vec<ScoreValue>) = values
+        .iter()
+        .map(|(player, value)| (player.clone(), value.max(0.0)))
+        .unzip();
+
+    if !weights.iter().any(|weight| *weight > 0.0) {
+        return Outcome::Draw(players);
+    }
+
+    Outcome::Winner(players[weighted_choice(&weights, rng)].clone())
+}
+
+/// How playouts containing a key, e.g. an action, turned out: the number of playouts and each
+/// player's total score over them.
+#[derive(Debug, Clone)]
+pub struct PlayoutEntry<P> where P: Player {
+    pub num_playouts: u32,
+    pub scores: HashMap<P, ScoreValue>,
+}
+
+/// Statistics gathered from playouts, shared between every rollout policy (and so every tree)
+/// holding a clone of it. The rollout policies which learn from their own playouts, like
+/// `MastRollout`, record into and sample from them.
+pub struct PlayoutStatistics<K, P> where P: Player {
+    entries: SharedCache<K, PlayoutEntry<P>>,
+}
+
+impl<K, P> PlayoutStatistics<K, P> where K: Hash + Eq, P: Player {
+    pub fn new() -> Self {
+        Self {
+            entries: SharedCache::new(),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<PlayoutEntry<P>> {
+        self.entries.get(key)
+    }
+
+    /// The average score `player` got in playouts containing `key`, if there were any.
+    pub fn value(&self, key: &K, player: P) -> Option<ScoreValue> {
+        let entry = self.get(key)?;
+        if entry.num_playouts == 0 {
+            return None;
+        }
+
+        Some(entry.scores.get(&player).copied().unwrap_or(0.0) / entry.num_playouts as ScoreValue)
+    }
+
+    /// Records a playout which ended in `outcome` against each of its keys.
+    pub fn record<I: IntoIterator<Item = K>>(&self, keys: I, outcome: &Outcome<P>) {
+        for key in keys {
+            let new_entry = || PlayoutEntry {
+                num_playouts: 0,
+                scores: HashMap::new(),
+            };
+
+            self.entries.update(key, new_entry, |entry| {
+                entry.num_playouts += 1;
+                add_outcome(&mut entry.scores, outcome);
+            });
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&self) {
+        self.entries.clear();
+    }
+}
+
+impl<K, P> Default for PlayoutStatistics<K, P> where K: Hash + Eq, P: Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, P> Clone for PlayoutStatistics<K, P> where P: Player {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+/// The Move-Average Sampling Technique: learns each action's average score, for the player who
+/// played it, from the playouts it appeared in, and picks playout actions by Gibbs sampling over
+/// those values. A lower temperature follows the values more greedily. Actions which haven't been
+/// played yet are valued optimistically, as if they always won.
+///
+/// Only the actions played during playouts count, not those the tree selected on the way down to
+/// the playout, so an action the tree keeps choosing is learnt from the playouts it turns up in
+/// deeper down.
+pub struct MastRollout<A, P> where P: Player {
+    temperature: ScoreValue,
+    statistics: PlayoutStatistics<A, P>,
+    max_length: usize,
+}
+
+impl<A, P> MastRollout<A, P> where A: Hash + Eq, P: Player {
+    pub fn new(temperature: ScoreValue) -> Self {
+        Self {
+            temperature: temperature.max(ScoreValue::EPSILON),
+            statistics: PlayoutStatistics::new(),
+            max_length: usize::MAX,
+        }
+    }
+
+    /// Cuts off playouts which haven't ended after `max_length` actions, as
+    /// `RandomRollout::with_max_length` does.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Learns into and samples from `statistics`, e.g. shared by every determinization's tree.
+    pub fn with_statistics(mut self, statistics: PlayoutStatistics<A, P>) -> Self {
+        self.statistics = statistics;
+        self
+    }
+
+    pub fn statistics(&self) -> &PlayoutStatistics<A, P> {
+        &self.statistics
+    }
+}
+
+impl<S, A, P> RolloutPolicy<S, A, P> for MastRollout<A, P>
+    where
+        S: State<A, P>,
+        A: Action + Eq + Hash + Send + Sync,
+        P: Player + Send + Sync,
+{
+    fn rollout(&self, state: &S, rng: &mut dyn RngCore) -> Result<Outcome<P>, SearchError<S::Error, A>> {
+        let mut played: Vec<A> = Vec::new();
+        let outcome = play_out(state, rng, self.max_length, |state, actions, rng| {
+            let mover = state.current_player();
+            let values: Vec<ScoreValue> = actions
+                .iter()
+                .map(|action| self.statistics.value(action, mover.clone()).unwrap_or(1.0))
+                .collect();
+
+            let action_idx = gibbs_choice(&values, self.temperature, rng);
+            played.push(actions[action_idx].clone());
+            action_idx
+        })?;
+
+        // an action repeated within a playout only counts once towards its average
+        self.statistics.record(played.into_iter().collect::<HashSet<A>>(), &outcome);
+        Ok(outcome)
+    }
+}
+
+/// The N-gram Selection Technique, which extends `MastRollout` from single actions to sequences of
+/// up to `max_length` consecutive actions. A candidate action is valued by averaging the values of
+/// the sequences it would end, those playing out a line which historically wins rather than a
+/// single good action. Sequences longer than a single action only count once they've appeared in
+/// `min_playouts` playouts, so rare ones don't add noise.
+pub struct NstRollout<A, P> where P: Player {
+    max_length: usize,
+    min_playouts: u32,
+    temperature: ScoreValue,
+    statistics: PlayoutStatistics<Vec<A>, P>,
+    max_playout_length: usize,
+}
+
+impl<A, P> NstRollout<A, P> where A: Hash + Eq, P: Player {
+    pub fn new(max_length: usize, temperature: ScoreValue) -> Self {
+        Self {
+            max_length: max_length.max(1),
+            min_playouts: 7,
+            temperature: temperature.max(ScoreValue::EPSILON),
+            statistics: PlayoutStatistics::new(),
+            max_playout_length: usize::MAX,
+        }
+    }
+
+    pub fn with_min_playouts(mut self, min_playouts: u32) -> Self {
+        self.min_playouts = min_playouts;
+        self
+    }
+
+    /// Cuts off playouts which haven't ended after `max_playout_length` actions, as
+    /// `RandomRollout::with_max_length` does. Unlike the `max_length` of the sequences, it's the
+    /// length of a whole playout.
+    pub fn with_max_playout_length(mut self, max_playout_length: usize) -> Self {
+        self.max_playout_length = max_playout_length;
+        self
+    }
+
+    /// Learns into and samples from `statistics`, e.g. shared by every determinization's tree.
+    pub fn with_statistics(mut self, statistics: PlayoutStatistics<Vec<A>, P>) -> Self {
+        self.statistics = statistics;
+        self
+    }
+
+    pub fn statistics(&self) -> &PlayoutStatistics<Vec<A>, P> {
+        &self.statistics
+    }
+}
+
+impl<A, P> NstRollout<A, P> where A: Action + Eq + Hash, P: Player {
+    /// the average value of the sequences `action` would end after the actions played so far
+    fn value(&self, played: &[A], action: &A, mover: P) -> ScoreValue {
+        let mut total = 0.0;
+        let mut num_values = 0;
+
+        let mut sequence = vec![action.clone()];
+        for length in 1..=self.max_length.min(played.len() + 1) {
+            if length > 1 {
+                sequence.insert(0, played[played.len() + 1 - length].clone());
+            }
+
+            let Some(entry) = self.statistics.get(&sequence) else {
+                // a longer sequence can't have been seen more often than the one it extends
+                break;
+            };
+
+            if length > 1 && entry.num_playouts < self.min_playouts {
+                break;
+            }
+
+            total += entry.scores.get(&mover).copied().unwrap_or(0.0) / entry.num_playouts.max(1) as ScoreValue;
+            num_values += 1;
+        }
+
+        if num_values == 0 {
+            // unplayed actions are valued optimistically, as in MAST
+            return 1.0;
+        }
+
+        total / num_values as ScoreValue
+    }
+}
+
+impl<S, A, P> RolloutPolicy<S, A, P> for NstRollout<A, P>
+    where
+        S: State<A, P>,
+        A: Action + Eq + Hash + Send + Sync,
+        P: Player + Send + Sync,
+{
+    fn rollout(&self, state: &S, rng: &mut dyn RngCore) -> Result<Outcome<P>, SearchError<S::Error, A>> {
+        let mut played: Vec<A> = Vec::new();
+        let outcome = play_out(state, rng, self.max_playout_length, |state, actions, rng| {
+            let mover = state.current_player();
+            let values: Vec<ScoreValue> = actions
+                .iter()
+                .map(|action| self.value(&played, action, mover.clone()))
+                .collect();
+
+            let action_idx = gibbs_choice(&values, self.temperature, rng);
+            played.push(actions[action_idx].clone());
+            action_idx
+        })?;
+
+        // a sequence repeated within a playout only counts once towards its average
+        let mut sequences: HashSet<Vec<A>> = HashSet::new();
+        for end in 1..=played.len() {
+            for length in 1..=self.max_length.min(end) {
+                sequences.insert(played[end - length..end].to_vec());
+            }
+        }
+
+        self.statistics.record(sequences, &outcome);
+        Ok(outcome)
+    }
+}
+
+/// Last-Good-Reply with forgetting: remembers, for each player and each action played just before
+/// their turn, the reply they made in the last playout they won, and plays it again whenever it's
+/// legal, otherwise a uniformly random action. A reply which then loses is forgotten. The state's
+/// `History` supplies the action played just before the playout, so its first reply can be looked
+/// up too.
+pub struct LastGoodReplyRollout<A, P> where P: Player {
+    replies: SharedCache<(P, A), A>,
+    max_length: usize,
+}
+
+impl<A, P> LastGoodReplyRollout<A, P> where A: Hash + Eq + Clone, P: Player {
+    pub fn new() -> Self {
+        Self {
+            replies: SharedCache::new(),
+            max_length: usize::MAX,
+        }
+    }
+
+    /// Cuts off playouts which haven't ended after `max_length` actions, as
+    /// `RandomRollout::with_max_length` does.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Learns into and replies from `replies`, e.g. shared by every determinization's tree.
+    pub fn with_replies(mut self, replies: SharedCache<(P, A), A>) -> Self {
+        self.replies = replies;
+        self
+    }
+
+    pub fn replies(&self) -> &SharedCache<(P, A), A> {
+        &self.replies
+    }
+}
+
+impl<A, P> Default for LastGoodReplyRollout<A, P> where A: Hash + Eq + Clone, P: Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, A, P> RolloutPolicy<S, A, P> for LastGoodReplyRollout<A, P>
+    where
+        S: History<A, P>,
+        A: Action + Eq + Hash + Send + Sync,
+        P: Player + Send + Sync,
+{
+    fn rollout(&self, state: &S, rng: &mut dyn RngCore) -> Result<Outcome<P>, SearchError<S::Error, A>> {
+        let mut previous = state.last_action().cloned();
+        let mut replies: Vec<(P, A, A)> = Vec::new();
+
+        let outcome = play_out(state, rng, self.max_length, |state, actions, rng| {
+            let mover = state.current_player();
+            let remembered = previous
+                .as_ref()
+                .and_then(|previous| self.replies.get(&(mover.clone(), previous.clone())))
+                .and_then(|reply| actions.iter().position(|action| *action == reply));
+
+            let action_idx = remembered.unwrap_or_else(|| rng.gen_range(0..actions.len()));
+            let action = actions[action_idx].clone();
+            if let Some(previous) = previous.replace(action.clone()) {
+                replies.push((mover, previous, action));
+            }
+
+            action_idx
+        })?;
+
+        // only a win says anything about the replies, either way
+        let (Outcome::Winner(winner) | Outcome::WinnerBy(winner, _)) = &outcome else {
+            return Ok(outcome);
+        };
+
+        let winners = winner.teammates();
+        for (mover, previous, reply) in replies {
+            if winners.contains(&mover) {
+                self.replies.insert((mover, previous), reply);
+            } else {
+                self.replies.remove_if(&(mover, previous), |remembered| *remembered == reply);
+            }
+        }
+
+        Ok(outcome)
+    }
+}
+
+/// samples an index with probability proportional to `exp(value / temperature)`
+fn gibbs_choice(values: &[ScoreValue], temperature: ScoreValue, rng: &mut dyn RngCore) -> usize {
+    // shifted by the maximum so the exponentials can't overflow
+    let max_value = values.iter().copied().fold(ScoreValue::MIN, ScoreValue::max);
+    let weights: Vec<ScoreValue> = values
+        .iter()
+        .map(|value| ((value - max_value) / temperature).exp())
+        .collect();
+
+    weighted_choice(&weights, rng)
+}
+
+/// Plays the state out, applying the action at the index `choose` picks out of the available
+/// actions until the game ends, or valuing it with `cut_off_outcome` after `max_length` actions.
+fn play_out<S, A, P, F>(state: &S, rng: &mut dyn RngCore, max_length: usize, choose: F) -> Result<Outcome<P>, SearchError<S::Error, A>>
+    where
+        S: State<A, P>,
+        A: Action,
+        P: Player,
+        F: FnMut(&S, &[A], &mut dyn RngCore) -> usize,
+{
+    play_out_with(state, rng, max_length, choose, |state, rng| cut_off_outcome(state, rng, max_length))
+}
+
+/// Like `play_out`, valuing a playout cut off after `max_length` actions with `cut_off` instead.
+fn play_out_with<S, A, P, F, C>(state: &S, rng: &mut dyn RngCore, max_length: usize, mut choose: F, cut_off: C) -> Result<Outcome<P>, SearchError<S::Error, A>>
+    where
+        S: State<A, P>,
+        A: Action,
+        P: Player,
+        F: FnMut(&S, &[A], &mut dyn RngCore) -> usize,
+        C: FnOnce(&S, &mut dyn RngCore) -> Outcome<P>,
+{
+    let mut state = state.clone();
+
+    for _ in 0..max_length {
+        if let Some(outcome) = rollout_outcome(&state) {
+            return Ok(outcome);
+        }
+
+        if resolve_chance(&mut state, rng) {
+            continue;
+        }
+
+        let mut actions = state.actions();
+        add_pass_action(&state, &mut actions);
+        if actions.is_empty() {
+            return no_actions_outcome();
+        }
+
+        // nature plays by the odds whatever the policy
+        let action_idx = if state.current_player().is_chance() {
+            nature_choice(&state, &actions, rng)
+        } else {
+            choose(&state, &actions, rng)
+        };
+        state.apply_action_mut(&mut &mut *rng, &actions[action_idx]).map_err(SearchError::state)?;
+    }
+
+    Ok(rollout_outcome(&state).unwrap_or_else(|| cut_off(&state, rng)))
+}
+
+/// picks an index with probability proportional to its weight, or uniformly when no weight is
+/// positive
+pub(crate) fn weighted_choice(weights: &[ScoreValue], rng: &mut dyn RngCore) -> usize {
+    let total: ScoreValue = weights.iter().sum();
+    if !(total > 0.0 && total.is_finite()) {
+        return rng.gen_range(0..weights.len());
+    }
+
+    let mut remaining = rng.gen::<ScoreValue>() * total;
+    for (idx, weight) in weights.iter().enumerate() {
+        if remaining < *weight {
+            return idx;
+        }
+
+        remaining -= weight;
+    }
+
+    // rounding can leave a sliver of the total unaccounted for
+    weights.iter().rposition(|weight| *weight > 0.0).unwrap_or(0)
+}
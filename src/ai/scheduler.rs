@@ -0,0 +1,136 @@
+use std::cmp::Reverse;
+use std::time::Instant;
+use rand::Rng;
+use crate::{Action, GameTree, Player, SearchError, State};
+
+pub type DecisionId = u64;
+
+#[derive(Debug, Clone)]
+pub struct DecisionRequest {
+    /// the decision is answered with whatever has been searched by this point
+    pub deadline: Instant,
+    /// higher priority decisions are always searched before lower priority ones
+    pub priority: u32,
+    /// the decision is answered early once this many simulations have been run
+    pub max_simulations: Option<u32>,
+}
+
+pub struct CompletedDecision<A> where A: Action {
+    pub id: DecisionId,
+    pub action: Option<A>,
+    pub num_simulations: u32,
+    /// set if the search failed, the action is then taken from whatever was searched before
+    pub error: Option<SearchError>,
+}
+
+struct PendingDecision<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    id: DecisionId,
+    request: DecisionRequest,
+    tree: GameTree<S, A, P>,
+    num_simulations: u32,
+    error: Option<SearchError>,
+}
+
+impl<S, A, P> PendingDecision<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    fn is_complete(&self, now: Instant) -> bool {
+        if self.error.is_some() || now >= self.request.deadline {
+            return true;
+        }
+
+        matches!(self.request.max_simulations, Some(max) if self.num_simulations >= max)
+    }
+}
+
+/// Shares one search budget between many concurrently pending decisions, e.g. a server playing
+/// hundreds of games at once.
+///
+/// The budget is handed out in quanta of simulations. Each quantum goes to the pending decision
+/// with the highest priority, and among those to the one with the earliest deadline, so
+/// fast time control games preempt casual ones.
+pub struct Scheduler<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    pending: Vec<PendingDecision<S, A, P>>,
+    next_id: DecisionId,
+    quantum: u32,
+}
+
+impl<S, A, P> Scheduler<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    pub fn new(quantum: u32) -> Self {
+        Self {
+            pending: Vec::new(),
+            next_id: 0,
+            quantum: quantum.max(1),
+        }
+    }
+
+    pub fn submit(&mut self, state: S, request: DecisionRequest) -> DecisionId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.pending.push(PendingDecision {
+            id,
+            request,
+            tree: GameTree::new(state),
+            num_simulations: 0,
+            error: None,
+        });
+
+        id
+    }
+
+    pub fn num_pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Searches the most urgent pending decision for one quantum, then returns every decision
+    /// which has reached its deadline or simulation cap.
+    pub fn run_quantum<R: Rng>(&mut self, rng: &mut R) -> Vec<CompletedDecision<A>> {
+        let most_urgent = self.pending
+            .iter_mut()
+            .filter(|pending| !pending.is_complete(Instant::now()))
+            .max_by_key(|pending| (pending.request.priority, Reverse(pending.request.deadline)));
+
+        if let Some(pending) = most_urgent {
+            let num_simulations = match pending.request.max_simulations {
+                Some(max) => self.quantum.min(max - pending.num_simulations),
+                None => self.quantum,
+            };
+
+            pending.error = pending.tree.search_n(rng, num_simulations).err().map(SearchError::into_formatted);
+            pending.num_simulations += num_simulations;
+        }
+
+        self.take_completed(Instant::now())
+    }
+
+    /// Runs quanta until every pending decision has completed.
+    pub fn run_until_idle<R: Rng>(&mut self, rng: &mut R) -> Vec<CompletedDecision<A>> {
+        let mut completed = Vec::new();
+        while !self.is_idle() {
+            completed.extend(self.run_quantum(rng));
+        }
+
+        completed
+    }
+
+    fn take_completed(&mut self, now: Instant) -> Vec<CompletedDecision<A>> {
+        let (completed, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|pending| pending.is_complete(now));
+
+        self.pending = pending;
+
+        completed
+            .into_iter()
+            .map(|completed: PendingDecision<S, A, P>| CompletedDecision {
+                id: completed.id,
+                action: completed.tree.best_action().cloned(),
+                num_simulations: completed.num_simulations,
+                error: completed.error,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,198 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+use rand::{Rng, SeedableRng};
+use crate::{Action, Describe, Outcome, Player, ScoreValue, State};
+use crate::ai::agents::{outcome_value, Agent, Decision};
+use crate::ai::batch;
+use crate::ai::game::{Game, GameError, Resignation};
+use crate::ai::information_set::ObservableState;
+use crate::ai::replay::Replay;
+use crate::ai::rng::{seeded_rng, stream_seed};
+use crate::ai::tournament::AgentFactory;
+
+/// The games `run_batch` plays: who plays them and where they start.
+pub struct SelfPlayConfig<R, S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    state: S,
+    seed: u64,
+    agents: HashMap<P, AgentFactory<R, S, A, P>>,
+    state_id: Option<String>,
+    resignation: Option<Resignation>,
+}
+
+impl<R, S, A, P> SelfPlayConfig<R, S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    /// Plays every game from `state`, with the games' rngs split off `seed`, so a batch is
+    /// reproduced by running it with the same seed.
+    pub fn new(state: S, seed: u64) -> Self {
+        Self {
+            state,
+            seed,
+            agents: HashMap::new(),
+            state_id: None,
+            resignation: None,
+        }
+    }
+
+    /// Has agents created by `create_agent` play for `player`, a new one for each game.
+    pub fn with_agent<F: Fn(P) -> Box<dyn Agent<R, S, A, P>> + Send + Sync + 'static>(mut self, player: P, create_agent: F) -> Self {
+        self.agents.insert(player, Arc::new(create_agent));
+        self
+    }
+
+    /// Records a replay of every game, whose initial state `state_id` identifies.
+    pub fn with_records<I: Into<String>>(mut self, state_id: I) -> Self {
+        self.state_id = Some(state_id.into());
+        self
+    }
+
+    /// Lets the agents resign lost games rather than play them out, see `Game::with_resignation`.
+    pub fn with_resignation(mut self, resignation: Resignation) -> Self {
+        self.resignation = Some(resignation);
+        self
+    }
+}
+
+/// A game played by `run_batch`.
+pub struct SelfPlayGame<A, P> where A: Action + Describe, P: Player + Describe {
+    /// the game's outcome, or the error it was abandoned with
+    pub result: Result<Outcome<P>, GameError<A, P>>,
+    /// the game's replay, if the batch was configured `with_records`
+    pub replay: Option<Replay<A, P>>,
+}
+
+/// Plays `num_games` games as configured by `config` on a pool of `parallelism` worker threads,
+/// each game with rngs of its own, e.g. to measure an agent's strength or to generate training
+/// data. Returns the games in the order they were seeded in.
+pub fn run_batch<R, S, A, P>(config: &SelfPlayConfig<R, S, A, P>, num_games: u32, parallelism: usize) -> Vec<SelfPlayGame<A, P>>
+    where
+        R: Rng + SeedableRng,
+        S: State<A, P> + Sync,
+        A: Action + Describe + Send,
+        P: Player + Describe + Send + Sync,
+{
+    batch::run_batch(game_seeds(config, num_games), parallelism, |(agent_seed, transition_seed)| {
+        play_game(config, agent_seed, transition_seed, |agent| agent)
+    })
+}
+
+/// A decision made during self-play, in a form to train an AlphaZero style network on.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrainingSample<O, A, P> {
+    /// what the player deciding could see of the state
+    pub observation: O,
+    pub player: P,
+    /// the share of the search each action got, the target for the policy
+    pub visits: Vec<(A, ScoreValue)>,
+    /// what the game's outcome was worth to the player, the target for the value: 1 for a win,
+    /// including the opponent resigning, 0.5 for a draw, 0 for a loss, or their reward in a game
+    /// ending with scores
+    pub value: ScoreValue,
+}
+
+/// A game played by `run_batch_with_samples`, along with the samples of its decisions.
+pub type SampledGame<O, A, P> = (SelfPlayGame<A, P>, Vec<TrainingSample<O, A, P>>);
+
+/// Like `run_batch`, also returning a training sample for every decision the agents made in each
+/// game. Games which were abandoned have no samples, as they have no outcome to learn from.
+pub fn run_batch_with_samples<R, S, A, P>(config: &SelfPlayConfig<R, S, A, P>, num_games: u32, parallelism: usize) -> Vec<SampledGame<S::Observation, A, P>>
+    where
+        R: Rng + SeedableRng + 'static,
+        S: ObservableState<A, P> + Sync + 'static,
+        S::Observation: Send,
+        A: Action + Describe + Send + 'static,
+        P: Player + Describe + Send + Sync,
+{
+    batch::run_batch(game_seeds(config, num_games), parallelism, |(agent_seed, transition_seed)| {
+        let decisions: Decisions<S::Observation, A, P> = Rc::default();
+        let game = play_game(config, agent_seed, transition_seed, |agent| {
+            Box::new(SampledAgent { agent, decisions: decisions.clone() })
+        });
+
+        let samples = match &game.result {
+            Ok(outcome) => decisions
+                .take()
+                .into_iter()
+                .map(|(observation, player, visits)| TrainingSample {
+                    observation,
+                    value: outcome_value(outcome, &player),
+                    player,
+                    visits,
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        (game, samples)
+    })
+}
+
+/// the seeds of each game's agents and random transitions, split off the config's seed
+fn game_seeds<R, S, A, P>(config: &SelfPlayConfig<R, S, A, P>, num_games: u32) -> Vec<(u64, u64)>
+    where
+        R: Rng,
+        S: State<A, P>,
+        A: Action,
+        P: Player,
+{
+    let base_rng = seeded_rng(config.seed);
+    (0..num_games)
+        .map(|game_idx| (stream_seed(&base_rng, 2 * game_idx), stream_seed(&base_rng, 2 * game_idx + 1)))
+        .collect()
+}
+
+/// plays one game, with each of the configured agents passed through `wrap_agent`
+fn play_game<R, S, A, P, F>(config: &SelfPlayConfig<R, S, A, P>, agent_seed: u64, transition_seed: u64, mut wrap_agent: F) -> SelfPlayGame<A, P>
+    where
+        R: Rng + SeedableRng,
+        S: State<A, P>,
+        A: Action + Describe,
+        P: Player + Describe,
+        F: FnMut(Box<dyn Agent<R, S, A, P>>) -> Box<dyn Agent<R, S, A, P>>,
+{
+    let agents = config.agents
+        .iter()
+        .map(|(player, create_agent)| (player.clone(), wrap_agent(create_agent(player.clone()))))
+        .collect();
+
+    let mut game = Game::new(R::seed_from_u64(agent_seed), config.state.clone(), agents);
+    if let Some(state_id) = &config.state_id {
+        game = game.with_recording(transition_seed, state_id.clone());
+    }
+    if let Some(resignation) = config.resignation {
+        game = game.with_resignation(resignation);
+    }
+
+    SelfPlayGame {
+        result: game.run(),
+        replay: config.state_id.is_some().then(|| game.replay()),
+    }
+}
+
+/// each decision's observation, player and visit distribution, shared with the game's agents
+type Decisions<O, A, P> = Rc<RefCell<Vec<(O, P, Vec<(A, ScoreValue)>)>>>;
+
+/// records every decision of the agent it wraps, along with what its player could see
+struct SampledAgent<R, S, A, P> where R: Rng, S: ObservableState<A, P>, A: Action, P: Player {
+    agent: Box<dyn Agent<R, S, A, P>>,
+    decisions: Decisions<S::Observation, A, P>,
+}
+
+impl<R, S, A, P> Agent<R, S, A, P> for SampledAgent<R, S, A, P> where R: Rng, S: ObservableState<A, P>, A: Action, P: Player {
+    fn player(&self) -> P {
+        self.agent.player()
+    }
+
+    fn decide(&self, rng: &mut R, state: &S) -> Option<A> {
+        self.decide_in_detail(rng, state).map(|decision| decision.action)
+    }
+
+    fn decide_in_detail(&self, rng: &mut R, state: &S) -> Option<Decision<A>> {
+        let decision = self.agent.decide_in_detail(rng, state)?;
+        let player = state.current_player();
+        self.decisions.borrow_mut().push((state.observation(player.clone()), player, decision.visits.clone()));
+
+        Some(decision)
+    }
+}
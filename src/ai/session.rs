@@ -0,0 +1,187 @@
+use std::collections::HashSet;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::Hash;
+use rand::Rng;
+use thiserror::Error;
+use crate::{Action, Describe, Player, State};
+use crate::ai::ismcts::{Determinable, MultithreadedInformationSetGame, MultithreadedInformationSetGameError};
+
+#[derive(Error)]
+pub enum SessionError<A: Action + Describe, P: Player + Describe> {
+    #[error("it's the turn of human player {}, who has to play their own move", .0.describe())]
+    HumanToMove(P),
+
+    #[error("it's the turn of player {}, who isn't human", .0.describe())]
+    NotHumanToMove(P),
+
+    #[error(transparent)]
+    Game(#[from] MultithreadedInformationSetGameError<A, P>),
+}
+
+impl<A: Action + Describe, P: Player + Describe> Debug for SessionError<A, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HumanToMove(player) => f.debug_tuple("HumanToMove").field(&player.describe()).finish(),
+            Self::NotHumanToMove(player) => f.debug_tuple("NotHumanToMove").field(&player.describe()).finish(),
+            Self::Game(error) => f.debug_tuple("Game").field(error).finish(),
+        }
+    }
+}
+
+/// a move which has been played, along with the states either side of it
+struct PlayedMove<S, A> {
+    before: S,
+    action: A,
+    after: S,
+}
+
+/// A game shared between human players taking turns at the same device and AI agents, supporting
+/// undo and redo.
+///
+/// Undoing restores the state from before the move rather than re-deriving it, and redoing
+/// restores the state the move originally led to, so random transitions are replayed exactly.
+/// Either way the game's policies are notified through `Policy::restore`.
+pub struct Session<R, S, A, P>
+    where
+        R: Rng + Clone + Send,
+        S: State<A, P> + Determinable<S, A, P> + Send,
+        A: Action + Send + Sync + Eq + Hash,
+        P: Player + Send + Sync,
+{
+    game: MultithreadedInformationSetGame<R, S, A, P>,
+    humans: HashSet<P>,
+    undo_stack: Vec<PlayedMove<S, A>>,
+    redo_stack: Vec<PlayedMove<S, A>>,
+}
+
+impl<R, S, A, P> Session<R, S, A, P>
+    where
+        R: Rng + Clone + Send,
+        S: State<A, P> + Determinable<S, A, P> + Send,
+        A: Action + Send + Sync + Eq + Hash + Describe,
+        P: Player + Send + Sync + Describe,
+{
+    /// Creates a session where `humans` play their own moves, and the rest are decided by `game`.
+    pub fn new<I: IntoIterator<Item = P>>(game: MultithreadedInformationSetGame<R, S, A, P>, humans: I) -> Self {
+        Self {
+            game,
+            humans: humans.into_iter().collect(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    pub fn state(&self) -> &S {
+        &self.game.state
+    }
+
+    pub fn game(&self) -> &MultithreadedInformationSetGame<R, S, A, P> {
+        &self.game
+    }
+
+    /// the human player whose move it is, if it's a human's move
+    pub fn human_to_move(&self) -> Option<P> {
+        if self.game.is_terminated() {
+            return None;
+        }
+
+        let current_player = self.game.state.current_player();
+        self.humans.contains(&current_player).then_some(current_player)
+    }
+
+    /// Has the AI decide the current move.
+    pub fn step(&mut self) -> Result<A, SessionError<A, P>> {
+        if let Some(human) = self.human_to_move() {
+            return Err(SessionError::HumanToMove(human));
+        }
+
+        let before = self.game.state.clone();
+        let action = self.game.step()?;
+        self.record(before, action.clone());
+
+        Ok(action)
+    }
+
+    /// Plays a human's move, which has to be legal.
+    pub fn play(&mut self, action: A) -> Result<(), SessionError<A, P>> {
+        let current_player = self.game.state.current_player();
+        if self.human_to_move().is_none() {
+            return Err(SessionError::NotHumanToMove(current_player));
+        }
+
+        let before = self.game.state.clone();
+        self.game.step_with_action(action.clone())?;
+        self.record(before, action);
+
+        Ok(())
+    }
+
+    /// Plays AI moves until it's a human's move or the game is over.
+    pub fn run_until_human(&mut self) -> Result<(), SessionError<A, P>> {
+        while !self.game.is_terminated() && self.human_to_move().is_none() {
+            self.step()?;
+        }
+
+        Ok(())
+    }
+
+    fn record(&mut self, before: S, action: A) {
+        self.undo_stack.push(PlayedMove {
+            before,
+            action,
+            after: self.game.state.clone(),
+        });
+
+        // a new move replaces whatever had been undone
+        self.redo_stack.clear();
+    }
+
+    /// Takes back the last move, returning it.
+    pub fn undo(&mut self) -> Option<&A> {
+        let played_move = self.undo_stack.pop()?;
+        let mut history = self.game.history().to_vec();
+        history.pop();
+        self.game.restore(played_move.before.clone(), history);
+        self.redo_stack.push(played_move);
+
+        self.redo_stack.last().map(|played_move| &played_move.action)
+    }
+
+    /// Takes back moves until it's a human's move again, e.g. the AI's replies along with the
+    /// human's own move. Returns the number of moves taken back.
+    pub fn undo_to_human(&mut self) -> usize {
+        let mut num_undone = 0;
+        while self.undo().is_some() {
+            num_undone += 1;
+            if self.human_to_move().is_some() {
+                break;
+            }
+        }
+
+        num_undone
+    }
+
+    /// Replays the last move which was undone, returning it.
+    pub fn redo(&mut self) -> Option<&A> {
+        let played_move = self.redo_stack.pop()?;
+        let mut history = self.game.history().to_vec();
+        history.push((played_move.before.current_player(), played_move.action.clone()));
+        self.game.restore(played_move.after.clone(), history);
+        self.undo_stack.push(played_move);
+
+        self.undo_stack.last().map(|played_move| &played_move.action)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// the moves played so far, oldest first
+    pub fn history(&self) -> impl Iterator<Item = &A> {
+        self.game.history().iter().map(|(_, action)| action)
+    }
+}
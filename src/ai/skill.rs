@@ -0,0 +1,122 @@
+use rand::Rng;
+use crate::Action;
+use crate::ai::game_tree::score::ScoreValue;
+use crate::ai::rollout::weighted_choice;
+
+/// How well a searching agent plays, so one engine can ship easy, medium and hard bots. A weaker
+/// agent searches less, misjudges its actions by some noise and samples among its best few
+/// actions rather than always playing the best. Defaults to full strength.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Skill {
+    /// the most simulations the agent runs per decision, or per determinization, whatever it was
+    /// configured with
+    pub max_simulations: Option<u32>,
+    /// how many of the most visited actions the agent chooses among
+    pub num_candidates: usize,
+    /// how evenly the agent samples the candidates by their values, 0 always playing the best
+    pub temperature: ScoreValue,
+    /// the most uniform noise added to each candidate's value before choosing, which only matters
+    /// with more than one candidate
+    pub value_noise: ScoreValue,
+}
+
+impl Default for Skill {
+    fn default() -> Self {
+        Self {
+            max_simulations: None,
+            num_candidates: 1,
+            temperature: 0.0,
+            value_noise: 0.0,
+        }
+    }
+}
+
+impl Skill {
+    /// a beginner, who often misses the best action
+    pub fn easy() -> Self {
+        Self {
+            max_simulations: Some(50),
+            num_candidates: 4,
+            temperature: 0.5,
+            value_noise: 0.2,
+        }
+    }
+
+    /// a casual player, who mostly plays well but makes the odd mistake
+    pub fn medium() -> Self {
+        Self {
+            max_simulations: Some(400),
+            num_candidates: 3,
+            temperature: 0.1,
+            value_noise: 0.05,
+        }
+    }
+
+    /// full strength
+    pub fn hard() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_simulations(mut self, max_simulations: u32) -> Self {
+        self.max_simulations = Some(max_simulations);
+        self
+    }
+
+    pub fn with_num_candidates(mut self, num_candidates: usize) -> Self {
+        self.num_candidates = num_candidates;
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: ScoreValue) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    pub fn with_value_noise(mut self, value_noise: ScoreValue) -> Self {
+        self.value_noise = value_noise;
+        self
+    }
+
+    /// `num_simulations` capped at the skill's maximum, while still running at least one
+    pub(crate) fn simulations(&self, num_simulations: u32) -> u32 {
+        match self.max_simulations {
+            Some(max_simulations) => num_simulations.min(max_simulations).max(1),
+            None => num_simulations,
+        }
+    }
+
+    /// whether the agent chooses among its actions at all, rather than playing the best
+    pub(crate) fn is_full_strength(&self) -> bool {
+        self.num_candidates <= 1
+    }
+
+    /// Chooses among `actions`, each with its share of the search's visits and its value to the
+    /// agent's player, sampling the most visited candidates by their noisy values.
+    pub(crate) fn choose<R: Rng, A: Action>(&self, rng: &mut R, mut actions: Vec<(A, ScoreValue, ScoreValue)>) -> Option<A> {
+        actions.sort_by(|a, b| b.1.total_cmp(&a.1));
+        actions.truncate(self.num_candidates.max(1));
+
+        let noisy_values: Vec<ScoreValue> = actions
+            .iter()
+            .map(|(_, _, value)| match self.value_noise > 0.0 {
+                true => value + rng.gen_range(-self.value_noise..=self.value_noise),
+                false => *value,
+            })
+            .collect();
+
+        let best_idx = (0..actions.len()).max_by(|a, b| noisy_values[*a].total_cmp(&noisy_values[*b]))?;
+        if self.temperature <= 0.0 {
+            return Some(actions.swap_remove(best_idx).0);
+        }
+
+        // softmax, shifted by the best value so the weights can't overflow
+        let best_value = noisy_values[best_idx];
+        let weights: Vec<ScoreValue> = noisy_values
+            .iter()
+            .map(|value| ((value - best_value) / self.temperature).exp())
+            .collect();
+
+        Some(actions.swap_remove(weighted_choice(&weights, rng)).0)
+    }
+}
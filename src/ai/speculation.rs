@@ -0,0 +1,122 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::thread::JoinHandle;
+use rand::Rng;
+use crate::{Action, GameTree, Player, State};
+use crate::ai::rng::split_rng;
+
+#[derive(Debug, Clone)]
+pub struct SpeculationConfig {
+    /// how many of the most likely opponent replies are searched ahead of time
+    pub num_replies: usize,
+    /// the maximum number of simulations spent on each speculated reply
+    pub simulations_per_reply: u32,
+}
+
+struct SpeculatedReply<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    reply: A,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<GameTree<S, A, P>>,
+}
+
+/// An MCTS agent which, after moving, searches the opponent's most likely replies on background
+/// threads. Whichever reply is actually played gets a warm start from its speculated tree.
+///
+/// The replies and their resulting states are taken from the agent's own search tree, so games
+/// with random transitions only get a warm start when the sampled resulting state is the one
+/// which actually occurred. States are compared for that, hence the `PartialEq` bound.
+pub struct SpeculativeAgent<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    pub player: P,
+    pub num_simulations: u32,
+    pub speculation: SpeculationConfig,
+    speculated: Vec<SpeculatedReply<S, A, P>>,
+}
+
+impl<S, A, P> SpeculativeAgent<S, A, P>
+    where
+        S: State<A, P> + PartialEq + Send + 'static,
+        A: Action + Send + 'static,
+        P: Player + Send + Sync,
+{
+    pub fn new(player: P, num_simulations: u32, speculation: SpeculationConfig) -> Self {
+        Self {
+            player,
+            num_simulations,
+            speculation,
+            speculated: Vec::new(),
+        }
+    }
+
+    /// Decides on an action for `state`. `opponent_reply` is the action which led to `state` from
+    /// the state this agent last decided on, if any.
+    pub fn decide<R: Rng + Clone + Send + 'static>(&mut self, rng: &mut R, state: &S, opponent_reply: Option<&A>) -> Option<A> {
+        let mut tree = opponent_reply
+            .and_then(|reply| self.take_speculated(reply, state))
+            .unwrap_or_else(|| GameTree::new(state.clone()));
+
+        self.cancel();
+
+        tree.search_n(rng, self.num_simulations).ok()?;
+        let action = tree.best_action().cloned()?;
+
+        self.speculate(rng, &tree, &action);
+
+        Some(action)
+    }
+
+    /// Stops all background searches.
+    pub fn cancel(&mut self) {
+        for speculated in self.speculated.drain(..) {
+            speculated.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Stops the search speculating on `reply` and takes its tree, unless the reply led somewhere
+    /// other than the speculated state, e.g. as a random transition went the other way.
+    fn take_speculated(&mut self, reply: &A, state: &S) -> Option<GameTree<S, A, P>> {
+        let idx = self.speculated.iter().position(|speculated| speculated.reply == *reply)?;
+        let speculated = self.speculated.swap_remove(idx);
+        speculated.stop.store(true, Ordering::Relaxed);
+
+        let tree = speculated.handle.join().ok()?;
+        (tree.root_state() == state).then_some(tree)
+    }
+
+    fn speculate<R: Rng + Clone + Send + 'static>(&mut self, rng: &R, tree: &GameTree<S, A, P>, action: &A) {
+        let replies = tree.ranked_children(std::slice::from_ref(action));
+
+        for (idx, (reply, state, _)) in replies.into_iter().take(self.speculation.num_replies).enumerate() {
+            let mut rng = split_rng(rng, idx as u32 + 1);
+            let mut tree = GameTree::new(state.clone());
+            let num_simulations = self.speculation.simulations_per_reply;
+
+            let stop = Arc::new(AtomicBool::new(false));
+            let thread_stop = stop.clone();
+
+            let handle = thread::spawn(move || {
+                for _ in 0..num_simulations {
+                    if thread_stop.load(Ordering::Relaxed) || tree.search(&mut rng).is_err() {
+                        break;
+                    }
+                }
+
+                tree
+            });
+
+            self.speculated.push(SpeculatedReply {
+                reply: reply.clone(),
+                stop,
+                handle,
+            });
+        }
+    }
+}
+
+impl<S, A, P> Drop for SpeculativeAgent<S, A, P> where S: State<A, P>, A: Action, P: Player {
+    fn drop(&mut self) {
+        for speculated in &self.speculated {
+            speculated.stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How the search reacts to suspicious situations, such as a state offering the same action twice,
+/// a non-terminal state without any actions, or an inconsistent outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strictness {
+    /// suspicious situations are returned as errors, useful while developing a game
+    Strict,
+    /// suspicious situations are worked around and logged as warnings, useful in shipped games
+    Lenient,
+}
+
+const UNSET: u8 = 0;
+const STRICT: u8 = 1;
+const LENIENT: u8 = 2;
+
+static STRICTNESS: AtomicU8 = AtomicU8::new(UNSET);
+
+/// Sets the strictness of every search in the process.
+pub fn set_strictness(strictness: Strictness) {
+    let value = match strictness {
+        Strictness::Strict => STRICT,
+        Strictness::Lenient => LENIENT,
+    };
+
+    STRICTNESS.store(value, Ordering::Relaxed);
+}
+
+/// The strictness set by `set_strictness`, which defaults to strict in debug builds and lenient in
+/// release builds.
+pub fn strictness() -> Strictness {
+    match STRICTNESS.load(Ordering::Relaxed) {
+        STRICT => Strictness::Strict,
+        LENIENT => Strictness::Lenient,
+        _ if cfg!(debug_assertions) => Strictness::Strict,
+        _ => Strictness::Lenient,
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use rand::{Rng, SeedableRng};
+use crate::{Action, Describe, Outcome, Player, State};
+use crate::ai::agents::Agent;
+use crate::ai::batch::default_parallelism;
+use crate::ai::game::Game;
+use crate::ai::rng::stream_seed;
+
+/// The resources a set of concurrently played games may use between them.
+#[derive(Debug, Clone)]
+pub struct ResourceBudget {
+    pub max_threads: usize,
+    pub max_memory_bytes: usize,
+}
+
+impl Default for ResourceBudget {
+    fn default() -> Self {
+        Self {
+            max_threads: default_parallelism(),
+            max_memory_bytes: usize::MAX,
+        }
+    }
+}
+
+/// A unit of work, typically one game of a pairing, along with an estimate of what it needs.
+pub struct Job<'a, T> {
+    num_threads: usize,
+    memory_estimate_bytes: usize,
+    run: Box<dyn FnOnce() -> T + Send + 'a>,
+}
+
+impl<'a, T> Job<'a, T> {
+    pub fn new<F: FnOnce() -> T + Send + 'a>(run: F) -> Self {
+        Self {
+            num_threads: 1,
+            memory_estimate_bytes: 0,
+            run: Box::new(run),
+        }
+    }
+
+    /// The number of threads the job keeps busy, e.g. an `ismcts_mt` agent's parallelism.
+    pub fn with_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = num_threads.max(1);
+        self
+    }
+
+    /// The peak memory the job is expected to use, e.g. the size of its agents' search trees.
+    pub fn with_memory_estimate(mut self, memory_estimate_bytes: usize) -> Self {
+        self.memory_estimate_bytes = memory_estimate_bytes;
+        self
+    }
+}
+
+#[derive(Default)]
+struct Usage {
+    num_threads: usize,
+    memory_bytes: usize,
+    num_running: usize,
+}
+
+impl Usage {
+    fn admits<T>(&self, job: &Job<T>, budget: &ResourceBudget) -> bool {
+        // a job which alone exceeds the budget still runs, just never alongside anything else
+        self.num_running == 0 || (
+            self.num_threads + job.num_threads <= budget.max_threads
+                && self.memory_bytes.saturating_add(job.memory_estimate_bytes) <= budget.max_memory_bytes
+        )
+    }
+}
+
+struct Reservation<'a> {
+    usage: &'a Mutex<Usage>,
+    released: &'a Condvar,
+    num_threads: usize,
+    memory_estimate_bytes: usize,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        let mut usage = self.usage.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        usage.num_threads -= self.num_threads;
+        usage.memory_bytes = usage.memory_bytes.saturating_sub(self.memory_estimate_bytes);
+        usage.num_running -= 1;
+        self.released.notify_all();
+    }
+}
+
+/// Runs every job concurrently, in order, starting each one only once it fits in the budget
+/// alongside the jobs already running. Returns the results in the same order as the jobs.
+pub fn run_budgeted<T: Send>(jobs: Vec<Job<T>>, budget: &ResourceBudget) -> Vec<T> {
+    let num_jobs = jobs.len();
+    let usage = Mutex::new(Usage::default());
+    let released = Condvar::new();
+    let results: Mutex<Vec<Option<T>>> = Mutex::new((0..num_jobs).map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for (idx, job) in jobs.into_iter().enumerate() {
+            let mut current_usage = usage.lock().unwrap();
+            while !current_usage.admits(&job, budget) {
+                current_usage = released.wait(current_usage).unwrap();
+            }
+
+            current_usage.num_threads += job.num_threads;
+            current_usage.memory_bytes = current_usage.memory_bytes.saturating_add(job.memory_estimate_bytes);
+            current_usage.num_running += 1;
+            drop(current_usage);
+
+            let usage = &usage;
+            let released = &released;
+            let results = &results;
+            scope.spawn(move || {
+                let Job { num_threads, memory_estimate_bytes, run } = job;
+
+                // released on drop, so a panicking job doesn't leave the others waiting forever
+                let _reservation = Reservation { usage, released, num_threads, memory_estimate_bytes };
+
+                let result = run();
+                results.lock().unwrap()[idx] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every job is run"))
+        .collect()
+}
+
+/// Creates an entrant's agent for the seat it's given in a game.
+pub type AgentFactory<R, S, A, P> = Arc<dyn Fn(P) -> Box<dyn Agent<R, S, A, P>> + Send + Sync>;
+
+/// A round-robin tournament between agent configurations in a two-player game, where every pair of
+/// entrants plays a number of games from the same state, swapping seats each game so neither moves
+/// first more often than the other.
+pub struct RoundRobin<R, S, A, P> where R: Rng, S: State<A, P>, A: Action, P: Player {
+    state: S,
+    seats: [P; 2],
+    entrants: Vec<(String, AgentFactory<R, S, A, P>)>,
+    games_per_pairing: u32,
+    budget: ResourceBudget,
+}
+
+impl<R, S, A, P> RoundRobin<R, S, A, P>
+    where
+        R: Rng + Clone + SeedableRng,
+        S: State<A, P> + Send + Sync,
+        A: Action + Describe,
+        P: Player + Describe + Send + Sync,
+{
+    /// Plays every game from `state`, with one entrant in each of `seats`, e.g. white and black.
+    /// Each pairing plays two games by default, one from either seat.
+    pub fn new(state: S, seats: [P; 2]) -> Self {
+        Self {
+            state,
+            seats,
+            entrants: Vec::new(),
+            games_per_pairing: 2,
+            budget: ResourceBudget::default(),
+        }
+    }
+
+    /// Enters a configuration under `name`, e.g. "mcts 1000", whose agents `create_agent` creates
+    /// for the seat they're given.
+    pub fn with_entrant<N, F>(mut self, name: N, create_agent: F) -> Self
+        where
+            N: Into<String>,
+            F: Fn(P) -> Box<dyn Agent<R, S, A, P>> + Send + Sync + 'static,
+    {
+        self.entrants.push((name.into(), Arc::new(create_agent)));
+        self
+    }
+
+    pub fn with_games_per_pairing(mut self, games_per_pairing: u32) -> Self {
+        self.games_per_pairing = games_per_pairing;
+        self
+    }
+
+    /// The resources the tournament's games may use between them, as they're played concurrently.
+    pub fn with_budget(mut self, budget: ResourceBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Plays every game of every pairing, each with its own rng split off `rng`, so a tournament
+    /// is reproduced by running it with the same rng.
+    pub fn run(&self, rng: &R) -> TournamentResults {
+        let mut pairings = Vec::new();
+        for first_idx in 0..self.entrants.len() {
+            for second_idx in first_idx + 1..self.entrants.len() {
+                pairings.push((first_idx, second_idx));
+            }
+        }
+
+        let mut jobs = Vec::new();
+        for &(first_idx, second_idx) in &pairings {
+            for game_idx in 0..self.games_per_pairing {
+                let seed = stream_seed(rng, jobs.len() as u32);
+                jobs.push(Job::new(move || self.play(first_idx, second_idx, game_idx % 2 == 1, seed)));
+            }
+        }
+
+        let mut verdicts = run_budgeted(jobs, &self.budget).into_iter();
+        let pairings = pairings
+            .into_iter()
+            .map(|(first_idx, second_idx)| {
+                let mut result = PairingResult {
+                    first: self.entrants[first_idx].0.clone(),
+                    second: self.entrants[second_idx].0.clone(),
+                    ..Default::default()
+                };
+
+                for verdict in verdicts.by_ref().take(self.games_per_pairing as usize) {
+                    match verdict {
+                        Some(Verdict::FirstWins) => result.wins += 1,
+                        Some(Verdict::Draw) => result.draws += 1,
+                        Some(Verdict::SecondWins) => result.losses += 1,
+                        None => result.unfinished += 1,
+                    }
+                }
+
+                result
+            })
+            .collect();
+
+        TournamentResults { pairings }
+    }
+
+    /// plays one game of a pairing, returning None if it ended without a result
+    fn play(&self, first_idx: usize, second_idx: usize, swap_seats: bool, seed: u64) -> Option<Verdict> {
+        let [first_seat, second_seat] = match swap_seats {
+            false => [&self.seats[0], &self.seats[1]],
+            true => [&self.seats[1], &self.seats[0]],
+        };
+
+        let agents = HashMap::from([
+            (first_seat.clone(), (self.entrants[first_idx].1)(first_seat.clone())),
+            (second_seat.clone(), (self.entrants[second_idx].1)(second_seat.clone())),
+        ]);
+
+        let mut game = Game::new(R::seed_from_u64(seed), self.state.clone(), agents);
+        match game.run() {
+            Ok(outcome) => Verdict::of(&outcome, first_seat, second_seat),
+            Err(error) => {
+                log::warn!("a tournament game was abandoned: {error}");
+                None
+            }
+        }
+    }
+}
+
+/// how a game went for the entrant listed first in its pairing
+enum Verdict {
+    FirstWins,
+    Draw,
+    SecondWins,
+}
+
+impl Verdict {
+    fn of<P: Player>(outcome: &Outcome<P>, first_seat: &P, second_seat: &P) -> Option<Self> {
+        match outcome {
+            Outcome::Winner(winner) | Outcome::WinnerBy(winner, _) => match winner.teammates().contains(first_seat) {
+                true => Some(Verdict::FirstWins),
+                false => Some(Verdict::SecondWins),
+            },
+            Outcome::Draw(_) => Some(Verdict::Draw),
+            Outcome::Scores(rewards) => {
+                let first_reward = rewards.get(first_seat).copied().unwrap_or(0.0);
+                let second_reward = rewards.get(second_seat).copied().unwrap_or(0.0);
+                match first_reward.partial_cmp(&second_reward)? {
+                    std::cmp::Ordering::Greater => Some(Verdict::FirstWins),
+                    std::cmp::Ordering::Equal => Some(Verdict::Draw),
+                    std::cmp::Ordering::Less => Some(Verdict::SecondWins),
+                }
+            }
+            Outcome::Resignation(resigner) => match resigner.teammates().contains(first_seat) {
+                true => Some(Verdict::SecondWins),
+                false => Some(Verdict::FirstWins),
+            },
+            Outcome::Escape(_) => None,
+        }
+    }
+}
+
+/// The games between two entrants, counted from the point of view of the first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PairingResult {
+    pub first: String,
+    pub second: String,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// games which were abandoned with an error or ended in an escape, rather than with a result
+    pub unfinished: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TournamentResults {
+    pub pairings: Vec<PairingResult>,
+}
+
+impl TournamentResults {
+    /// An entrant's wins, draws and losses over every one of its pairings.
+    pub fn record(&self, name: &str) -> (u32, u32, u32) {
+        self.pairings.iter().fold((0, 0, 0), |(wins, draws, losses), pairing| {
+            if pairing.first == name {
+                (wins + pairing.wins, draws + pairing.draws, losses + pairing.losses)
+            } else if pairing.second == name {
+                (wins + pairing.losses, draws + pairing.draws, losses + pairing.wins)
+            } else {
+                (wins, draws, losses)
+            }
+        })
+    }
+}
+
+/// one line per pairing, e.g. "mcts 1000 vs random: +7 =2 -1"
+impl Display for TournamentResults {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for pairing in &self.pairings {
+            write!(f, "{} vs {}: +{} ={} -{}", pairing.first, pairing.second, pairing.wins, pairing.draws, pairing.losses)?;
+            if pairing.unfinished > 0 {
+                write!(f, " ({} unfinished)", pairing.unfinished)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,37 +1,271 @@
 mod ai;
 
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 use rand::{Rng};
 
 pub use ai::{
     mcts,
+    game,
+    agents,
     ismcts,
+    audit,
+    scheduler,
+    speculation,
+    strictness,
+    perspective,
+    tournament,
+    ratings,
+    replay,
+    selfplay,
+    skill,
+    calibration,
+    ponder,
+    cache,
+    information_set,
+    benchmark,
+    policy,
+    session,
+    belief,
+    determinize,
     game_tree::{
         GameTree,
+        ArenaGameTree,
+        StatisticsDecay,
+        ChanceExpansion,
+        MarginScaling,
+        ExactEvaluator,
+        EndgameSolver,
+        IterationReport,
+        SearchProgress,
         node::GameTreeNode,
         edge::GameTreeEdge,
+        score::{Score, ScoreValue},
+        annotation::{AnnotatedAction, Annotation},
+        backend::{TreeBackend, GraphBackend, ArenaBackend},
+        error::SearchError,
+        summary::{TreeSummary, ActionSummary, SolverStatus},
+        transposition::{TranspositionTable, TranspositionEntry},
+        context::SearchContext,
     },
-    random_rollout::random_rollout
+    random_rollout::{random_rollout, random_rollout_with_max_length, random_rollout_with_context, rollout_batch, random_rollout_traced, RolloutStep, RolloutTrace},
+    evaluator::{Evaluator, StateEvaluation},
+    rollout::{RolloutPolicy, RandomRollout, WeightedRollout, PriorRollout, EpsilonGreedyRollout, DepthLimitedRollout, MastRollout, NstRollout, LastGoodReplyRollout, PlayoutStatistics, PlayoutEntry},
 };
 
-pub trait Action: Clone {}
+#[cfg(feature = "tokio")]
+pub use ai::async_search;
 
-pub trait Player: 'static + Copy + Clone + Hash + Eq + PartialEq {}
+/// An action a player can take. Actions are compared to one another, e.g. to catch a state
+/// offering the same action twice or to follow a played action down a kept tree, so unlike in 0.1
+/// they have to implement `PartialEq`.
+pub trait Action: Clone + PartialEq {}
+
+pub trait Player: 'static + Clone + Hash + Eq + PartialEq {
+    /// Every player on this player's team, including this player, e.g. both partners in bridge.
+    /// A win or draw credits the whole team, so partners search for the team's value and
+    /// aggregation counts them as allies rather than opponents. Defaults to a team of one.
+    fn teammates(&self) -> Vec<Self> {
+        vec![self.clone()]
+    }
+
+    /// Whether this is a pseudo-player standing for chance, e.g. `Seat::Nature`, whose actions the
+    /// search samples by `State::action_priors` rather than choosing the best. Defaults to false.
+    fn is_chance(&self) -> bool {
+        false
+    }
+}
+
+/// A player type for games which model chance as a turn of its own, e.g. a dealer phase or a dice
+/// phase, where `current_player` returns `Seat::Nature`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Seat<P> {
+    Player(P),
+    Nature,
+}
+
+impl<P: Player> Player for Seat<P> {
+    fn teammates(&self) -> Vec<Self> {
+        match self {
+            Seat::Player(player) => player.teammates().into_iter().map(Seat::Player).collect(),
+            Seat::Nature => vec![Seat::Nature],
+        }
+    }
+
+    fn is_chance(&self) -> bool {
+        matches!(self, Seat::Nature)
+    }
+}
+
+/// A human readable name for an action or player, e.g. "e4" or "North", which diagnostics like
+/// error messages, score reports and tree exports show in place of derived `Debug` output.
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+macro_rules! describe_with_to_string {
+    ($($t:ty),*) => {
+        $(impl Describe for $t {
+            fn describe(&self) -> String {
+                self.to_string()
+            }
+        })*
+    };
+}
+
+describe_with_to_string!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, char, bool, String, &'static str);
+
+impl<P: Describe> Describe for Seat<P> {
+    fn describe(&self) -> String {
+        match self {
+            Seat::Player(player) => player.describe(),
+            Seat::Nature => "nature".to_string(),
+        }
+    }
+}
 
 pub trait State<A: Action, P: Player>: Sized + Clone {
     type Error: Debug;
 
     fn actions(&self) -> Vec<A>;
+
+    /// Appends the actions to `actions`, a buffer the search clears and reuses between calls.
+    /// States can override it to write their actions straight into the buffer rather than
+    /// allocating a new `Vec` for every call to `actions`.
+    fn actions_into(&self, actions: &mut Vec<A>) {
+        actions.extend(self.actions());
+    }
+
+    /// The action played when a state which isn't over has no actions, e.g. passing when a player
+    /// has no legal move. Without one such a state is an error in strict mode and an escape
+    /// otherwise, as the search has nothing to expand into.
+    fn pass_action(&self) -> Option<A> {
+        None
+    }
+
+    /// Returns at most `max_actions` of the actions, starting from the `start`th, in the same order
+    /// as `actions`. States with very many actions can override this to generate them lazily, so
+    /// a search with an expansion cap doesn't have to generate every action on a node's first visit.
+    fn actions_chunk(&self, start: usize, max_actions: usize) -> Vec<A> {
+        self.actions().into_iter().skip(start).take(max_actions).collect()
+    }
+    /// A hash identifying the state, for states which can be reached along several paths, or in
+    /// several determinizations, to pool their statistics through a `TranspositionTable`. States
+    /// which return None aren't shared.
+    fn transposition_key(&self) -> Option<u64> {
+        None
+    }
     fn apply_action<R: Rng>(&self, rng: &mut R, action: &A) -> Result<Self, Self::Error>;
+
+    /// Applies the action to the state itself, which rollouts use as they never look back at the
+    /// states they pass through. Defaults to replacing the state with the one `apply_action`
+    /// returns; a `MutableState` can override it to call `apply_in_place` instead, saving a clone
+    /// of the state every ply.
+    fn apply_action_mut<R: Rng>(&mut self, rng: &mut R, action: &A) -> Result<(), Self::Error> {
+        *self = self.apply_action(rng, action)?;
+        Ok(())
+    }
     fn outcome(&self) -> Option<Outcome<P>>;
 
+    /// A prior probability, or any non-negative weight, for each of `actions` in the same order,
+    /// e.g. from a trained policy network. PUCT selection and `PriorRollout` sample by them, and
+    /// children are expanded most likely first. Defaults to none, which treats every action alike.
+    fn action_priors(&self, _actions: &[A]) -> Vec<ScoreValue> {
+        Vec::new()
+    }
+
+    /// A static evaluation of the state for each player, e.g. a material balance scaled to between
+    /// 0 and 1 like a win, for games with domain knowledge to plug into the search. It biases
+    /// selection under `GameTree::with_progressive_bias`, values rollouts cut off by
+    /// `RandomRollout::with_max_length`, and can value every rollout through
+    /// `DepthLimitedRollout::new(max_plies, S::heuristic_value)`. Defaults to none, leaving
+    /// states valued only by playing them out.
+    fn heuristic_value(&self) -> Vec<(P, ScoreValue)> {
+        Vec::new()
+    }
+
+    /// The states a chance event, e.g. a dice roll, can turn this state into along with their
+    /// probabilities, for games which model chance explicitly rather than inside `apply_action`.
+    /// A state returning any is a chance node: nobody chooses its actions, the search branches on
+    /// its outcomes and rollouts sample one. Defaults to none.
+    fn chance_outcomes(&self) -> Vec<(Self, ScoreValue)> {
+        Vec::new()
+    }
+
+    /// An outcome which is already decided although the game isn't over, e.g. an insurmountable
+    /// material advantage. Rollouts consult it at each step and stop early once it returns one,
+    /// while the search itself still plays such states out.
+    fn quick_verdict(&self) -> Option<Outcome<P>> {
+        None
+    }
+
     fn current_player(&self) -> P;
+
+    /// Every player in the game, including those who haven't scored yet, so score reports list
+    /// each of them rather than only the players the search happened to credit. Defaults to none,
+    /// leaving the players to be discovered from the scores.
+    fn players(&self) -> Vec<P> {
+        Vec::new()
+    }
+}
+
+/// A state which can list the actions that led to it, oldest first, so history heuristics like
+/// `LastGoodReplyRollout`, and repetition detection, can be implemented once by the search rather
+/// than by every game.
+pub trait History<A: Action, P: Player>: State<A, P> {
+    fn history(&self) -> &[A];
+
+    fn last_action(&self) -> Option<&A> {
+        self.history().last()
+    }
+
+    /// Whether the last `cycle_length` actions repeat the `cycle_length` actions before them, e.g.
+    /// pieces shuffling back and forth, a cheap check for repetition which doesn't compare states.
+    fn repeats_cycle(&self, cycle_length: usize) -> bool {
+        let history = self.history();
+        if cycle_length == 0 || history.len() < 2 * cycle_length {
+            return false;
+        }
+
+        let cycle_start = history.len() - cycle_length;
+        history[cycle_start..] == history[cycle_start - cycle_length..cycle_start]
+    }
+}
+
+/// A state which can apply an action to itself and take it back again, for games whose states are
+/// expensive to clone. Implementors should override `State::apply_action_mut` to call
+/// `apply_in_place`, which is how the rollouts pick it up.
+///
+/// Expansion still clones the state once per child, as every node of a `GameTree` keeps its own
+/// state; `GameTree::with_expansion_cap(1)` limits those clones to the children a search actually
+/// visits.
+pub trait MutableState<A: Action, P: Player>: State<A, P> {
+    /// Whatever `undo` needs to take an action back, e.g. the piece it captured.
+    type Undo;
+
+    fn apply_in_place<R: Rng>(&mut self, rng: &mut R, action: &A) -> Result<Self::Undo, Self::Error>;
+    fn undo(&mut self, undo: Self::Undo);
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Outcome<P: Player> {
     Winner(P),
+    /// A win by a margin, e.g. the difference in points, which a tree built with
+    /// `GameTree::with_margin_scaling` rewards more the larger it is. Otherwise it counts like a
+    /// `Winner`.
+    WinnerBy(P, ScoreValue),
     Draw(Vec<P>),
     Escape(String),
+    /// Each player's reward, for games which end with points rather than a single winner. Rewards
+    /// are credited as they are, so they're best kept between 0 and 1 like a win; players without
+    /// an entry get nothing.
+    Scores(HashMap<P, ScoreValue>),
+    /// The player resigned, so their team loses and every other player wins. The game runners
+    /// record it for agents resigning under a `game::Resignation`; states shouldn't return it, as
+    /// the search can't tell who the other players are to credit them.
+    Resignation(P),
 }
 
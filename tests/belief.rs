@@ -0,0 +1,131 @@
+mod common;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use bg_ai::{Action, Outcome, State};
+use bg_ai::belief::BeliefState;
+use bg_ai::information_set::{Observable, ObservableState};
+use bg_ai::ismcts::Determinable;
+use common::NimPlayer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Move {
+    /// the card's holder says whether it's odd
+    Signal(u8),
+    Guess(u8),
+}
+
+impl Action for Move {}
+
+/// The second player holds one of four cards, hidden from the first, and signals whether it's
+/// odd. The first player then guesses it, which reveals the card.
+#[derive(Debug, Clone, PartialEq)]
+struct Card {
+    card: u8,
+    signal: Option<u8>,
+    guess: Option<u8>,
+}
+
+impl Card {
+    fn new(card: u8) -> Self {
+        Self { card, signal: None, guess: None }
+    }
+}
+
+impl State<Move, NimPlayer> for Card {
+    type Error = String;
+
+    fn actions(&self) -> Vec<Move> {
+        match (self.signal, self.guess) {
+            (None, _) => vec![Move::Signal(self.card % 2)],
+            (Some(_), None) => (0..4).map(Move::Guess).collect(),
+            (Some(_), Some(_)) => Vec::new(),
+        }
+    }
+
+    fn apply_action<R: Rng>(&self, _rng: &mut R, action: &Move) -> Result<Self, Self::Error> {
+        if !self.actions().contains(action) {
+            return Err(format!("{action:?} isn't legal"));
+        }
+
+        Ok(match *action {
+            Move::Signal(parity) => Self { signal: Some(parity), ..self.clone() },
+            Move::Guess(card) => Self { guess: Some(card), ..self.clone() },
+        })
+    }
+
+    fn outcome(&self) -> Option<Outcome<NimPlayer>> {
+        let guess = self.guess?;
+        Some(Outcome::Winner(NimPlayer(if guess == self.card { 0 } else { 1 })))
+    }
+
+    fn current_player(&self) -> NimPlayer {
+        NimPlayer(if self.signal.is_none() { 1 } else { 0 })
+    }
+
+    fn players(&self) -> Vec<NimPlayer> {
+        vec![NimPlayer(0), NimPlayer(1)]
+    }
+}
+
+impl Determinable<Card, Move, NimPlayer> for Card {
+    fn determine<R: Rng>(&self, rng: &mut R, perspective_player: NimPlayer) -> Card {
+        match perspective_player {
+            // the card is no secret to its holder, nor once it's been guessed
+            NimPlayer(1) => self.clone(),
+            _ if self.guess.is_some() => self.clone(),
+            _ => Self { card: rng.gen_range(0..4), ..self.clone() },
+        }
+    }
+}
+
+impl Observable<Move, NimPlayer> for Card {
+    fn observe(&self, action: &Move, _observer: NimPlayer) -> Move {
+        *action
+    }
+}
+
+impl ObservableState<Move, NimPlayer> for Card {
+    type Observation = (Option<u8>, Option<u8>, Option<u8>);
+
+    fn observation(&self, player: NimPlayer) -> Self::Observation {
+        // the card is known to its holder, and to everyone once it's been guessed
+        let card = (player == NimPlayer(1) || self.guess.is_some()).then_some(self.card);
+        (card, self.signal, self.guess)
+    }
+}
+
+fn cards(belief: &BeliefState<Card>) -> Vec<u8> {
+    belief.particles().iter().map(|particle| particle.state.card).collect()
+}
+
+#[test]
+fn particles_contradicting_an_observed_action_are_discarded() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut belief = BeliefState::sample(&mut rng, &Card::new(3), NimPlayer(0), 40);
+    assert!(cards(&belief).contains(&0));
+
+    belief.observe(&mut rng, NimPlayer(0), &Move::Signal(1));
+    assert!(!belief.is_empty());
+    assert!(belief.num_particles() == 40 && belief.particles().len() < 40);
+    assert!(cards(&belief).iter().all(|card| card % 2 == 1));
+    assert!(belief.particles().iter().all(|particle| particle.state.signal == Some(1)));
+}
+
+#[test]
+fn particles_contradicting_the_revealed_state_are_discarded() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let signalled = Card::new(3).apply_action(&mut rng, &Move::Signal(1)).unwrap();
+    let mut belief = BeliefState::sample(&mut rng, &signalled, NimPlayer(0), 40);
+    let guessed = signalled.apply_action(&mut rng, &Move::Guess(1)).unwrap();
+
+    belief.observe(&mut rng, NimPlayer(0), &Move::Guess(1));
+    assert_eq!(belief.particles().len(), 40);
+    belief.discard_unobserved(&guessed, NimPlayer(0));
+    assert!(belief.particles().len() < 40);
+    assert!(cards(&belief).iter().all(|&card| card == 3));
+
+    belief.replenish(&mut rng, &guessed, NimPlayer(0));
+    assert_eq!(belief.particles().len(), 40);
+    assert!(cards(&belief).iter().all(|&card| card == 3));
+}
@@ -0,0 +1,148 @@
+//! A toy game of Nim for the tests: the players take turns taking one to three stones off a heap,
+//! and whoever takes the last stone wins. Leaving the opponent a multiple of four wins, so the
+//! best move is known for every heap.
+
+// each test crate only uses some of the helpers
+#![allow(dead_code)]
+
+use rand::Rng;
+use bg_ai::{Action, Describe, History, Outcome, Player, ScoreValue, State};
+use bg_ai::information_set::{Observable, ObservableState};
+use bg_ai::ismcts::Determinable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Take(pub u32);
+
+impl Action for Take {}
+
+impl Describe for Take {
+    fn describe(&self) -> String {
+        format!("take {}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NimPlayer(pub u8);
+
+impl Player for NimPlayer {}
+
+impl Describe for NimPlayer {
+    fn describe(&self) -> String {
+        format!("player {}", self.0 + 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nim {
+    pub heap: u32,
+    turn: u8,
+    /// whether a coin is flipped after every move, a chance event which doesn't change the game
+    coin_flips: bool,
+    flip_pending: bool,
+    heads: bool,
+    played: Vec<Take>,
+}
+
+impl Nim {
+    pub fn new(heap: u32) -> Self {
+        Self {
+            heap,
+            turn: 0,
+            coin_flips: false,
+            flip_pending: false,
+            heads: false,
+            played: Vec::new(),
+        }
+    }
+
+    pub fn with_coin_flips(heap: u32) -> Self {
+        Self {
+            coin_flips: true,
+            ..Self::new(heap)
+        }
+    }
+
+    /// the best number of stones to take, if the heap isn't already lost
+    pub fn winning_take(&self) -> Option<Take> {
+        match self.heap % 4 {
+            0 => None,
+            stones => Some(Take(stones)),
+        }
+    }
+}
+
+impl State<Take, NimPlayer> for Nim {
+    type Error = String;
+
+    fn actions(&self) -> Vec<Take> {
+        (1..=self.heap.min(3)).map(Take).collect()
+    }
+
+    fn apply_action<R: Rng>(&self, _rng: &mut R, action: &Take) -> Result<Self, Self::Error> {
+        if action.0 == 0 || action.0 > self.heap.min(3) {
+            return Err(format!("can't take {} stones off a heap of {}", action.0, self.heap));
+        }
+
+        let heap = self.heap - action.0;
+        let mut played = self.played.clone();
+        played.push(*action);
+        Ok(Self {
+            heap,
+            turn: 1 - self.turn,
+            flip_pending: self.coin_flips && heap > 0,
+            played,
+            ..self.clone()
+        })
+    }
+
+    fn outcome(&self) -> Option<Outcome<NimPlayer>> {
+        // the player who took the last stone is no longer the one to move
+        (self.heap == 0).then(|| Outcome::Winner(NimPlayer(1 - self.turn)))
+    }
+
+    fn chance_outcomes(&self) -> Vec<(Self, ScoreValue)> {
+        if !self.flip_pending {
+            return Vec::new();
+        }
+
+        [true, false]
+            .into_iter()
+            .map(|heads| (Self { flip_pending: false, heads, ..self.clone() }, 0.5))
+            .collect()
+    }
+
+    fn current_player(&self) -> NimPlayer {
+        NimPlayer(self.turn)
+    }
+
+    fn players(&self) -> Vec<NimPlayer> {
+        vec![NimPlayer(0), NimPlayer(1)]
+    }
+}
+
+impl History<Take, NimPlayer> for Nim {
+    fn history(&self) -> &[Take] {
+        &self.played
+    }
+}
+
+/// Nim has no hidden information, so every determinization is the state itself.
+impl Determinable<Nim, Take, NimPlayer> for Nim {
+    fn determine<R: Rng>(&self, _rng: &mut R, _perspective_player: NimPlayer) -> Nim {
+        self.clone()
+    }
+}
+
+impl Observable<Take, NimPlayer> for Nim {
+    fn observe(&self, action: &Take, _observer: NimPlayer) -> Take {
+        *action
+    }
+}
+
+impl ObservableState<Take, NimPlayer> for Nim {
+    type Observation = (u32, u8, bool);
+
+    fn observation(&self, _player: NimPlayer) -> Self::Observation {
+        (self.heap, self.turn, self.heads)
+    }
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use std::collections::HashMap;
+use std::time::Duration;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use bg_ai::{Outcome, State};
+use bg_ai::agents::{Agent, RandomAgent};
+use bg_ai::game::{Game, GameError, Resignation, TimeControl, Timeout};
+use bg_ai::mcts;
+use bg_ai::replay::{ReplayError, Replayer};
+use bg_ai::selfplay::{run_batch_with_samples, SelfPlayConfig};
+use common::{Nim, NimPlayer, Take};
+
+fn mcts_agent(player: NimPlayer) -> Box<dyn Agent<StdRng, Nim, Take, NimPlayer>> {
+    Box::new(mcts::Agent::new(player, 1000).unwrap())
+}
+
+fn mcts_agents() -> HashMap<NimPlayer, Box<dyn Agent<StdRng, Nim, Take, NimPlayer>>> {
+    [NimPlayer(0), NimPlayer(1)]
+        .into_iter()
+        .map(|player| (player, mcts_agent(player)))
+        .collect()
+}
+
+#[test]
+fn a_lost_game_is_resigned() {
+    // a heap of 8 is lost for whoever moves first
+    let mut game = Game::new(StdRng::seed_from_u64(1), Nim::new(8), mcts_agents())
+        .with_resignation(Resignation::new(0.4, 1));
+
+    assert!(matches!(game.run(), Ok(Outcome::Resignation(NimPlayer(0)))));
+    assert!(game.history().is_empty());
+}
+
+#[test]
+fn a_resignation_is_a_win_for_the_other_team() {
+    let config = SelfPlayConfig::new(Nim::new(8), 2)
+        .with_agent(NimPlayer(0), mcts_agent)
+        .with_agent(NimPlayer(1), mcts_agent)
+        .with_resignation(Resignation::new(0.4, 1));
+
+    for (game, samples) in run_batch_with_samples(&config, 2, 2) {
+        assert!(matches!(game.result, Ok(Outcome::Resignation(NimPlayer(0)))));
+        assert!(!samples.is_empty());
+        for sample in samples {
+            let expected = match sample.player {
+                NimPlayer(0) => 0.0,
+                _ => 1.0,
+            };
+            assert_eq!(sample.value, expected);
+        }
+    }
+}
+
+/// takes the first legal action, after thinking for longer than any clock in these tests allows
+struct SlowAgent(NimPlayer);
+
+impl Agent<StdRng, Nim, Take, NimPlayer> for SlowAgent {
+    fn player(&self) -> NimPlayer {
+        self.0
+    }
+
+    fn decide(&self, _rng: &mut StdRng, state: &Nim) -> Option<Take> {
+        std::thread::sleep(Duration::from_millis(20));
+        state.actions().into_iter().next()
+    }
+}
+
+#[test]
+fn running_out_of_time_forfeits() {
+    let agents: HashMap<_, Box<dyn Agent<StdRng, Nim, Take, NimPlayer>>> = HashMap::from([
+        (NimPlayer(0), mcts_agent(NimPlayer(0))),
+        (NimPlayer(1), Box::new(SlowAgent(NimPlayer(1))) as Box<_>),
+    ]);
+    let mut game = Game::new(StdRng::seed_from_u64(3), Nim::new(10), agents)
+        .with_time_control(NimPlayer(1), TimeControl::new(Duration::from_millis(5)));
+
+    assert!(matches!(game.run(), Err(GameError::TimeForfeit(NimPlayer(1)))));
+    assert_eq!(game.history().len(), 1);
+    assert_eq!(game.time_control(&NimPlayer(1)).unwrap().remaining, Duration::ZERO);
+}
+
+#[test]
+fn the_best_so_far_is_played_on_timeout() {
+    let agents: HashMap<_, Box<dyn Agent<StdRng, Nim, Take, NimPlayer>>> = HashMap::from([
+        (NimPlayer(0), Box::new(SlowAgent(NimPlayer(0))) as Box<_>),
+        (NimPlayer(1), Box::new(SlowAgent(NimPlayer(1))) as Box<_>),
+    ]);
+    let time_control = TimeControl::new(Duration::from_millis(5)).with_on_timeout(Timeout::BestSoFar);
+    let mut game = Game::new(StdRng::seed_from_u64(4), Nim::new(4), agents)
+        .with_time_control(NimPlayer(0), time_control.clone())
+        .with_time_control(NimPlayer(1), time_control);
+
+    assert!(matches!(game.run(), Ok(Outcome::Winner(_))));
+    assert_eq!(game.history().len(), 4);
+}
+
+#[test]
+fn a_recorded_game_replays_to_the_same_state() {
+    let agents: HashMap<_, Box<dyn Agent<StdRng, Nim, Take, NimPlayer>>> = HashMap::from([
+        (NimPlayer(0), Box::new(RandomAgent::new(NimPlayer(0))) as Box<_>),
+        (NimPlayer(1), Box::new(RandomAgent::new(NimPlayer(1))) as Box<_>),
+    ]);
+    let mut game = Game::new(StdRng::seed_from_u64(5), Nim::new(15), agents).with_recording(7, "nim 15");
+    let outcome = game.run().unwrap();
+
+    let replay = game.replay();
+    assert_eq!((replay.seed, replay.state_id.as_deref()), (Some(7), Some("nim 15")));
+    assert_eq!(replay.actions, game.history());
+
+    let mut replayer = Replayer::new(replay.clone(), Nim::new(15));
+    let state = replayer.run().unwrap();
+    assert!(matches!((state.outcome(), outcome), (Some(Outcome::Winner(a)), Outcome::Winner(b)) if a == b));
+    assert_eq!(state.heap, 0);
+    assert!(replayer.is_finished());
+
+    // the same actions can't be played out from another heap
+    let mut replayer = Replayer::new(replay, Nim::new(2));
+    assert!(matches!(replayer.run(), Err(ReplayError::Diverged(_))));
+}
+
+#[test]
+fn chance_events_are_resolved_before_the_next_decision() {
+    let mut game = Game::new(StdRng::seed_from_u64(6), Nim::with_coin_flips(9), mcts_agents()).with_recording(11, "nim 9");
+    while !game.is_terminated() {
+        game.step().unwrap();
+        assert!(game.state.chance_outcomes().is_empty());
+    }
+
+    // the first player can always leave a multiple of four
+    assert!(matches!(game.outcome(), Some(Outcome::Winner(NimPlayer(0)))));
+
+    // the replay flips the same coins
+    let mut replayer = Replayer::new(game.replay(), Nim::with_coin_flips(9));
+    assert_eq!(*replayer.run().unwrap(), game.state);
+}
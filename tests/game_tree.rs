@@ -0,0 +1,177 @@
+mod common;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use bg_ai::{ArenaGameTree, GameTree, Outcome, SearchError, State};
+use bg_ai::mcts::mcts;
+use common::{Nim, NimPlayer, Take};
+
+fn searched(heap: u32, seed: u64, num_simulations: u32) -> GameTree<Nim, Take, NimPlayer> {
+    let mut tree = GameTree::new(Nim::new(heap));
+    tree.search_n(&mut StdRng::seed_from_u64(seed), num_simulations).unwrap();
+    tree
+}
+
+/// each root action's visits, in the order the children were added
+fn root_visits<B>(tree: &GameTree<Nim, Take, NimPlayer, B>) -> Vec<(Take, u32)>
+    where
+        B: bg_ai::TreeBackend<bg_ai::GameTreeNode<Nim, Take, NimPlayer>, bg_ai::GameTreeEdge<Take, NimPlayer>>,
+{
+    tree.root_scores()
+        .into_iter()
+        .filter(|score| score.player == NimPlayer(0))
+        .map(|score| (score.action, score.num_visits))
+        .collect()
+}
+
+#[test]
+fn finds_the_winning_move() {
+    for heap in [5, 6, 7] {
+        let tree = searched(heap, 1, 3000);
+        assert_eq!(tree.best_action().copied(), Nim::new(heap).winning_take(), "heap of {heap}");
+    }
+}
+
+#[test]
+fn back_propagates_every_simulation_through_the_root() {
+    let tree = searched(10, 2, 500);
+    let total_visits: u32 = tree.ranked_children(&[]).iter().map(|(_, _, visits)| visits).sum();
+    assert_eq!(total_visits, 500);
+
+    // the winning move leaves the opponent lost, which its score for the mover should reflect
+    let scores = tree.root_scores();
+    let value = |take: Take| {
+        let score = scores.iter().find(|score| score.action == take && score.player == NimPlayer(0)).unwrap();
+        score.score / score.num_visits as bg_ai::ScoreValue
+    };
+    assert!(value(Take(2)) > value(Take(1)));
+    assert!(value(Take(2)) > value(Take(3)));
+}
+
+#[test]
+fn selection_prefers_the_winning_move() {
+    let tree = searched(6, 3, 2000);
+    let ranked = tree.ranked_children(&[]);
+    assert_eq!(*ranked[0].0, Take(2));
+    assert!(ranked[0].2 > ranked[1].2 * 2);
+}
+
+#[test]
+fn backends_build_identical_trees() {
+    let graph_tree = searched(9, 4, 800);
+    let mut arena_tree = ArenaGameTree::with_backend(Nim::new(9));
+    arena_tree.search_n(&mut StdRng::seed_from_u64(4), 800).unwrap();
+
+    assert_eq!(root_visits(&graph_tree), root_visits(&arena_tree));
+    assert_eq!(graph_tree.node_count(), arena_tree.node_count());
+
+    let path = [Take(1), Take(3)];
+    let deep_visits = |ranked: Vec<(&Take, &Nim, u32)>| ranked.into_iter().map(|(take, _, visits)| (*take, visits)).collect::<Vec<_>>();
+    assert_eq!(deep_visits(graph_tree.ranked_children(&path)), deep_visits(arena_tree.ranked_children(&path)));
+}
+
+#[test]
+fn children_keep_their_order() {
+    let mut tree = searched(9, 5, 800);
+    let order = |tree: &GameTree<Nim, Take, NimPlayer>| root_visits(tree).into_iter().map(|(take, _)| take).collect::<Vec<_>>();
+    assert_eq!(order(&tree), [Take(1), Take(2), Take(3)]);
+
+    assert!(tree.advance_root(&Take(1)));
+    assert!(tree.advance_root(&Take(1)));
+    assert_eq!(order(&tree), [Take(1), Take(2), Take(3)]);
+}
+
+#[test]
+fn advance_root_keeps_the_subtree() {
+    let mut tree = searched(8, 6, 1000);
+    let node_count = tree.node_count();
+    let child_visits = tree.ranked_children(&[]).iter().find(|(take, _, _)| **take == Take(3)).unwrap().2;
+    let grandchild_visits: Vec<(Take, u32)> = tree
+        .ranked_children(&[Take(3)])
+        .into_iter()
+        .map(|(take, _, visits)| (*take, visits))
+        .collect();
+
+    assert!(tree.advance_root(&Take(3)));
+    assert_eq!(tree.root_state().heap, 5);
+    assert!(tree.node_count() < node_count);
+
+    let ranked: Vec<(Take, u32)> = tree.ranked_children(&[]).into_iter().map(|(take, _, visits)| (*take, visits)).collect();
+    assert_eq!(ranked, grandchild_visits);
+    assert!(ranked.iter().map(|(_, visits)| visits).sum::<u32>() <= child_visits);
+}
+
+#[test]
+fn advance_root_ignores_unknown_actions() {
+    let mut tree = searched(8, 7, 100);
+    let node_count = tree.node_count();
+
+    assert!(!tree.advance_root(&Take(4)));
+    assert_eq!(tree.node_count(), node_count);
+    assert_eq!(tree.root_state().heap, 8);
+}
+
+#[test]
+fn merge_adds_up_the_statistics() {
+    let mut tree = searched(9, 8, 300);
+    let other = searched(9, 9, 200);
+    let expected: Vec<(Take, u32)> = root_visits(&tree)
+        .into_iter()
+        .map(|(take, visits)| {
+            let other_visits = root_visits(&other).into_iter().find(|(other_take, _)| *other_take == take).map_or(0, |(_, visits)| visits);
+            (take, visits + other_visits)
+        })
+        .collect();
+
+    tree.merge(&other);
+    assert_eq!(root_visits(&tree), expected);
+    assert_eq!(expected.iter().map(|(_, visits)| visits).sum::<u32>(), 500);
+}
+
+#[test]
+fn searches_through_chance_nodes() {
+    let mut tree = GameTree::new(Nim::with_coin_flips(7));
+    tree.search_n(&mut StdRng::seed_from_u64(10), 3000).unwrap();
+    assert_eq!(tree.best_action(), Some(&Take(3)));
+}
+
+#[test]
+fn a_chance_root_has_no_decision() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let flipping = bg_ai::State::apply_action(&Nim::with_coin_flips(7), &mut rng, &Take(1)).unwrap();
+    let mut tree = GameTree::new(flipping);
+    assert!(matches!(tree.search(&mut rng), Err(SearchError::ChanceRoot)));
+}
+
+/// Nim whose rules fail to take two stones
+#[derive(Clone)]
+struct Misbehaving(Nim);
+
+impl State<Take, NimPlayer> for Misbehaving {
+    type Error = String;
+
+    fn actions(&self) -> Vec<Take> {
+        self.0.actions()
+    }
+
+    fn apply_action<R: Rng>(&self, rng: &mut R, action: &Take) -> Result<Self, Self::Error> {
+        match action {
+            Take(2) => Err("can't take two".to_string()),
+            _ => self.0.apply_action(rng, action).map(Misbehaving),
+        }
+    }
+
+    fn outcome(&self) -> Option<Outcome<NimPlayer>> {
+        self.0.outcome()
+    }
+
+    fn current_player(&self) -> NimPlayer {
+        self.0.current_player()
+    }
+}
+
+#[test]
+fn a_failing_action_is_reported_along_with_its_node() {
+    let error = mcts(&Misbehaving(Nim::new(5)), &mut StdRng::seed_from_u64(7), 100).unwrap_err();
+    assert!(matches!(error, SearchError::State { action: Some(Take(2)), node: Some(_), .. }));
+}
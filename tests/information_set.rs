@@ -0,0 +1,23 @@
+mod common;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use bg_ai::SearchError;
+use bg_ai::information_set::{mo_ismcts, so_ismcts};
+use common::{Nim, NimPlayer, Take};
+
+#[test]
+fn single_observer_ismcts_finds_the_winning_move() {
+    assert_eq!(so_ismcts(&Nim::new(7), &StdRng::seed_from_u64(4), 3000).unwrap(), Take(3));
+}
+
+#[test]
+fn multiple_observer_ismcts_finds_the_winning_move() {
+    let players = [NimPlayer(0), NimPlayer(1)];
+    assert_eq!(mo_ismcts(&Nim::new(6), &StdRng::seed_from_u64(5), &players, 3000).unwrap(), Take(2));
+}
+
+#[test]
+fn multiple_observer_ismcts_needs_every_mover() {
+    assert!(matches!(mo_ismcts(&Nim::new(6), &StdRng::seed_from_u64(6), &[NimPlayer(0)], 100), Err(SearchError::NoObserverTree)));
+}
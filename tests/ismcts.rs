@@ -0,0 +1,112 @@
+mod common;
+
+use std::collections::HashMap;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use bg_ai::{mcts, Outcome, SearchError, State};
+use bg_ai::agents::RandomAgent;
+use bg_ai::ismcts::{ismcts, ismcts_mt, ismcts_with_config, Determinable, IsMctsMtAgent, IsmctsConfig, MtAgent, MultithreadedInformationSetGame, MultithreadedInformationSetGameError, SimulationAllocation};
+use common::{Nim, NimPlayer, Take};
+
+#[test]
+fn ismcts_finds_the_winning_move() {
+    let rng = StdRng::seed_from_u64(1);
+    assert_eq!(ismcts(&Nim::new(6), &rng, 4, 1000).unwrap(), Take(2));
+    assert_eq!(ismcts_mt(&Nim::new(7), &rng, 4, 1000).unwrap(), Take(3));
+}
+
+#[test]
+fn ismcts_is_reproducible() {
+    let rng = StdRng::seed_from_u64(2);
+    let config = IsmctsConfig::new(3, 200);
+    let first = ismcts_with_config(&Nim::new(10), &rng, &config).unwrap();
+    let second = ismcts_with_config(&Nim::new(10), &rng, &config).unwrap();
+    assert_eq!(first.visit_distribution(), second.visit_distribution());
+}
+
+#[test]
+fn successive_halving_finds_the_winning_move() {
+    let config = IsmctsConfig::new(8, 1000).with_simulation_allocation(SimulationAllocation::SuccessiveHalving);
+    let result = ismcts_with_config(&Nim::new(5), &StdRng::seed_from_u64(3), &config).unwrap();
+    assert_eq!(result.best_action, Some(Take(1)));
+}
+
+#[test]
+fn any_agent_plays_as_an_mt_agent() {
+    let agents: Vec<Box<dyn IsMctsMtAgent<StdRng, Nim, Take, NimPlayer>>> = vec![
+        Box::new(RandomAgent::new(NimPlayer(0))),
+        Box::new(mcts::Agent::new(NimPlayer(0), 1000).unwrap()),
+    ];
+
+    let mut rng = StdRng::seed_from_u64(10);
+    assert!(agents[0].decide(&mut rng, &Nim::new(2)).is_some_and(|take| take.0 <= 2));
+    assert_eq!(agents[1].decide(&mut rng, &Nim::new(6)), Some(Take(2)));
+    assert_eq!(agents[1].player(), NimPlayer(0));
+}
+
+#[test]
+fn reusing_trees_plays_the_game_out() {
+    let agents: HashMap<NimPlayer, MtAgent<NimPlayer>> = [NimPlayer(0), NimPlayer(1)]
+        .into_iter()
+        .map(|player| (player, MtAgent::new(player, 2, 1000).unwrap()))
+        .collect();
+
+    let mut game = MultithreadedInformationSetGame::new(StdRng::seed_from_u64(7), Nim::new(9), agents).with_tree_reuse();
+    game.run().unwrap();
+
+    // the first player can always leave a multiple of four
+    assert!(matches!(game.outcome(), Some(Outcome::Winner(NimPlayer(0)))));
+}
+
+#[test]
+fn coin_flips_are_resolved_between_turns() {
+    let agents: HashMap<NimPlayer, MtAgent<NimPlayer>> = [NimPlayer(0), NimPlayer(1)]
+        .into_iter()
+        .map(|player| (player, MtAgent::new(player, 2, 1000).unwrap()))
+        .collect();
+
+    let mut game = MultithreadedInformationSetGame::new(StdRng::seed_from_u64(9), Nim::with_coin_flips(9), agents).with_tree_reuse();
+    game.run().unwrap();
+
+    assert!(matches!(game.outcome(), Some(Outcome::Winner(NimPlayer(0)))));
+    assert_eq!(game.history().len() % 2, 1);
+}
+
+/// Nim whose rules fail to take any stones
+#[derive(Clone)]
+struct Broken(Nim);
+
+impl State<Take, NimPlayer> for Broken {
+    type Error = String;
+
+    fn actions(&self) -> Vec<Take> {
+        self.0.actions()
+    }
+
+    fn apply_action<R: Rng>(&self, _rng: &mut R, action: &Take) -> Result<Self, Self::Error> {
+        Err(format!("can't take {}", action.0))
+    }
+
+    fn outcome(&self) -> Option<Outcome<NimPlayer>> {
+        self.0.outcome()
+    }
+
+    fn current_player(&self) -> NimPlayer {
+        self.0.current_player()
+    }
+}
+
+impl Determinable<Broken, Take, NimPlayer> for Broken {
+    fn determine<R: Rng>(&self, _rng: &mut R, _perspective_player: NimPlayer) -> Broken {
+        self.clone()
+    }
+}
+
+#[test]
+fn an_agent_fails_when_no_determinization_can_be_searched() {
+    let agents = HashMap::from([(NimPlayer(0), MtAgent::new(NimPlayer(0), 2, 100).unwrap())]);
+    let mut game = MultithreadedInformationSetGame::new(StdRng::seed_from_u64(8), Broken(Nim::new(5)), agents);
+
+    let error = game.step().unwrap_err();
+    assert!(matches!(error, MultithreadedInformationSetGameError::AgentSearchError(NimPlayer(0), SearchError::State { action: Some(_), .. })));
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use bg_ai::{Action, Outcome, State};
+use bg_ai::policy::{PassThroughAgent, Policy, ScriptedAgent};
+use common::NimPlayer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Move {
+    Pass,
+}
+
+impl Action for Move {}
+
+/// A game in which neither player ever has a move, so each can only pass, until both have.
+#[derive(Debug, Clone, PartialEq)]
+struct Stuck {
+    num_passes: u8,
+}
+
+impl State<Move, NimPlayer> for Stuck {
+    type Error = String;
+
+    fn actions(&self) -> Vec<Move> {
+        Vec::new()
+    }
+
+    fn pass_action(&self) -> Option<Move> {
+        Some(Move::Pass)
+    }
+
+    fn apply_action<R: Rng>(&self, _rng: &mut R, _action: &Move) -> Result<Self, Self::Error> {
+        Ok(Self { num_passes: self.num_passes + 1 })
+    }
+
+    fn outcome(&self) -> Option<Outcome<NimPlayer>> {
+        (self.num_passes >= 2).then(|| Outcome::Draw(vec![NimPlayer(0), NimPlayer(1)]))
+    }
+
+    fn current_player(&self) -> NimPlayer {
+        NimPlayer(self.num_passes % 2)
+    }
+}
+
+#[test]
+fn a_pass_through_agent_passes_when_that_is_all_it_can_do() {
+    let mut policy = PassThroughAgent::new();
+    assert_eq!(policy.decide(&mut StdRng::seed_from_u64(1), &Stuck { num_passes: 0 }), Some(Move::Pass));
+}
+
+#[test]
+fn a_scripted_pass_is_played_rather_than_reported_as_a_divergence() {
+    let mut policy = ScriptedAgent::<StdRng, Stuck, Move, NimPlayer>::new(vec![Move::Pass, Move::Pass]);
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut state = Stuck { num_passes: 0 };
+    while state.outcome().is_none() {
+        let action = policy.decide(&mut rng, &state).unwrap();
+        state = state.apply_action(&mut rng, &action).unwrap();
+    }
+
+    assert!(!policy.has_diverged());
+    assert!(policy.is_exhausted());
+}
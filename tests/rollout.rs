@@ -0,0 +1,158 @@
+mod common;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use bg_ai::{DepthLimitedRollout, LastGoodReplyRollout, MastRollout, NstRollout, Outcome, RolloutPolicy, ScoreValue, SearchError, Seat, State};
+use common::{Nim, NimPlayer, Take};
+
+/// whether every rollout from `state` is won by `winner`
+fn always_won_by<T: RolloutPolicy<Nim, Take, NimPlayer>>(policy: &T, state: &Nim, winner: NimPlayer) -> bool {
+    (0..20).all(|seed| {
+        let outcome = policy.rollout(state, &mut StdRng::seed_from_u64(seed)).unwrap();
+        matches!(outcome, Outcome::Winner(player) if player == winner)
+    })
+}
+
+#[test]
+fn mast_plays_the_actions_which_won_before() {
+    let mast = MastRollout::new(0.01);
+    let statistics = mast.statistics();
+    for _ in 0..10 {
+        statistics.record([Take(1)], &Outcome::Winner(NimPlayer(0)));
+        statistics.record([Take(2), Take(3)], &Outcome::Winner(NimPlayer(1)));
+    }
+
+    // the first player takes one from three, as it's won them every playout, and the second
+    // player then takes the last two
+    assert!(always_won_by(&mast, &Nim::new(3), NimPlayer(1)));
+}
+
+#[test]
+fn mast_learns_from_its_playouts() {
+    let mast = MastRollout::new(1.0);
+    let mut rng = StdRng::seed_from_u64(1);
+    for _ in 0..50 {
+        mast.rollout(&Nim::new(9), &mut rng).unwrap();
+    }
+
+    // every action appeared in some playout, and counted at most once in each
+    for take in 1..=3 {
+        let entry = mast.statistics().get(&Take(take)).unwrap();
+        assert!(entry.num_playouts > 0 && entry.num_playouts <= 50);
+    }
+
+    // the statistics are shared with clones, e.g. by every determinization's tree
+    let shared = MastRollout::<Take, NimPlayer>::new(1.0).with_statistics(mast.statistics().clone());
+    assert_eq!(shared.statistics().len(), 3);
+}
+
+#[test]
+fn nst_plays_the_sequences_which_won_before() {
+    let nst = NstRollout::new(2, 0.01);
+    let statistics = nst.statistics();
+    for _ in 0..100 {
+        statistics.record([vec![Take(1)], vec![Take(2)]], &Outcome::Winner(NimPlayer(0)));
+        statistics.record([vec![Take(2)], vec![Take(3)]], &Outcome::Winner(NimPlayer(1)));
+        statistics.record([vec![Take(1), Take(1)]], &Outcome::Winner(NimPlayer(1)));
+        statistics.record([vec![Take(1), Take(2)]], &Outcome::Winner(NimPlayer(0)));
+    }
+
+    // taking two is the better action alone, but after the first player takes one, taking one
+    // back is the better line, leaving the last stone to the first player
+    assert!(always_won_by(&nst, &Nim::new(3), NimPlayer(0)));
+}
+
+#[test]
+fn nst_learns_sequences_up_to_its_length() {
+    let nst = NstRollout::new(2, 1.0);
+    let mut rng = StdRng::seed_from_u64(2);
+    for _ in 0..50 {
+        nst.rollout(&Nim::new(9), &mut rng).unwrap();
+    }
+
+    assert!(nst.statistics().get(&vec![Take(1)]).is_some());
+    assert!(nst.statistics().get(&vec![Take(1), Take(1)]).is_some());
+    assert!(nst.statistics().get(&vec![Take(1), Take(1), Take(1)]).is_none());
+}
+
+#[test]
+fn last_good_reply_replays_winning_replies_and_forgets_losing_ones() {
+    let lgr = LastGoodReplyRollout::new();
+    lgr.replies().insert((NimPlayer(1), Take(1)), Take(3));
+    let state = Nim::new(5).apply_action(&mut StdRng::seed_from_u64(0), &Take(1)).unwrap();
+
+    // taking three off four, in reply to the first player taking one, leaves them the last stone
+    let outcome = lgr.rollout(&state, &mut StdRng::seed_from_u64(1)).unwrap();
+    assert!(matches!(outcome, Outcome::Winner(NimPlayer(0))));
+
+    assert_eq!(lgr.replies().get(&(NimPlayer(1), Take(1))), None);
+    assert_eq!(lgr.replies().get(&(NimPlayer(0), Take(3))), Some(Take(1)));
+}
+
+#[test]
+fn depth_limited_rollouts_are_valued_by_the_evaluation_once_cut_off() {
+    let favour_the_second_player = |_: &Nim| vec![(NimPlayer(0), 0.0), (NimPlayer(1), 1.0)];
+
+    // nine stones can't be taken within two plies, however they're taken
+    let cut_off = DepthLimitedRollout::new(2, favour_the_second_player);
+    assert!(always_won_by(&cut_off, &Nim::new(9), NimPlayer(1)));
+
+    // a game ending within the plies is valued by its real outcome
+    let played_out = DepthLimitedRollout::new(2, favour_the_second_player);
+    assert!(always_won_by(&played_out, &Nim::new(1), NimPlayer(0)));
+}
+
+/// Nature flips a coin weighted entirely to heads, which wins the game for the first player, or
+/// leaves nobody with anything to do when `stuck`.
+#[derive(Clone)]
+struct WeightedCoin {
+    flipped: Option<Take>,
+    stuck: bool,
+}
+
+impl State<Take, Seat<NimPlayer>> for WeightedCoin {
+    type Error = String;
+
+    fn actions(&self) -> Vec<Take> {
+        match (self.flipped, self.stuck) {
+            (None, false) => vec![Take(1), Take(2)],
+            _ => Vec::new(),
+        }
+    }
+
+    fn apply_action<R: Rng>(&self, _rng: &mut R, action: &Take) -> Result<Self, Self::Error> {
+        Ok(Self { flipped: Some(*action), ..self.clone() })
+    }
+
+    fn outcome(&self) -> Option<Outcome<Seat<NimPlayer>>> {
+        match self.flipped? {
+            Take(1) => Some(Outcome::Winner(Seat::Player(NimPlayer(0)))),
+            _ => Some(Outcome::Winner(Seat::Player(NimPlayer(1)))),
+        }
+    }
+
+    fn action_priors(&self, _actions: &[Take]) -> Vec<ScoreValue> {
+        vec![1.0, 0.0]
+    }
+
+    fn current_player(&self) -> Seat<NimPlayer> {
+        Seat::Nature
+    }
+}
+
+#[test]
+fn depth_limited_rollouts_sample_nature_by_the_odds() {
+    let rollout = DepthLimitedRollout::new(5, |_: &WeightedCoin| Vec::new());
+    for seed in 0..20 {
+        let outcome = rollout.rollout(&WeightedCoin { flipped: None, stuck: false }, &mut StdRng::seed_from_u64(seed)).unwrap();
+        assert!(matches!(outcome, Outcome::Winner(Seat::Player(NimPlayer(0)))));
+    }
+}
+
+#[test]
+fn depth_limited_rollouts_stuck_without_actions_are_an_error_when_strict() {
+    // tests are debug builds, which are strict unless set otherwise
+    let rollout = DepthLimitedRollout::new(5, |_: &WeightedCoin| Vec::new());
+    let outcome = rollout.rollout(&WeightedCoin { flipped: None, stuck: true }, &mut StdRng::seed_from_u64(0));
+    assert!(matches!(outcome, Err(SearchError::NoActions)));
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use rand::rngs::StdRng;
+use bg_ai::{Outcome, ScoreValue};
+use bg_ai::agents::RandomAgent;
+use bg_ai::mcts;
+use bg_ai::selfplay::{run_batch, run_batch_with_samples, SelfPlayConfig};
+use common::{Nim, NimPlayer, Take};
+
+fn random_config(seed: u64) -> SelfPlayConfig<StdRng, Nim, Take, NimPlayer> {
+    SelfPlayConfig::new(Nim::new(12), seed)
+        .with_agent(NimPlayer(0), |player| Box::new(RandomAgent::new(player)))
+        .with_agent(NimPlayer(1), |player| Box::new(RandomAgent::new(player)))
+        .with_records("nim 12")
+}
+
+fn histories(seed: u64, parallelism: usize) -> Vec<Vec<(NimPlayer, Take)>> {
+    run_batch(&random_config(seed), 8, parallelism)
+        .into_iter()
+        .map(|game| game.replay.unwrap().actions)
+        .collect()
+}
+
+#[test]
+fn a_batch_is_reproducible_on_any_number_of_threads() {
+    let games = histories(1, 1);
+    assert_eq!(games.len(), 8);
+    assert_eq!(games, histories(1, 4));
+    assert_ne!(games, histories(2, 4));
+
+    // the games have rngs of their own rather than all playing the same moves
+    assert!(games.iter().any(|actions| *actions != games[0]));
+}
+
+#[test]
+fn samples_are_valued_by_the_games_outcome() {
+    let config: SelfPlayConfig<StdRng, _, _, _> = SelfPlayConfig::new(Nim::new(7), 3)
+        .with_agent(NimPlayer(0), |player| Box::new(mcts::Agent::new(player, 300).unwrap()))
+        .with_agent(NimPlayer(1), |player| Box::new(mcts::Agent::new(player, 300).unwrap()));
+
+    for (game, samples) in run_batch_with_samples(&config, 2, 2) {
+        let Ok(Outcome::Winner(winner)) = game.result else {
+            panic!("the game should have been won");
+        };
+
+        assert!(!samples.is_empty());
+        for sample in samples {
+            assert_eq!(sample.value, if sample.player == winner { 1.0 } else { 0.0 });
+            assert_eq!(sample.observation.1, sample.player.0);
+
+            let total_visits: ScoreValue = sample.visits.iter().map(|(_, share)| share).sum();
+            assert!((total_visits - 1.0).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use std::collections::HashMap;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use bg_ai::ismcts::{MtAgent, MultithreadedInformationSetGame};
+use bg_ai::session::Session;
+use common::{Nim, NimPlayer, Take};
+
+fn session(heap: u32) -> Session<StdRng, Nim, Take, NimPlayer> {
+    let agents = HashMap::from([(NimPlayer(1), MtAgent::new(NimPlayer(1), 2, 200).unwrap())]);
+    let game = MultithreadedInformationSetGame::new(StdRng::seed_from_u64(1), Nim::new(heap), agents);
+    Session::new(game, [NimPlayer(0)])
+}
+
+#[test]
+fn undo_and_redo_restore_the_same_states() {
+    let mut session = session(10);
+    session.play(Take(2)).unwrap();
+    session.run_until_human().unwrap();
+    let after = session.state().clone();
+
+    assert_eq!(session.undo_to_human(), 2);
+    assert_eq!(session.state(), &Nim::new(10));
+    assert_eq!(session.human_to_move(), Some(NimPlayer(0)));
+    assert_eq!(session.history().count(), 0);
+
+    assert_eq!(session.redo(), Some(&Take(2)));
+    assert!(session.redo().is_some());
+    assert_eq!(session.state(), &after);
+    assert_eq!(session.history().count(), 2);
+    assert!(!session.can_redo());
+}
+
+#[test]
+fn a_new_move_replaces_the_undone_ones() {
+    let mut session = session(10);
+    session.play(Take(2)).unwrap();
+    session.undo();
+    assert!(session.can_redo());
+
+    session.play(Take(1)).unwrap();
+    assert!(!session.can_redo());
+    assert_eq!(session.history().copied().collect::<Vec<_>>(), vec![Take(1)]);
+}
+
+#[test]
+fn the_ai_doesnt_move_for_a_human() {
+    let mut session = session(10);
+    assert!(session.step().is_err());
+    session.play(Take(2)).unwrap();
+    assert!(session.play(Take(1)).is_err());
+}
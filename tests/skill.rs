@@ -0,0 +1,33 @@
+mod common;
+
+use std::collections::HashSet;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use bg_ai::agents::Agent;
+use bg_ai::mcts;
+use bg_ai::skill::Skill;
+use common::{Nim, NimPlayer, Take};
+
+fn decisions(skill: Skill, num_decisions: u32) -> HashSet<Take> {
+    let agent = mcts::Agent::new(NimPlayer(0), 1000).unwrap().with_skill(skill);
+    let mut rng = StdRng::seed_from_u64(1);
+    (0..num_decisions)
+        .map(|_| Agent::<StdRng, _, _, _>::decide(&agent, &mut rng, &Nim::new(7)).unwrap())
+        .collect()
+}
+
+#[test]
+fn full_strength_always_plays_the_best_action() {
+    assert_eq!(decisions(Skill::hard(), 10), HashSet::from([Take(3)]));
+}
+
+#[test]
+fn a_beginner_misses_the_best_action() {
+    assert!(decisions(Skill::easy(), 30).len() > 1);
+}
+
+#[test]
+fn a_single_candidate_plays_the_best_action_despite_the_noise() {
+    let skill = Skill::hard().with_value_noise(0.5).with_temperature(1.0);
+    assert_eq!(decisions(skill, 10), HashSet::from([Take(3)]));
+}
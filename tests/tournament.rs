@@ -0,0 +1,46 @@
+mod common;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use bg_ai::agents::RandomAgent;
+use bg_ai::mcts;
+use bg_ai::tournament::RoundRobin;
+use common::{Nim, NimPlayer, Take};
+
+fn round_robin() -> RoundRobin<StdRng, Nim, Take, NimPlayer> {
+    RoundRobin::new(Nim::new(6), [NimPlayer(0), NimPlayer(1)])
+        .with_entrant("mcts", |player| Box::new(mcts::Agent::new(player, 1000).unwrap()))
+        .with_entrant("random", |player| Box::new(RandomAgent::new(player)))
+        .with_games_per_pairing(4)
+}
+
+#[test]
+fn the_stronger_entrant_wins_its_pairing() {
+    let results = round_robin().run(&StdRng::seed_from_u64(1));
+
+    assert_eq!(results.pairings.len(), 1);
+    let pairing = &results.pairings[0];
+    assert_eq!((pairing.first.as_str(), pairing.second.as_str()), ("mcts", "random"));
+    assert_eq!(pairing.wins + pairing.draws + pairing.losses + pairing.unfinished, 4);
+    assert!(pairing.wins >= 3);
+}
+
+#[test]
+fn every_pair_of_entrants_plays() {
+    let results = round_robin()
+        .with_entrant("another random", |player| Box::new(RandomAgent::new(player)))
+        .with_games_per_pairing(2)
+        .run(&StdRng::seed_from_u64(2));
+
+    let pairs: Vec<(&str, &str)> = results.pairings
+        .iter()
+        .map(|pairing| (pairing.first.as_str(), pairing.second.as_str()))
+        .collect();
+    assert_eq!(pairs, vec![("mcts", "random"), ("mcts", "another random"), ("random", "another random")]);
+}
+
+#[test]
+fn a_tournament_is_reproducible() {
+    let rng = StdRng::seed_from_u64(3);
+    assert_eq!(round_robin().run(&rng).pairings, round_robin().run(&rng).pairings);
+}